@@ -4,29 +4,170 @@
 //! All types from [`tokio::fs`] are re-exported here.
 //! Some functions are overwritten and have different error types.
 
-use crate::error::Error;
-use std::path::Path;
+#[cfg(feature = "jsonl")]
+use crate::error::MtJsonlError;
+use crate::error::{Error, Operation};
+use crate::fs::{
+    compression_from_extension, compression_from_magic, copy_metadata, guess_file_type,
+    Compression, FileType, RecompressOptions,
+};
+use futures_core::Stream;
+use log::{debug, warn};
+#[cfg(feature = "jsonl")]
+use serde::de::DeserializeOwned;
+use std::{
+    ffi::OsStr,
+    fmt,
+    future::Future,
+    io,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
 #[doc(inline)]
 pub use tokio::fs::*;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite,
+    AsyncWriteExt, BufReader, BufWriter, Lines,
+};
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+
+/// Open a file for streaming its content asynchronously.
+///
+/// The filetype is detected based on the magic bytes at the start of the file, like in [`crate::fs::file_open_read`].
+/// Compressed files are decompressed while reading, such that the content never needs to be fully loaded into memory.
+/// Decompression happens on the calling task and does not block the executor for long, since the content is processed in small chunks.
+/// Compressed formats are only supported if the corresponding `file-*` feature is enabled.
+pub async fn file_open_read(
+    path: impl AsRef<Path>,
+) -> Result<Box<dyn AsyncRead + Unpin + Send>, Error> {
+    let path = path.as_ref();
+    let mut file = File::open(path).await.map_err(|err| Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::Open,
+        source: err,
+    })?;
+
+    // read magic bytes
+    let mut buffer = [0; 6];
+    if file.read_exact(&mut buffer).await.is_err() {
+        // Files shorter than the magic bytes cannot be compressed
+        buffer = [0; 6];
+    }
+    let detected = compression_from_magic(&buffer);
+    file.seek(SeekFrom::Start(0))
+        .await
+        .map_err(|err| Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::Seek,
+            source: err,
+        })?;
+    let expected = compression_from_extension(path);
+    if expected.is_some() && detected != expected {
+        warn!(
+            "File {} is expected to have type `{}` based on the extension, but is detected to have type `{}`",
+            path.display(),
+            expected.unwrap_or("plaintext"),
+            detected.unwrap_or("plaintext")
+        );
+    }
+
+    debug!(
+        "File {} is detected to have type `{}`",
+        path.display(),
+        detected.unwrap_or("plaintext")
+    );
+    let bufread = BufReader::new(file);
+    decoder(bufread, detected).map_err(|technique| Error::CompressionNotEnabled {
+        file: path.to_path_buf(),
+        technique,
+    })
+}
+
+/// Decompress the content of an arbitrary async source, like a socket or a request body.
+///
+/// The compression is detected based on the magic bytes at the start of `reader`, like in [`file_open_read`].
+/// The magic bytes are inspected without consuming them, but they must be contained in the first chunk returned by [`AsyncBufReadExt::fill_buf`].
+/// Content without known magic bytes is returned unchanged.
+///
+/// Returns an error of kind [`io::ErrorKind::Unsupported`] if the content is compressed, but the corresponding `file-*` feature is not enabled.
+pub async fn decompress_reader<R>(mut reader: R) -> io::Result<Box<dyn AsyncRead + Unpin + Send>>
+where
+    R: 'static + AsyncBufRead + Unpin + Send,
+{
+    let peeked = reader.fill_buf().await?;
+    let mut buffer = [0; 6];
+    let len = peeked.len().min(buffer.len());
+    buffer[..len].copy_from_slice(&peeked[..len]);
+    let detected = compression_from_magic(&buffer);
+    decoder(reader, detected).map_err(|technique| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("The content is detected to be type `{technique}`, but the file-{technique} feature is not enabled."),
+        )
+    })
+}
+
+/// Wrap `reader` into the decoder for the `detected` compression.
+///
+/// Returns the name of the compression technique if its feature is not enabled.
+fn decoder<R>(
+    reader: R,
+    detected: Option<&'static str>,
+) -> Result<Box<dyn AsyncRead + Unpin + Send>, &'static str>
+where
+    R: 'static + AsyncBufRead + Unpin + Send,
+{
+    match detected {
+        #[cfg(feature = "file-xz")]
+        Some("xz") => {
+            let mut decoder = async_compression::tokio::bufread::XzDecoder::new(reader);
+            decoder.multiple_members(true);
+            Ok(Box::new(decoder))
+        }
+        #[cfg(feature = "file-gz")]
+        Some("gz") => {
+            let mut decoder = async_compression::tokio::bufread::GzipDecoder::new(reader);
+            decoder.multiple_members(true);
+            Ok(Box::new(decoder))
+        }
+        #[cfg(feature = "file-bz2")]
+        Some("bz2") => {
+            let mut decoder = async_compression::tokio::bufread::BzDecoder::new(reader);
+            decoder.multiple_members(true);
+            Ok(Box::new(decoder))
+        }
+        Some(technique) => Err(technique),
+        None => Ok(Box::new(reader)),
+    }
+}
 
 /// Read the entire contents of a file into a bytes vector.
 ///
 /// This function supports opening compressed files transparently.
 ///
-/// The API mirrors the function in [`tokio::fs::read`] but is implemented via [`crate::fs::read`].
+/// The API mirrors the function in [`tokio::fs::read`] but is implemented via [`crate::fs::file_open_read`].
+/// The blocking work runs on the current runtime; use [`with_runtime`] to choose a different one.
+/// Dropping the future stops the blocking work after the current chunk, instead of decompressing the whole file.
 pub async fn read(path: impl AsRef<Path>) -> Result<Vec<u8>, Error> {
-    let path = path.as_ref().to_owned();
-    tokio::task::spawn_blocking(move || crate::fs::read(path)).await?
+    with_runtime(Handle::current()).read(path).await
 }
 
 /// Read the entire contents of a file into a string.
 ///
 /// This function supports opening compressed files transparently.
 ///
-/// The API mirrors the function in [`tokio::fs::read_to_string`] but is implemented via [`crate::fs::read_to_string`].
+/// The API mirrors the function in [`tokio::fs::read_to_string`] but is implemented via [`crate::fs::file_open_read`].
+/// The blocking work runs on the current runtime; use [`with_runtime`] to choose a different one.
+/// Dropping the future stops the blocking work after the current chunk, instead of decompressing the whole file.
 pub async fn read_to_string(path: impl AsRef<Path>) -> Result<String, Error> {
-    let path = path.as_ref().to_owned();
-    tokio::task::spawn_blocking(move || crate::fs::read_to_string(path)).await?
+    with_runtime(Handle::current()).read_to_string(path).await
 }
 
 /// Write a slice as the entire contents of a file.
@@ -37,10 +178,9 @@ pub async fn read_to_string(path: impl AsRef<Path>) -> Result<String, Error> {
 /// The method will truncate the file before writing, such that `contents` will be the only content of the file.
 ///
 /// The API mirrors the function in [`tokio::fs::write`] but is implemented via [`crate::fs::write`].
+/// The blocking work runs on the current runtime; use [`with_runtime`] to choose a different one.
 pub async fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), Error> {
-    let path = path.as_ref().to_owned();
-    let contents = contents.as_ref().to_owned();
-    tokio::task::spawn_blocking(move || crate::fs::write(path, contents)).await?
+    with_runtime(Handle::current()).write(path, contents).await
 }
 
 /// Append the content to the file.
@@ -48,8 +188,716 @@ pub async fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result
 /// This function only works for plaintext and gzip files.
 ///
 /// The function is similar to [`write()`] but will append the content to the end of the file instead of truncating.
+/// The blocking work runs on the current runtime; use [`with_runtime`] to choose a different one.
 pub async fn append(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    with_runtime(Handle::current()).append(path, contents).await
+}
+
+/// Run the blocking work of the async functions on the runtime of `handle`.
+///
+/// The free functions of this module use the blocking pool of the current runtime.
+/// In applications with multiple runtimes, this selects the runtime explicitly, independent of where the futures are polled.
+///
+/// # Example
+///
+/// ```rust
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let tmpdir = tempfile::Builder::new().tempdir()?;
+/// # let path = tmpdir.path().join("file.txt");
+/// let io_runtime = tokio::runtime::Builder::new_current_thread().build()?;
+/// let fs = misc_utils::async_fs::with_runtime(io_runtime.handle().clone());
+///
+/// let main_runtime = tokio::runtime::Builder::new_current_thread().build()?;
+/// main_runtime.block_on(async {
+///     fs.write(&path, "Hello World").await?;
+///     assert_eq!(fs.read_to_string(&path).await?, "Hello World");
+///     Ok::<_, misc_utils::error::Error>(())
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn with_runtime(handle: Handle) -> RuntimeFs {
+    RuntimeFs { handle }
+}
+
+/// Async filesystem functions bound to a specific runtime.
+///
+/// Created by [`with_runtime`].
+/// The functions behave like the free functions of the same name, but run their blocking work on the runtime of the [`Handle`].
+#[derive(Clone, Debug)]
+pub struct RuntimeFs {
+    handle: Handle,
+}
+
+impl RuntimeFs {
+    /// The [`Handle`] of the runtime used for blocking work.
+    pub fn handle(&self) -> &Handle {
+        &self.handle
+    }
+
+    /// Read the entire contents of a file into a bytes vector.
+    ///
+    /// See [`read()`].
+    pub async fn read(&self, path: impl AsRef<Path>) -> Result<Vec<u8>, Error> {
+        let path = path.as_ref().to_owned();
+        let cancel = CancelOnDrop::default();
+        let cancelled = cancel.0.clone();
+        self.handle
+            .spawn_blocking(move || {
+                let mut buffer = Vec::new();
+                let mut reader = read_cancellable(&path, cancelled)?;
+                io::Read::read_to_end(&mut reader, &mut buffer).map_err(|err| Error::FileIo {
+                    file: path,
+                    operation: Operation::Read,
+                    source: err,
+                })?;
+                Ok(buffer)
+            })
+            .await?
+    }
+
+    /// Read the entire contents of a file into a string.
+    ///
+    /// See [`read_to_string()`].
+    pub async fn read_to_string(&self, path: impl AsRef<Path>) -> Result<String, Error> {
+        let path = path.as_ref().to_owned();
+        let cancel = CancelOnDrop::default();
+        let cancelled = cancel.0.clone();
+        self.handle
+            .spawn_blocking(move || {
+                let mut buffer = String::new();
+                let mut reader = read_cancellable(&path, cancelled)?;
+                io::Read::read_to_string(&mut reader, &mut buffer).map_err(|err| {
+                    Error::FileIo {
+                        file: path,
+                        operation: Operation::Read,
+                        source: err,
+                    }
+                })?;
+                Ok(buffer)
+            })
+            .await?
+    }
+
+    /// Write a slice as the entire contents of a file.
+    ///
+    /// See [`write()`].
+    pub async fn write(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> Result<(), Error> {
+        let path = path.as_ref().to_owned();
+        let contents = contents.as_ref().to_owned();
+        self.handle
+            .spawn_blocking(move || crate::fs::write(path, contents))
+            .await?
+    }
+
+    /// Append the content to the file.
+    ///
+    /// See [`append()`].
+    pub async fn append(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> Result<(), Error> {
+        let path = path.as_ref().to_owned();
+        let contents = contents.as_ref().to_owned();
+        self.handle
+            .spawn_blocking(move || crate::fs::append(path, contents))
+            .await?
+    }
+
+    /// Parse a [JSONL] file as a [`Stream`] of values.
+    ///
+    /// See [`parse_jsonl_stream()`].
+    ///
+    /// [JSONL]: http://jsonlines.org/
+    #[cfg(feature = "jsonl")]
+    pub fn parse_jsonl_stream<T>(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> impl Stream<Item = Result<T, MtJsonlError>> + Send + Unpin
+    where
+        T: 'static + DeserializeOwned + Send,
+    {
+        let path = path.as_ref().to_owned();
+        self.iter_stream(move || crate::fs::parse_jsonl_multi_threaded(path, 1024))
+    }
+
+    /// Find all paths matching a glob `pattern` as a [`Stream`].
+    ///
+    /// See [`glob()`].
+    pub fn glob(&self, pattern: &str) -> impl Stream<Item = Result<PathBuf, Error>> + Send + Unpin {
+        let pattern = pattern.to_owned();
+        self.iter_stream(move || {
+            let paths: Box<dyn Iterator<Item = Result<PathBuf, Error>>> = match glob::glob(&pattern)
+            {
+                Ok(paths) => Box::new(paths.map(|path| {
+                    path.map_err(|err| Error::FileIo {
+                        file: err.path().to_path_buf(),
+                        operation: Operation::ReadDir,
+                        source: err.into(),
+                    })
+                })),
+                Err(err) => Box::new(std::iter::once(Err(Error::GlobPatternError {
+                    pattern,
+                    source: err,
+                }))),
+            };
+            paths
+        })
+    }
+
+    /// Drive the iterator created by `make_iter` on the blocking thread pool and return its items as a [`Stream`].
+    ///
+    /// Only a limited number of items is buffered, such that the iterator pauses if the stream is not polled.
+    /// Dropping the stream stops the iterator.
+    fn iter_stream<I, F>(&self, make_iter: F) -> ChannelStream<I::Item>
+    where
+        I: Iterator,
+        I::Item: 'static + Send,
+        F: 'static + FnOnce() -> I + Send,
+    {
+        let (sender, receiver) = mpsc::channel(STREAM_BUFSIZE);
+        self.handle.spawn_blocking(move || {
+            for item in make_iter() {
+                if sender.blocking_send(item).is_err() {
+                    // The stream was dropped
+                    return;
+                }
+            }
+        });
+        ChannelStream { receiver }
+    }
+}
+
+/// Sets a cancellation flag when dropped.
+///
+/// Blocking work cannot be aborted by dropping its [`tokio::task::JoinHandle`].
+/// Instead, the future owns this guard and the blocking work checks the flag between chunks.
+#[derive(Default)]
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Reader which fails once the cancellation flag is set.
+struct CancellableReader<R> {
+    inner: R,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<R: io::Read> io::Read for CancellableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(io::Error::other("The operation was cancelled."));
+        }
+        self.inner.read(buf)
+    }
+}
+
+/// Open `path` with [`crate::fs::file_open_read`] and stop reading once `cancelled` is set.
+fn read_cancellable(
+    path: &Path,
+    cancelled: Arc<AtomicBool>,
+) -> Result<CancellableReader<Box<dyn io::Read>>, Error> {
+    Ok(CancellableReader {
+        inner: crate::fs::file_open_read(path)?,
+        cancelled,
+    })
+}
+
+/// Read the lines of a file as a [`Stream`].
+///
+/// The file is opened with [`file_open_read`] when the stream is first polled, such that compressed files are decompressed while reading.
+/// The lines do not contain the line terminators `\n` or `\r\n`.
+/// The stream ends after the first error.
+pub fn lines(path: impl AsRef<Path>) -> impl Stream<Item = Result<String, Error>> + Send + Unpin {
     let path = path.as_ref().to_owned();
-    let contents = contents.as_ref().to_owned();
-    tokio::task::spawn_blocking(move || crate::fs::append(path, contents)).await?
+    let open = Box::pin(file_open_read(path.clone()));
+    LinesStream {
+        path,
+        state: LinesState::Opening(open),
+    }
+}
+
+/// Stream returned by [`lines`].
+struct LinesStream {
+    path: PathBuf,
+    state: LinesState,
+}
+
+/// Future returned by [`file_open_read`].
+type OpenFuture =
+    Pin<Box<dyn Future<Output = Result<Box<dyn AsyncRead + Unpin + Send>, Error>> + Send>>;
+
+enum LinesState {
+    Opening(OpenFuture),
+    Reading(Lines<BufReader<Box<dyn AsyncRead + Unpin + Send>>>),
+    Done,
+}
+
+impl Stream for LinesStream {
+    type Item = Result<String, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let err = match &mut self.state {
+                LinesState::Opening(open) => match open.as_mut().poll(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(reader)) => {
+                        self.state = LinesState::Reading(BufReader::new(reader).lines());
+                        continue;
+                    }
+                    Poll::Ready(Err(err)) => err,
+                },
+                LinesState::Reading(lines) => match Pin::new(lines).poll_next_line(cx) {
+                    Poll::Pending => return Poll::Pending,
+                    Poll::Ready(Ok(Some(line))) => return Poll::Ready(Some(Ok(line))),
+                    Poll::Ready(Ok(None)) => {
+                        self.state = LinesState::Done;
+                        return Poll::Ready(None);
+                    }
+                    Poll::Ready(Err(err)) => Error::FileIo {
+                        file: self.path.clone(),
+                        operation: Operation::Read,
+                        source: err,
+                    },
+                },
+                LinesState::Done => return Poll::Ready(None),
+            };
+            self.state = LinesState::Done;
+            return Poll::Ready(Some(Err(err)));
+        }
+    }
+}
+
+/// Create a [`WriteBuilder`] for asynchronously writing into `path`.
+///
+/// The filetype is chosen based on the extension like in [`crate::fs::file_write`].
+pub fn file_write(path: impl AsRef<Path>) -> WriteBuilder {
+    WriteBuilder::new(path.as_ref().to_path_buf())
+}
+
+/// Configure how to open a file for asynchronous writing.
+///
+/// This mirrors [`crate::fs::WriteBuilder`], but returns a [`CompressedWriter`] implementing [`AsyncWrite`].
+/// Options of the blocking writer, which require blocking operations, like preallocating space or multithreaded compression, are not available.
+#[derive(Debug)]
+pub struct WriteBuilder {
+    /// Capacity of the [`BufWriter`] of the file.
+    buffer_capacity: Option<usize>,
+    /// Compression level for archives.
+    compression_level: Compression,
+    /// FileType of the new file, guessed from the extension if unset.
+    filetype: Option<FileType>,
+    /// Controls how the file will be opened.
+    open_options: OpenOptions,
+    /// Path where the file will be written.
+    path: PathBuf,
+}
+
+impl WriteBuilder {
+    /// Create a new [`WriteBuilder`] for a given path.
+    ///
+    /// See the individual methods for the available configuration options.
+    pub fn new(path: PathBuf) -> Self {
+        let mut open_options = OpenOptions::new();
+        open_options.read(false).write(true).create(true);
+
+        WriteBuilder {
+            buffer_capacity: None,
+            compression_level: Compression::default(),
+            filetype: None,
+            open_options,
+            path,
+        }
+    }
+
+    /// Open the file in *append* mode.
+    pub async fn append(&mut self) -> Result<CompressedWriter, Error> {
+        self.open_options.append(true);
+        self.open().await
+    }
+
+    /// Open the file in *truncate* mode.
+    pub async fn truncate(&mut self) -> Result<CompressedWriter, Error> {
+        self.open_options.truncate(true);
+        self.open().await
+    }
+
+    async fn open(&mut self) -> Result<CompressedWriter, Error> {
+        let filetype = match self.filetype {
+            Some(filetype) => filetype,
+            None => guess_file_type(&self.path)?,
+        };
+        let file = self
+            .open_options
+            .open(&self.path)
+            .await
+            .map_err(|err| Error::FileIo {
+                file: self.path.to_path_buf(),
+                operation: Operation::Open,
+                source: err,
+            })?;
+        let bufwrite = match self.buffer_capacity {
+            Some(size) => BufWriter::with_capacity(size, file),
+            None => BufWriter::new(file),
+        };
+
+        #[cfg(any(feature = "file-bz2", feature = "file-gz", feature = "file-xz"))]
+        let level = match self.compression_level {
+            Compression::Fastest => async_compression::Level::Fastest,
+            Compression::Default => async_compression::Level::Default,
+            Compression::Best => async_compression::Level::Best,
+            Compression::Numeric(n) => async_compression::Level::Precise(i32::from(n.min(9))),
+        };
+        let writer: Box<dyn AsyncWrite + Unpin + Send> = match filetype {
+            #[cfg(feature = "file-bz2")]
+            FileType::Bz2 => Box::new(async_compression::tokio::write::BzEncoder::with_quality(
+                bufwrite, level,
+            )),
+            #[cfg(feature = "file-gz")]
+            FileType::Gz => Box::new(async_compression::tokio::write::GzipEncoder::with_quality(
+                bufwrite, level,
+            )),
+            FileType::PlainText => Box::new(bufwrite),
+            #[cfg(feature = "file-xz")]
+            FileType::Xz => Box::new(async_compression::tokio::write::XzEncoder::with_quality(
+                bufwrite, level,
+            )),
+        };
+        Ok(CompressedWriter {
+            writer,
+            bytes_written: 0,
+            filetype,
+            path: self.path.clone(),
+        })
+    }
+
+    /// Sets the capacity of the [`BufWriter`] to `capacity` in Bytes.
+    pub fn buffer_capacity(&mut self, buffer_capacity: usize) -> &mut Self {
+        self.buffer_capacity = Some(buffer_capacity);
+        self
+    }
+
+    /// Sets the option to create a new file, or open it if it already exists.
+    ///
+    /// See [`crate::fs::WriteBuilder::create`].
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.open_options.create(create);
+        self
+    }
+
+    /// Sets the option to create a new file, failing if it already exists.
+    ///
+    /// See [`crate::fs::WriteBuilder::create_new`].
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.open_options.create_new(create_new);
+        self
+    }
+
+    /// Sets the compression level for archives.
+    ///
+    /// See [`crate::fs::WriteBuilder::compression_level`].
+    pub fn compression_level(&mut self, compression_level: Compression) -> &mut Self {
+        self.compression_level = compression_level;
+        self
+    }
+
+    /// Sets the output filetype.
+    ///
+    /// This can be used to overwrite the automatically detected filetype.
+    pub fn filetype(&mut self, filetype: FileType) -> &mut Self {
+        self.filetype = Some(filetype);
+        self
+    }
+}
+
+/// Asynchronous writer for plaintext or compressed files.
+///
+/// This struct is created by [`WriteBuilder::append`] and [`WriteBuilder::truncate`].
+///
+/// Unlike [`crate::fs::CompressedWriter`], dropping the writer does not finalize the file.
+/// Call [`CompressedWriter::finish`] or [`AsyncWriteExt::shutdown`] to write all remaining data.
+pub struct CompressedWriter {
+    writer: Box<dyn AsyncWrite + Unpin + Send>,
+    /// Number of uncompressed bytes written so far
+    bytes_written: u64,
+    filetype: FileType,
+    path: PathBuf,
+}
+
+impl CompressedWriter {
+    /// Write all remaining data and finalize the file.
+    pub async fn finish(mut self) -> Result<(), Error> {
+        self.writer.shutdown().await.map_err(|err| Error::FileIo {
+            file: self.path,
+            operation: Operation::Flush,
+            source: err,
+        })
+    }
+
+    /// Number of bytes written into this writer.
+    ///
+    /// This counts the uncompressed bytes, not the number of bytes in the file.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// The [`FileType`] of the written file.
+    pub fn filetype(&self) -> FileType {
+        self.filetype
+    }
+
+    /// Path of the written file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl fmt::Debug for CompressedWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressedWriter")
+            .field("bytes_written", &self.bytes_written)
+            .field("filetype", &self.filetype)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncWrite for CompressedWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.writer).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = poll {
+            self.bytes_written += written as u64;
+        }
+        poll
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.writer).poll_shutdown(cx)
+    }
+}
+
+/// Read the entries of a directory, sorted by their file name.
+///
+/// The order of [`read_dir`] depends on the platform and filesystem.
+/// This function collects all entries and sorts them by comparing the file names bytewise.
+/// See [`read_dir_sorted_natural`] to sort numbers in the file names by their value.
+pub async fn read_dir_sorted(path: impl AsRef<Path>) -> Result<Vec<DirEntry>, Error> {
+    let mut entries = read_dir_entries(path.as_ref()).await?;
+    entries.sort_by_key(DirEntry::file_name);
+    Ok(entries)
+}
+
+/// Read the entries of a directory, sorted by their file name in natural order.
+///
+/// Like [`read_dir_sorted`], but sequences of ASCII digits are compared by their numeric value.
+/// For example, `file2.txt` sorts before `file10.txt`.
+pub async fn read_dir_sorted_natural(path: impl AsRef<Path>) -> Result<Vec<DirEntry>, Error> {
+    let mut entries = read_dir_entries(path.as_ref()).await?;
+    entries.sort_by(|a, b| natural_cmp(&a.file_name(), &b.file_name()));
+    Ok(entries)
+}
+
+/// Collect all entries of the directory `path`.
+async fn read_dir_entries(path: &Path) -> Result<Vec<DirEntry>, Error> {
+    let read_error = |err| Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::ReadDir,
+        source: err,
+    };
+    let mut entries = Vec::new();
+    let mut dir = read_dir(path).await.map_err(read_error)?;
+    while let Some(entry) = dir.next_entry().await.map_err(read_error)? {
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Compare two file names, such that sequences of ASCII digits are compared by their numeric value.
+///
+/// Numbers with the same value but more leading zeros sort first, to keep the order total.
+fn natural_cmp(a: &OsStr, b: &OsStr) -> std::cmp::Ordering {
+    let (a, b) = (a.as_encoded_bytes(), b.as_encoded_bytes());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i].is_ascii_digit() && b[j].is_ascii_digit() {
+            let a_end = i + a[i..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let b_end = j + b[j..].iter().take_while(|c| c.is_ascii_digit()).count();
+            let a_num = trim_leading_zeros(&a[i..a_end]);
+            let b_num = trim_leading_zeros(&b[j..b_end]);
+            let ordering = a_num
+                .len()
+                .cmp(&b_num.len())
+                .then_with(|| a_num.cmp(b_num))
+                .then_with(|| (b_end - j).cmp(&(a_end - i)));
+            if ordering.is_ne() {
+                return ordering;
+            }
+            (i, j) = (a_end, b_end);
+        } else {
+            let ordering = a[i].cmp(&b[j]);
+            if ordering.is_ne() {
+                return ordering;
+            }
+            (i, j) = (i + 1, j + 1);
+        }
+    }
+    (a.len() - i).cmp(&(b.len() - j))
+}
+
+/// Remove the leading `0`s of a sequence of digits.
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&c| c == b'0').count();
+    &digits[zeros..]
+}
+
+/// Size of the chunks copied by [`recompress`] before yielding to the scheduler.
+const COPY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Copy the contents of one file to another file, converting the compression on the way.
+///
+/// The API mirrors the function [`crate::fs::copy`], see [`recompress`] for details.
+pub async fn copy(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> Result<u64, Error> {
+    recompress(src, dst, &RecompressOptions::default()).await
+}
+
+/// Copy the contents of one file to another file, re-encoding it according to `options`.
+///
+/// The source file is opened with [`file_open_read`], thus compressed files are decompressed transparently.
+/// The destination file is truncated before writing.
+/// The content is streamed in chunks of bounded size and the task yields to the scheduler between chunks, such that large files do not block the worker threads.
+/// [`RecompressOptions::threads`] is ignored, since the compression happens on the calling task.
+///
+/// The API mirrors the function [`crate::fs::recompress`].
+/// Returns the number of uncompressed bytes copied.
+pub async fn recompress(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+    options: &RecompressOptions,
+) -> Result<u64, Error> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+
+    // Capture the metadata before reading changes the access time
+    let metadata = if options.preserve_metadata {
+        Some(metadata(src).await.map_err(|err| Error::FileIo {
+            file: src.to_path_buf(),
+            operation: Operation::Metadata,
+            source: err,
+        })?)
+    } else {
+        None
+    };
+
+    let mut builder = file_write(dst);
+    builder.compression_level(options.compression_level);
+    if let Some(filetype) = options.filetype {
+        builder.filetype(filetype);
+    }
+    let mut reader = file_open_read(src).await?;
+    let mut writer = builder.truncate().await?;
+
+    let write_error = |err| Error::FileIo {
+        file: dst.to_path_buf(),
+        operation: Operation::Write,
+        source: err,
+    };
+    let mut buffer = vec![0; COPY_CHUNK_SIZE];
+    let mut copied = 0;
+    loop {
+        let len = reader
+            .read(&mut buffer)
+            .await
+            .map_err(|err| Error::FileIo {
+                file: src.to_path_buf(),
+                operation: Operation::Read,
+                source: err,
+            })?;
+        if len == 0 {
+            break;
+        }
+        writer
+            .write_all(&buffer[..len])
+            .await
+            .map_err(write_error)?;
+        copied += len as u64;
+        tokio::task::yield_now().await;
+    }
+    writer.shutdown().await.map_err(write_error)?;
+
+    if let Some(metadata) = metadata {
+        let dst = dst.to_path_buf();
+        tokio::task::spawn_blocking(move || copy_metadata(&metadata, &dst)).await??;
+    }
+    Ok(copied)
+}
+
+/// Parse a [JSONL] file as a [`Stream`] of values.
+///
+/// The file is read and parsed by [`crate::fs::parse_jsonl_multi_threaded`] on the blocking thread pool of Tokio.
+/// Only a limited number of values is buffered, such that reading pauses if the stream is not polled.
+/// Dropping the stream stops reading the file.
+///
+/// This function is only available if the `jsonl` feature is enabled, too.
+///
+/// # Panics
+///
+/// This function panics if called outside of a Tokio runtime.
+/// Use [`with_runtime`] to choose the runtime explicitly.
+///
+/// [JSONL]: http://jsonlines.org/
+#[cfg(feature = "jsonl")]
+pub fn parse_jsonl_stream<T>(
+    path: impl AsRef<Path>,
+) -> impl Stream<Item = Result<T, MtJsonlError>> + Send + Unpin
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    with_runtime(Handle::current()).parse_jsonl_stream(path)
+}
+
+/// Find all paths matching a glob `pattern` as a [`Stream`].
+///
+/// The pattern syntax is described in [`glob::Pattern`], e.g., `data/**/*.jsonl.gz`.
+/// The directories are traversed on the blocking thread pool of Tokio and the paths are yielded in alphabetical order.
+/// An invalid pattern yields a single [`Error::GlobPatternError`].
+/// Errors while reading a directory are yielded as items, but do not stop the traversal.
+///
+/// # Panics
+///
+/// This function panics if called outside of a Tokio runtime.
+/// Use [`with_runtime`] to choose the runtime explicitly.
+pub fn glob(pattern: &str) -> impl Stream<Item = Result<PathBuf, Error>> + Send + Unpin {
+    with_runtime(Handle::current()).glob(pattern)
+}
+
+/// Number of items buffered by the streams driven on the blocking thread pool.
+const STREAM_BUFSIZE: usize = 1024;
+
+/// Stream returned by [`RuntimeFs::iter_stream`].
+struct ChannelStream<T> {
+    receiver: mpsc::Receiver<T>,
+}
+
+impl<T> Stream for ChannelStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
 }
@@ -0,0 +1,895 @@
+//!  Contains functions to print bytes in a human-readable format.
+//!
+//! The bytes are printes in a mostly ASCII format.
+//! Non-ASCII values are escaped using the `\xXX` notation.
+//!
+//! # Examples
+//!
+//! ```rust
+//! let bytes = [72, 101, 108, 108, 111, 10, 0, 9, 10, 0xde, 0xad, 0xbe, 0xef];
+//! let expected = r"Hello\n\0\t\n\xde\xad\xbe\xef";
+//! assert_eq!(expected, misc_utils::byteascii::byteascii(&bytes));
+//! ```
+//!
+//! The escaped notation can be decoded again using [`unbyteascii`].
+
+use crate::error::DecodeError;
+use std::borrow::Borrow;
+use std::cmp::PartialEq;
+use std::fmt::{self, Write as _};
+
+// Map each byte to its escaped version
+#[rustfmt::skip]
+pub(crate) static BYTESPRINTED: [&str; 256] = [
+    "\\0",   "\\x01", "\\x02", "\\x03", "\\x04", "\\x05", "\\x06", "\\x07",
+    "\\x08", "\\t",   "\\n",   "\\x0b", "\\x0c", "\\r",   "\\x0e", "\\x0f",
+    "\\x10", "\\x11", "\\x12", "\\x13", "\\x14", "\\x15", "\\x16", "\\x17",
+    "\\x18", "\\x19", "\\x1a", "\\x1b", "\\x1c", "\\x1d", "\\x1e", "\\x1f",
+    " ",     "!",     "\"",    "#",     "$",     "%",     "&",     "'",
+    "(",     ")",     "*",     "+",     ",",     "-",     ".",     "/",
+    "0",     "1",     "2",     "3",     "4",     "5",     "6",     "7",
+    "8",     "9",     ":",     ";",     "<",     "=",     ">",     "?",
+    "@",     "A",     "B",     "C",     "D",     "E",     "F",     "G",
+    "H",     "I",     "J",     "K",     "L",     "M",     "N",     "O",
+    "P",     "Q",     "R",     "S",     "T",     "U",     "V",     "W",
+    "X",     "Y",     "Z",     "[",     "\\\\",  "]",     "^",    "_",
+    "`",     "a",     "b",     "c",     "d",     "e",     "f",     "g",
+    "h",     "i",     "j",     "k",     "l",     "m",     "n",     "o",
+    "p",     "q",     "r",     "s",     "t",     "u",     "v",     "w",
+    "x",     "y",     "z",     "{",     "|",     "}",     "~",     "\\x7f",
+    "\\x80", "\\x81", "\\x82", "\\x83", "\\x84", "\\x85", "\\x86", "\\x87",
+    "\\x88", "\\x89", "\\x8a", "\\x8b", "\\x8c", "\\x8d", "\\x8e", "\\x8f",
+    "\\x90", "\\x91", "\\x92", "\\x93", "\\x94", "\\x95", "\\x96", "\\x97",
+    "\\x98", "\\x99", "\\x9a", "\\x9b", "\\x9c", "\\x9d", "\\x9e", "\\x9f",
+    "\\xa0", "\\xa1", "\\xa2", "\\xa3", "\\xa4", "\\xa5", "\\xa6", "\\xa7",
+    "\\xa8", "\\xa9", "\\xaa", "\\xab", "\\xac", "\\xad", "\\xae", "\\xaf",
+    "\\xb0", "\\xb1", "\\xb2", "\\xb3", "\\xb4", "\\xb5", "\\xb6", "\\xb7",
+    "\\xb8", "\\xb9", "\\xba", "\\xbb", "\\xbc", "\\xbd", "\\xbe", "\\xbf",
+    "\\xc0", "\\xc1", "\\xc2", "\\xc3", "\\xc4", "\\xc5", "\\xc6", "\\xc7",
+    "\\xc8", "\\xc9", "\\xca", "\\xcb", "\\xcc", "\\xcd", "\\xce", "\\xcf",
+    "\\xd0", "\\xd1", "\\xd2", "\\xd3", "\\xd4", "\\xd5", "\\xd6", "\\xd7",
+    "\\xd8", "\\xd9", "\\xda", "\\xdb", "\\xdc", "\\xdd", "\\xde", "\\xdf",
+    "\\xe0", "\\xe1", "\\xe2", "\\xe3", "\\xe4", "\\xe5", "\\xe6", "\\xe7",
+    "\\xe8", "\\xe9", "\\xea", "\\xeb", "\\xec", "\\xed", "\\xee", "\\xef",
+    "\\xf0", "\\xf1", "\\xf2", "\\xf3", "\\xf4", "\\xf5", "\\xf6", "\\xf7",
+    "\\xf8", "\\xf9", "\\xfa", "\\xfb", "\\xfc", "\\xfd", "\\xfe", "\\xff",
+];
+
+/// Print a byte sequence as an ASCII string.
+///
+/// This function prints the bytes under the assumption most of the values are in the ASCII range.
+/// It prints ASCII characters and encodes other bytes as `\xHH` where `HH` is the hexadecimal value.
+pub fn byteascii(bytes: &[u8]) -> String {
+    let mut s = String::new();
+    byteascii_into(bytes, &mut s);
+    s
+}
+
+/// Append the bytes printed as an ASCII string to `out`.
+///
+/// The output is identical to [`byteascii`].
+/// The required capacity is reserved upfront, such that `out` reallocates at most once.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::byteascii_into;
+/// let mut out = String::from("data: ");
+/// byteascii_into(b"\x00abc", &mut out);
+/// assert_eq!(out, r"data: \0abc");
+/// ```
+pub fn byteascii_into(bytes: &[u8], out: &mut String) {
+    out.reserve(escaped_len(bytes));
+    out.extend(escape_iter(bytes));
+}
+
+/// Write the bytes printed as an ASCII string into `out`.
+///
+/// The output is identical to [`byteascii`].
+/// This works with any [`fmt::Write`] target, like a [`fmt::Formatter`].
+pub fn byteascii_write<W>(bytes: &[u8], out: &mut W) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+{
+    escape_iter(bytes).try_for_each(|escaped| out.write_str(escaped))
+}
+
+/// Render bytes as a C string literal, including the surrounding quotes.
+///
+/// Non-printable bytes are escaped using three digit octal escapes, such that a following digit is never part of the escape sequence.
+/// `?` is escaped to prevent trigraphs.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::c_literal;
+/// assert_eq!(c_literal(b"say \"hi\"\n\x001??"), r#""say \"hi\"\n\0001\?\?""#);
+/// ```
+pub fn c_literal(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('"');
+    for &b in bytes {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'?' => out.push_str("\\?"),
+            b'\t' => out.push_str("\\t"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            0x20..=0x7e => out.push(char::from(b)),
+            _ => {
+                // Writing into a String cannot fail
+                let _ = write!(out, "\\{b:03o}");
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Render bytes as a Rust byte string literal, including the `b` prefix and the quotes.
+///
+/// The escape sequences are identical to [`byteascii`], except that `"` is escaped, too.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::rust_byte_literal;
+/// assert_eq!(rust_byte_literal(b"say \"hi\"\n\xff"), r#"b"say \"hi\"\n\xff""#);
+/// ```
+pub fn rust_byte_literal(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(escaped_len(bytes) + 3);
+    out.push_str("b\"");
+    for &b in bytes {
+        match b {
+            b'"' => out.push_str("\\\""),
+            _ => out.push_str(BYTESPRINTED[b as usize]),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Render bytes as a JSON string, including the surrounding quotes.
+///
+/// JSON strings cannot contain arbitrary bytes.
+/// Each byte is mapped to the Unicode code point with the same value, i.e., the bytes are interpreted as Latin-1.
+/// This allows to recover the bytes losslessly from the decoded string.
+/// All non-ASCII and non-printable bytes are escaped, such that the output is pure ASCII.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::json_string;
+/// assert_eq!(json_string(b"say \"hi\"\n\x00\xff"), r#""say \"hi\"\n\u0000\u00ff""#);
+/// ```
+pub fn json_string(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('"');
+    for &b in bytes {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\t' => out.push_str("\\t"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            0x08 => out.push_str("\\b"),
+            0x0c => out.push_str("\\f"),
+            0x20..=0x7e => out.push(char::from(b)),
+            _ => {
+                // Writing into a String cannot fail
+                let _ = write!(out, "\\u{b:04x}");
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Quote bytes for a POSIX shell using single quotes.
+///
+/// All bytes are taken literally inside of single quotes, except for `'` itself, which is written as `'\''`.
+/// The result is a byte vector, since the shell passes non-UTF-8 bytes unmodified.
+///
+/// Returns `None` if `bytes` contains a NUL byte, which cannot be part of a shell argument.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::shell_quote;
+/// assert_eq!(shell_quote(b"it's $HOME").unwrap(), br"'it'\''s $HOME'");
+/// assert_eq!(shell_quote(b"nul\0"), None);
+/// ```
+pub fn shell_quote(bytes: &[u8]) -> Option<Vec<u8>> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(bytes.len() + 2);
+    out.push(b'\'');
+    for &b in bytes {
+        match b {
+            b'\'' => out.extend_from_slice(br"'\''"),
+            _ => out.push(b),
+        }
+    }
+    out.push(b'\'');
+    Some(out)
+}
+
+/// Return the length of the string [`byteascii`] creates for `bytes`.
+pub fn escaped_len(bytes: &[u8]) -> usize {
+    escape_iter(bytes).map(str::len).sum()
+}
+
+/// Iterate over the escaped representation of each byte.
+///
+/// The iterator yields the same pieces as [`byteascii`] without allocating.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::escape_iter;
+/// let pieces: Vec<&str> = escape_iter(b"a\n\xff").collect();
+/// assert_eq!(pieces, ["a", r"\n", r"\xff"]);
+/// ```
+pub fn escape_iter(
+    bytes: &[u8],
+) -> impl DoubleEndedIterator<Item = &'static str> + ExactSizeIterator + Clone + '_ {
+    bytes.iter().map(|&b| BYTESPRINTED[b as usize])
+}
+
+/// Print a byte sequence as an ASCII string without allocating.
+///
+/// The returned value implements [`Display`](fmt::Display) and prints the bytes identical to [`byteascii`].
+/// This allows writing the escaped bytes directly into a formatter or writer.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::escape_display;
+/// # use std::fmt::Write;
+/// let mut log = String::new();
+/// write!(log, "received {}", escape_display(b"\x00\x01ok")).unwrap();
+/// assert_eq!(log, r"received \0\x01ok");
+/// ```
+pub fn escape_display(bytes: &[u8]) -> impl fmt::Display + '_ {
+    ByteAscii(bytes)
+}
+
+/// Decode a string in the notation of [`byteascii`] back into bytes.
+///
+/// The escape sequences `\0`, `\t`, `\n`, `\r`, `\\`, and `\xHH` are decoded, where `HH` are two hexadecimal digits in upper or lower case.
+/// All other characters are taken verbatim, such that non-ASCII characters are decoded as their UTF-8 encoding.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::{byteascii, unbyteascii};
+/// let bytes = [72, 105, 10, 0, 0xde, 0xad, b'\\'];
+/// assert_eq!(unbyteascii(r"Hi\n\0\xde\xad\\").unwrap(), bytes);
+/// assert_eq!(unbyteascii(&byteascii(&bytes)).unwrap(), bytes);
+/// assert!(unbyteascii(r"\q").is_err());
+/// ```
+pub fn unbyteascii(s: &str) -> Result<Vec<u8>, DecodeError> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        let position = i;
+        let b = match bytes.get(i + 1) {
+            Some(b'0') => 0,
+            Some(b't') => b'\t',
+            Some(b'n') => b'\n',
+            Some(b'r') => b'\r',
+            Some(b'\\') => b'\\',
+            Some(b'x') => {
+                let hex = bytes
+                    .get(i + 2..i + 4)
+                    .ok_or(DecodeError::IncompleteEscape { position })?;
+                let hex = std::str::from_utf8(hex)
+                    .ok()
+                    .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .ok_or(DecodeError::InvalidHex { position })?;
+                i += 2;
+                u8::from_str_radix(hex, 16).map_err(|_| DecodeError::InvalidHex { position })?
+            }
+            Some(_) => return Err(DecodeError::UnknownEscape { position }),
+            None => return Err(DecodeError::IncompleteEscape { position }),
+        };
+        decoded.push(b);
+        i += 2;
+    }
+    Ok(decoded)
+}
+
+/// [`Debug`] and [`Display`](fmt::Display) print a byte sequence as an ASCII string.
+///
+/// This newtype can be used when creating a [`Debug`] implementation for a type or to print bytes with `format!("{}")`.
+/// It prints the bytes identical to [`byteascii`].
+/// Use [`ByteAsciiString`] to render the bytes only once and keep the result.
+pub struct ByteAscii<B>(pub B);
+
+impl<B> fmt::Debug for ByteAscii<B>
+where
+    B: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for escaped in escape_iter(self.0.as_ref()) {
+            f.write_str(escaped)?;
+        }
+        Ok(())
+    }
+}
+
+impl<B> PartialEq for ByteAscii<B>
+where
+    B: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq(&other.0)
+    }
+}
+
+impl<B> Eq for ByteAscii<B> where B: Eq {}
+
+impl<B> ByteAscii<B>
+where
+    B: AsRef<[u8]>,
+{
+    /// Print the bytes with a line break after every `width` source bytes.
+    ///
+    /// Escape sequences are never split across lines.
+    /// Each line can be prefixed with the offset of its first byte using [`ByteAsciiWrapped::with_offsets`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `width` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use misc_utils::byteascii::ByteAscii;
+    /// let bytes = b"Hello\nWorld\xff";
+    /// assert_eq!(
+    ///     ByteAscii(bytes).wrap(4).to_string(),
+    ///     "Hell\no\\nWo\nrld\\xff",
+    /// );
+    /// assert_eq!(
+    ///     ByteAscii(bytes).wrap(8).with_offsets(true).to_string(),
+    ///     "00000000: Hello\\nWo\n00000008: rld\\xff",
+    /// );
+    /// ```
+    pub fn wrap(self, width: usize) -> ByteAsciiWrapped<B> {
+        assert!(width > 0, "The line width must be larger than zero");
+        ByteAsciiWrapped {
+            bytes: self.0,
+            width,
+            offsets: false,
+        }
+    }
+
+    /// Print only the first `max_len` bytes, followed by the total length.
+    ///
+    /// If there are more than `max_len` bytes, the output ends with `… (<total> bytes)`.
+    /// This makes it safe to log potentially huge binary payloads.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use misc_utils::byteascii::ByteAscii;
+    /// let payload = vec![0xff; 1_000_000];
+    /// assert_eq!(
+    ///     ByteAscii(&payload).preview(3).to_string(),
+    ///     r"\xff\xff\xff… (1000000 bytes)",
+    /// );
+    /// assert_eq!(ByteAscii(b"short").preview(10).to_string(), "short");
+    /// ```
+    pub fn preview(self, max_len: usize) -> ByteAsciiPreview<B> {
+        ByteAsciiPreview {
+            bytes: self.0,
+            max_len,
+        }
+    }
+
+    /// Print the bytes with the escape sequences highlighted by ANSI colors.
+    ///
+    /// Printable ASCII characters are printed normally, while escape sequences are printed in yellow.
+    /// Following the [`NO_COLOR`](https://no-color.org/) convention, no colors are used if the `NO_COLOR` environment variable is set to a non-empty value.
+    /// The environment variable is checked when calling this function.
+    /// Use [`ByteAsciiColored::with_color`] to override this.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use misc_utils::byteascii::ByteAscii;
+    /// assert_eq!(
+    ///     ByteAscii(b"ok\xff\x00!").colored().with_color(true).to_string(),
+    ///     "ok\x1b[33m\\xff\\0\x1b[0m!",
+    /// );
+    /// ```
+    pub fn colored(self) -> ByteAsciiColored<B> {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        ByteAsciiColored {
+            bytes: self.0,
+            color: !no_color,
+        }
+    }
+}
+
+impl<B> fmt::Display for ByteAscii<B>
+where
+    B: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+/// Print a byte sequence as an ASCII string with line breaks.
+///
+/// This struct is created by [`ByteAscii::wrap`].
+/// The lines are separated by `\n` and the output has no trailing line break.
+#[derive(Clone, Debug)]
+pub struct ByteAsciiWrapped<B> {
+    bytes: B,
+    width: usize,
+    offsets: bool,
+}
+
+impl<B> ByteAsciiWrapped<B> {
+    /// Prefix each line with the hexadecimal offset of its first byte.
+    pub fn with_offsets(mut self, offsets: bool) -> Self {
+        self.offsets = offsets;
+        self
+    }
+}
+
+impl<B> fmt::Display for ByteAsciiWrapped<B>
+where
+    B: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, line) in self.bytes.as_ref().chunks(self.width).enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            if self.offsets {
+                write!(f, "{:08x}: ", i * self.width)?;
+            }
+            for escaped in escape_iter(line) {
+                f.write_str(escaped)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Print the beginning of a byte sequence as an ASCII string.
+///
+/// This struct is created by [`ByteAscii::preview`].
+#[derive(Clone, Debug)]
+pub struct ByteAsciiPreview<B> {
+    bytes: B,
+    max_len: usize,
+}
+
+impl<B> fmt::Display for ByteAsciiPreview<B>
+where
+    B: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.bytes.as_ref();
+        if bytes.len() <= self.max_len {
+            return byteascii_write(bytes, f);
+        }
+        byteascii_write(&bytes[..self.max_len], f)?;
+        write!(f, "… ({} bytes)", bytes.len())
+    }
+}
+
+/// Print a byte sequence as an ASCII string with highlighted escape sequences.
+///
+/// This struct is created by [`ByteAscii::colored`].
+#[derive(Clone, Debug)]
+pub struct ByteAsciiColored<B> {
+    bytes: B,
+    color: bool,
+}
+
+impl<B> ByteAsciiColored<B> {
+    /// Enable or disable the colors, regardless of the `NO_COLOR` environment variable.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+impl<B> fmt::Display for ByteAsciiColored<B>
+where
+    B: AsRef<[u8]>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const ESCAPE_STYLE: &str = "\x1b[33m";
+        const RESET_STYLE: &str = "\x1b[0m";
+
+        if !self.color {
+            return byteascii_write(self.bytes.as_ref(), f);
+        }
+        // Consecutive escape sequences share a single colored span
+        let mut in_escape = false;
+        for escaped in escape_iter(self.bytes.as_ref()) {
+            let is_escape = escaped.starts_with('\\');
+            if is_escape != in_escape {
+                f.write_str(if is_escape { ESCAPE_STYLE } else { RESET_STYLE })?;
+                in_escape = is_escape;
+            }
+            f.write_str(escaped)?;
+        }
+        if in_escape {
+            f.write_str(RESET_STYLE)?;
+        }
+        Ok(())
+    }
+}
+
+/// Show where two byte sequences differ.
+///
+/// The returned value implements [`Display`](fmt::Display).
+/// It prints the offset of the first differing byte and escaped excerpts of both inputs around it.
+/// The excerpts are aligned and a marker points at the first difference.
+/// This is useful to create readable assertion failures for binary data.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::diff;
+/// let expected = b"\x1f\x8b\x08\x00header";
+/// let actual = b"\x1f\x8b\x08\x08header";
+/// assert_eq!(
+///     diff(expected, actual).to_string(),
+///     r"first difference at offset 3 (left: 10 bytes, right: 10 bytes)
+///  left: \x1f\x8b\x08\0header
+/// right: \x1f\x8b\x08\x08header
+///                    ^"
+/// );
+/// ```
+pub fn diff<'a>(left: &'a [u8], right: &'a [u8]) -> ByteAsciiDiff<'a> {
+    ByteAsciiDiff { left, right }
+}
+
+/// Show where two byte sequences differ.
+///
+/// This struct is created by [`diff`].
+#[derive(Copy, Clone, Debug)]
+pub struct ByteAsciiDiff<'a> {
+    left: &'a [u8],
+    right: &'a [u8],
+}
+
+impl fmt::Display for ByteAsciiDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        /// Number of bytes shown before and after the first difference
+        const CONTEXT: usize = 16;
+        const ELLIPSIS: &str = "...";
+
+        let (left, right) = (self.left, self.right);
+        if left == right {
+            return write!(f, "both inputs are equal ({} bytes)", left.len());
+        }
+        let offset = left
+            .iter()
+            .zip(right)
+            .position(|(l, r)| l != r)
+            .unwrap_or_else(|| left.len().min(right.len()));
+        let start = offset.saturating_sub(CONTEXT);
+        let prefix = if start > 0 { ELLIPSIS } else { "" };
+
+        writeln!(
+            f,
+            "first difference at offset {offset} (left: {} bytes, right: {} bytes)",
+            left.len(),
+            right.len()
+        )?;
+        for (label, bytes) in [(" left", left), ("right", right)] {
+            let end = bytes.len().min(offset + CONTEXT);
+            let suffix = if end < bytes.len() { ELLIPSIS } else { "" };
+            write!(f, "{label}: {prefix}")?;
+            byteascii_write(&bytes[start..end], f)?;
+            writeln!(f, "{suffix}")?;
+        }
+        // The bytes before the difference are identical, so the marker is at the same column for both lines
+        let column = " left: ".len() + prefix.len() + escaped_len(&left[start..offset]);
+        write!(f, "{:column$}^", "")
+    }
+}
+
+/// Owned string of bytes escaped by [`byteascii`].
+///
+/// The bytes are rendered once on creation, such that the string can be used repeatedly, e.g., in error messages or as a map key.
+///
+/// # Examples
+///
+/// ```rust
+/// # use misc_utils::byteascii::ByteAsciiString;
+/// # use std::collections::HashMap;
+/// let key = ByteAsciiString::new(b"key\xff");
+/// assert_eq!(key.as_str(), r"key\xff");
+///
+/// let mut map = HashMap::new();
+/// map.insert(key, 1);
+/// assert_eq!(map.get(r"key\xff"), Some(&1));
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ByteAsciiString(String);
+
+impl ByteAsciiString {
+    /// Escape `bytes` using [`byteascii`].
+    pub fn new(bytes: impl AsRef<[u8]>) -> Self {
+        Self(byteascii(bytes.as_ref()))
+    }
+
+    /// Return the escaped string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Return the escaped string, consuming `self`.
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    /// Decode the escaped string back into the original bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        unbyteascii(&self.0).expect("ByteAsciiString always contains valid escape sequences")
+    }
+}
+
+impl fmt::Display for ByteAsciiString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for ByteAsciiString {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for ByteAsciiString {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<B> From<ByteAscii<B>> for ByteAsciiString
+where
+    B: AsRef<[u8]>,
+{
+    fn from(bytes: ByteAscii<B>) -> Self {
+        Self::new(bytes.0)
+    }
+}
+
+impl From<ByteAsciiString> for String {
+    fn from(s: ByteAsciiString) -> Self {
+        s.0
+    }
+}
+
+#[test]
+fn test_byteascii() {
+    let mut all_bytes: [u8; 256] = [0; 256];
+    for (i, b) in all_bytes.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+    expect_test::expect![[r##"\0\x01\x02\x03\x04\x05\x06\x07\x08\t\n\x0b\x0c\r\x0e\x0f\x10\x11\x12\x13\x14\x15\x16\x17\x18\x19\x1a\x1b\x1c\x1d\x1e\x1f !"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~\x7f\x80\x81\x82\x83\x84\x85\x86\x87\x88\x89\x8a\x8b\x8c\x8d\x8e\x8f\x90\x91\x92\x93\x94\x95\x96\x97\x98\x99\x9a\x9b\x9c\x9d\x9e\x9f\xa0\xa1\xa2\xa3\xa4\xa5\xa6\xa7\xa8\xa9\xaa\xab\xac\xad\xae\xaf\xb0\xb1\xb2\xb3\xb4\xb5\xb6\xb7\xb8\xb9\xba\xbb\xbc\xbd\xbe\xbf\xc0\xc1\xc2\xc3\xc4\xc5\xc6\xc7\xc8\xc9\xca\xcb\xcc\xcd\xce\xcf\xd0\xd1\xd2\xd3\xd4\xd5\xd6\xd7\xd8\xd9\xda\xdb\xdc\xdd\xde\xdf\xe0\xe1\xe2\xe3\xe4\xe5\xe6\xe7\xe8\xe9\xea\xeb\xec\xed\xee\xef\xf0\xf1\xf2\xf3\xf4\xf5\xf6\xf7\xf8\xf9\xfa\xfb\xfc\xfd\xfe\xff"##]].assert_eq(&byteascii(&all_bytes));
+}
+
+#[test]
+fn test_byteascii_newtype() {
+    let mut all_bytes: [u8; 256] = [0; 256];
+    for (i, b) in all_bytes.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+    expect_test::expect![[r##"
+        \0\x01\x02\x03\x04\x05\x06\x07\x08\t\n\x0b\x0c\r\x0e\x0f\x10\x11\x12\x13\x14\x15\x16\x17\x18\x19\x1a\x1b\x1c\x1d\x1e\x1f !"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~\x7f\x80\x81\x82\x83\x84\x85\x86\x87\x88\x89\x8a\x8b\x8c\x8d\x8e\x8f\x90\x91\x92\x93\x94\x95\x96\x97\x98\x99\x9a\x9b\x9c\x9d\x9e\x9f\xa0\xa1\xa2\xa3\xa4\xa5\xa6\xa7\xa8\xa9\xaa\xab\xac\xad\xae\xaf\xb0\xb1\xb2\xb3\xb4\xb5\xb6\xb7\xb8\xb9\xba\xbb\xbc\xbd\xbe\xbf\xc0\xc1\xc2\xc3\xc4\xc5\xc6\xc7\xc8\xc9\xca\xcb\xcc\xcd\xce\xcf\xd0\xd1\xd2\xd3\xd4\xd5\xd6\xd7\xd8\xd9\xda\xdb\xdc\xdd\xde\xdf\xe0\xe1\xe2\xe3\xe4\xe5\xe6\xe7\xe8\xe9\xea\xeb\xec\xed\xee\xef\xf0\xf1\xf2\xf3\xf4\xf5\xf6\xf7\xf8\xf9\xfa\xfb\xfc\xfd\xfe\xff
+    "##]].assert_debug_eq(& ByteAscii(&all_bytes));
+}
+
+#[test]
+fn test_unbyteascii() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    assert_eq!(all_bytes, unbyteascii(&byteascii(&all_bytes)).unwrap());
+
+    assert_eq!(b"", &*unbyteascii("").unwrap());
+    assert_eq!(b"\xab\xcd", &*unbyteascii(r"\xAB\xcD").unwrap());
+    assert_eq!("caf\u{e9}".as_bytes(), &*unbyteascii("caf\u{e9}").unwrap());
+
+    assert_eq!(
+        Err(DecodeError::UnknownEscape { position: 3 }),
+        unbyteascii(r"abc\q")
+    );
+    assert_eq!(
+        Err(DecodeError::IncompleteEscape { position: 1 }),
+        unbyteascii(r"a\")
+    );
+    assert_eq!(
+        Err(DecodeError::IncompleteEscape { position: 0 }),
+        unbyteascii(r"\x1")
+    );
+    assert_eq!(
+        Err(DecodeError::InvalidHex { position: 0 }),
+        unbyteascii(r"\x1g")
+    );
+    assert_eq!(
+        Err(DecodeError::InvalidHex { position: 0 }),
+        unbyteascii(r"\x+1")
+    );
+    assert_eq!(
+        Err(DecodeError::InvalidHex { position: 0 }),
+        unbyteascii("\\x\u{e9}")
+    );
+}
+
+#[test]
+fn test_byteascii_display() {
+    let bytes = b"Hello\n\xde\xad";
+    assert_eq!(r"Hello\n\xde\xad", ByteAscii(bytes).to_string());
+    assert_eq!(
+        format!("{:?}", ByteAscii(bytes)),
+        format!("{}", ByteAscii(bytes))
+    );
+
+    let s = ByteAsciiString::from(ByteAscii(bytes));
+    assert_eq!(ByteAsciiString::new(bytes), s);
+    assert_eq!(r"Hello\n\xde\xad", s.as_str());
+    assert_eq!(r"Hello\n\xde\xad", s.to_string());
+    assert_eq!(bytes, &*s.to_bytes());
+    assert_eq!(r"Hello\n\xde\xad", String::from(s));
+}
+
+#[test]
+fn test_byteascii_wrap() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    let wrapped = ByteAscii(&all_bytes)
+        .wrap(16)
+        .with_offsets(true)
+        .to_string();
+    let lines: Vec<&str> = wrapped.lines().collect();
+    assert_eq!(16, lines.len());
+    assert_eq!(r"00000040: @ABCDEFGHIJKLMNO", lines[4]);
+    assert_eq!(
+        r"000000f0: \xf0\xf1\xf2\xf3\xf4\xf5\xf6\xf7\xf8\xf9\xfa\xfb\xfc\xfd\xfe\xff",
+        lines[15]
+    );
+
+    // Removing the line breaks restores the unwrapped output
+    let wrapped = ByteAscii(&all_bytes).wrap(7).to_string();
+    assert_eq!(byteascii(&all_bytes), wrapped.replace('\n', ""));
+
+    assert_eq!("", ByteAscii(b"").wrap(3).with_offsets(true).to_string());
+    assert_eq!("abc", ByteAscii(b"abc").wrap(3).to_string());
+}
+
+#[test]
+fn test_escape_iter() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    assert_eq!(256, escape_iter(&all_bytes).len());
+    assert_eq!(Some(r"\xff"), escape_iter(&all_bytes).next_back());
+    assert_eq!(
+        byteascii(&all_bytes),
+        escape_display(&all_bytes).to_string()
+    );
+    assert_eq!(None, escape_iter(b"").next());
+}
+
+#[test]
+fn test_byteascii_into() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+    let expected = byteascii(&all_bytes);
+    assert_eq!(expected.len(), escaped_len(&all_bytes));
+    assert_eq!(0, escaped_len(b""));
+
+    let mut out = String::from(">");
+    byteascii_into(&all_bytes, &mut out);
+    assert_eq!(format!(">{expected}"), out);
+
+    let mut out = String::new();
+    byteascii_write(&all_bytes, &mut out).unwrap();
+    assert_eq!(expected, out);
+}
+
+#[test]
+fn test_byteascii_colored() {
+    let colored = |bytes: &[u8]| ByteAscii(bytes).colored().with_color(true).to_string();
+    assert_eq!("", colored(b""));
+    assert_eq!("plain", colored(b"plain"));
+    assert_eq!("\x1b[33m\\n\x1b[0m", colored(b"\n"));
+    assert_eq!(
+        "a\x1b[33m\\t\\\\\x1b[0mb\x1b[33m\\xff\x1b[0m",
+        colored(b"a\t\\b\xff")
+    );
+    assert_eq!(
+        r"a\t\\b\xff",
+        ByteAscii(b"a\t\\b\xff")
+            .colored()
+            .with_color(false)
+            .to_string()
+    );
+}
+
+#[test]
+fn test_diff() {
+    assert_eq!(
+        "both inputs are equal (3 bytes)",
+        diff(b"abc", b"abc").to_string()
+    );
+
+    let left: Vec<u8> = (0..=255).collect();
+    let mut right = left.clone();
+    right[100] = 0;
+    expect_test::expect![[r#"
+        first difference at offset 100 (left: 256 bytes, right: 256 bytes)
+         left: ...TUVWXYZ[\\]^_`abcdefghijklmnopqrs...
+        right: ...TUVWXYZ[\\]^_`abc\0efghijklmnopqrs...
+                                   ^"#]]
+    .assert_eq(&diff(&left, &right).to_string());
+
+    expect_test::expect![[r#"
+        first difference at offset 3 (left: 3 bytes, right: 5 bytes)
+         left: abc
+        right: abc\n\xff
+                  ^"#]]
+    .assert_eq(&diff(b"abc", b"abc\n\xff").to_string());
+}
+
+#[test]
+fn test_byteascii_preview() {
+    let bytes = b"abc\n\xff";
+    assert_eq!("", ByteAscii(b"").preview(0).to_string());
+    assert_eq!("… (5 bytes)", ByteAscii(bytes).preview(0).to_string());
+    assert_eq!(r"abc\n… (5 bytes)", ByteAscii(bytes).preview(4).to_string());
+    assert_eq!(r"abc\n\xff", ByteAscii(bytes).preview(5).to_string());
+    assert_eq!(
+        r"abc\n\xff",
+        ByteAscii(bytes).preview(usize::MAX).to_string()
+    );
+}
+
+#[test]
+fn test_literals() {
+    let all_bytes: Vec<u8> = (0..=255).collect();
+
+    let c = c_literal(&all_bytes);
+    assert!(c.is_ascii());
+    assert!(c.starts_with(r#""\000\001\002"#));
+    assert!(c.contains(r##" !\"#$%&'()*+,-./0123456789:;<=>\?@"##));
+    assert!(c.ends_with(r#"\375\376\377""#));
+
+    let rust = rust_byte_literal(&all_bytes);
+    assert_eq!(
+        format!("b\"{}\"", byteascii(&all_bytes).replace('"', "\\\"")),
+        rust
+    );
+
+    let json = json_string(&all_bytes);
+    assert!(json.is_ascii());
+    #[cfg(feature = "serde_json")]
+    {
+        let decoded: String = serde_json::from_str(&json).unwrap();
+        let decoded: Vec<u8> = decoded.chars().map(|c| c as u8).collect();
+        assert_eq!(all_bytes, decoded);
+    }
+
+    assert_eq!(b"''", &*shell_quote(b"").unwrap());
+    assert_eq!(br"''\'''\'''", &*shell_quote(b"''").unwrap());
+    assert_eq!(b"'a\nb\xff'", &*shell_quote(b"a\nb\xff").unwrap());
+    assert_eq!(None, shell_quote(&all_bytes));
+}
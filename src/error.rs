@@ -17,12 +17,12 @@ pub enum Error {
         path: PathBuf,
     },
     /// Wrapper around [io::Error]
-    #[error("{msg} while operating on file {}", file.display())]
+    #[error("Could not {operation} {}", file.display())]
     FileIo {
         /// File which caused the error
         file: PathBuf,
-        /// Message describing what went wrong
-        msg: &'static str,
+        /// Operation which failed
+        operation: Operation,
         /// Underlying source [io::Error]
         #[source]
         source: io::Error,
@@ -35,6 +35,18 @@ pub enum Error {
         /// Name of the compression technique
         technique: &'static str,
     },
+    /// The detected filetype of a file does not match its extension
+    ///
+    /// This error is only returned in strict mode, see [`ReadBuilder::strict`](crate::fs::ReadBuilder::strict).
+    #[error("File {} is expected to be type `{expected}` based on the extension, but is detected to be type `{detected}`.", file.display())]
+    FormatMismatch {
+        /// File which is opened for reading
+        file: PathBuf,
+        /// Filetype indicated by the file extension
+        expected: &'static str,
+        /// Filetype detected from the file content
+        detected: &'static str,
+    },
     #[cfg(feature = "file-xz")]
     /// Error when creating a XZ reader
     ///
@@ -47,6 +59,101 @@ pub enum Error {
         #[source]
         source: xz2::stream::Error,
     },
+    /// Decoding a xz file requires more memory than allowed
+    ///
+    /// See [`ReadBuilder::xz_memlimit`](crate::fs::ReadBuilder::xz_memlimit).
+    /// This variant only exists if the `file-xz` feature is enabled.
+    #[cfg(feature = "file-xz")]
+    #[error("Decoding the xz file {} requires more memory than the limit of {memlimit} bytes", file.display())]
+    XzMemLimitError {
+        /// File which is opened for reading
+        file: PathBuf,
+        /// Configured memory limit in bytes
+        memlimit: u64,
+    },
+    /// Error when serializing or deserializing a JSON file
+    ///
+    /// This variant only exists if the `json` or `jsonl` feature is enabled.
+    #[cfg(any(feature = "json", feature = "jsonl"))]
+    #[error("Failed to process the JSON file {}", file.display())]
+    JsonError {
+        /// File which is read or written
+        file: PathBuf,
+        /// Original cause of the error
+        #[source]
+        source: serde_json::Error,
+    },
+    /// Error when deserializing a TOML file
+    ///
+    /// This variant only exists if the `toml` feature is enabled.
+    #[cfg(feature = "toml")]
+    #[error("Failed to parse the TOML file {}", file.display())]
+    TomlError {
+        /// File which is parsed
+        file: PathBuf,
+        /// Original cause of the error
+        #[source]
+        source: Box<toml::de::Error>,
+    },
+    /// Error when deserializing a YAML file
+    ///
+    /// This variant only exists if the `yaml` feature is enabled.
+    #[cfg(feature = "yaml")]
+    #[error("Failed to parse the YAML file {}", file.display())]
+    YamlError {
+        /// File which is parsed
+        file: PathBuf,
+        /// Original cause of the error
+        #[source]
+        source: serde_yaml_ng::Error,
+    },
+    /// Error when serializing a value into a CBOR sequence
+    ///
+    /// This variant only exists if the `cbor` feature is enabled.
+    #[cfg(feature = "cbor")]
+    #[error("Failed to serialize a CBOR value for file {}", file.display())]
+    CborError {
+        /// File which is written
+        file: PathBuf,
+        /// Original cause of the error
+        #[source]
+        source: ciborium::ser::Error<io::Error>,
+    },
+    /// Error when converting a JSONL file into Arrow record batches
+    ///
+    /// This variant only exists if the `parquet` feature is enabled.
+    #[cfg(feature = "parquet")]
+    #[error("Failed to convert the JSONL file {} into Arrow records", file.display())]
+    ArrowError {
+        /// File which is read
+        file: PathBuf,
+        /// Original cause of the error
+        #[source]
+        source: arrow_schema::ArrowError,
+    },
+    /// Error when writing a Parquet file
+    ///
+    /// This variant only exists if the `parquet` feature is enabled.
+    #[cfg(feature = "parquet")]
+    #[error("Failed to write the Parquet file {}", file.display())]
+    ParquetError {
+        /// File which is written
+        file: PathBuf,
+        /// Original cause of the error
+        #[source]
+        source: parquet::errors::ParquetError,
+    },
+    /// The glob pattern is invalid
+    ///
+    /// This variant only exists if the `async-fs` feature is enabled.
+    #[cfg(feature = "async-fs")]
+    #[error("The glob pattern `{pattern}` is invalid")]
+    GlobPatternError {
+        /// The invalid pattern
+        pattern: String,
+        /// Original cause of the error
+        source: glob::PatternError,
+    },
     /// Error when joining an async task
     ///
     /// This variant only exists if the `async-fs` feature is enabled.
@@ -59,6 +166,61 @@ pub enum Error {
     },
 }
 
+/// The filesystem operation which failed in [`Error::FileIo`]
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Opening or creating a file
+    Open,
+    /// Reading from a file
+    Read,
+    /// Seeking to a position in a file
+    Seek,
+    /// Writing to a file
+    Write,
+    /// Flushing buffered data and finishing the compression stream
+    Flush,
+    /// Moving a file to its final location
+    Rename,
+    /// Syncing a file to disk
+    Sync,
+    /// Accessing the metadata of a file
+    Metadata,
+    /// Changing the permissions or timestamps of a file
+    SetMetadata,
+    /// Listing the entries of a directory
+    ReadDir,
+    /// Creating a directory
+    CreateDir,
+    /// Removing a file or directory
+    Remove,
+    /// Locking a file
+    Lock,
+    /// Unlocking a file
+    Unlock,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Operation::Open => "open",
+            Operation::Read => "read",
+            Operation::Seek => "seek in",
+            Operation::Write => "write",
+            Operation::Flush => "flush",
+            Operation::Rename => "rename",
+            Operation::Sync => "sync",
+            Operation::Metadata => "access the metadata of",
+            Operation::SetMetadata => "set the metadata of",
+            Operation::ReadDir => "read the directory",
+            Operation::CreateDir => "create the directory",
+            Operation::Remove => "remove",
+            Operation::Lock => "lock",
+            Operation::Unlock => "unlock",
+        })
+    }
+}
+
 /// Error value for elements returned by [`MtJsonl`](crate::fs::MtJsonl).
 ///
 /// Please see the individual variants for details.
@@ -81,11 +243,610 @@ pub enum MtJsonlError {
 
     /// Some error occured while parsing a JSON value
     /// Created in the parsing thread based on a [`serde_json::Error`]
-    #[error("Could not parse a JSON value")]
+    #[error("Could not parse a JSON value in line {line} at byte offset {offset}")]
     ParsingError {
+        /// Line number of the error in the file, starting at 1
+        line: u64,
+        /// Byte offset of the start of the line in the decompressed file
+        offset: u64,
+        /// Excerpt of the line, with at most 256 bytes before and after the error position
+        excerpt: String,
+        /// Byte position of the error in `excerpt`, if reported by the parsing library
+        position: Option<usize>,
         /// Error message of the parsing library
-        #[from]
         #[source]
         source: serde_json::Error,
     },
+
+    /// A JSON value was parsed, but rejected by the validator
+    /// Created in the parsing thread, see [`MtJsonlBuilder::validate`](crate::fs::MtJsonlBuilder::validate)
+    #[error("Invalid JSON value in line {line} at byte offset {offset}")]
+    ValidationError {
+        /// Line number of the value in the file, starting at 1
+        line: u64,
+        /// Byte offset of the start of the line in the decompressed file
+        offset: u64,
+        /// Error returned by the validator
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+#[cfg(feature = "jsonl")]
+impl MtJsonlError {
+    /// Maximum number of bytes of the line kept before and after the error position
+    const EXCERPT_CONTEXT: usize = 256;
+
+    /// Create a [`MtJsonlError::ParsingError`] for an error in the line `text`.
+    ///
+    /// Only an excerpt of the line around the position reported by serde_json is kept.
+    pub(crate) fn parsing_error(
+        line: u64,
+        offset: u64,
+        text: &str,
+        source: serde_json::Error,
+    ) -> Self {
+        let floor_char_boundary = |mut idx: usize| {
+            while !text.is_char_boundary(idx) {
+                idx -= 1;
+            }
+            idx
+        };
+
+        // serde_json reports a column of 0 if the position is unknown, otherwise the columns count bytes starting at 1
+        let position = (source.line() > 0 && source.column() > 0)
+            .then(|| floor_char_boundary((source.column() - 1).min(text.len())));
+        let center = position.unwrap_or(0);
+        let start = floor_char_boundary(center.saturating_sub(Self::EXCERPT_CONTEXT));
+        let mut end = (center + Self::EXCERPT_CONTEXT).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        MtJsonlError::ParsingError {
+            line,
+            offset,
+            excerpt: text[start..end].to_string(),
+            position: position.map(|position| position - start),
+            source,
+        }
+    }
+}
+
+/// Error value for elements returned by [`MtCsv`](crate::fs::MtCsv).
+///
+/// Please see the individual variants for details.
+#[cfg(feature = "csv")]
+#[allow(variant_size_differences)]
+#[derive(Debug, thiserror::Error)]
+pub enum MtCsvError {
+    /// Indicates some error while processing the file.
+    /// Not all records in the file were processed.
+    #[error("Reading the file has failed and not all records could be read.")]
+    NotCompleted,
+
+    /// Some error occured while opening or reading the file.
+    #[error(transparent)]
+    IoError {
+        /// Source Error
+        #[from]
+        source: Error,
+    },
+
+    /// Some error occured while parsing a CSV record
+    ///
+    /// The error contains the position of the record, if available.
+    #[error("Could not parse a CSV record")]
+    ParsingError {
+        /// Error message of the parsing library
+        #[from]
+        #[source]
+        source: csv::Error,
+    },
+}
+
+/// Error value for elements returned by [`MtCborSeq`](crate::fs::MtCborSeq).
+///
+/// Please see the individual variants for details.
+#[cfg(feature = "cbor")]
+#[allow(variant_size_differences)]
+#[derive(Debug, thiserror::Error)]
+pub enum MtCborError {
+    /// Indicates some error while processing the file.
+    /// Not all items in the file were processed.
+    #[error("Reading the file has failed and not all items could be read.")]
+    NotCompleted,
+
+    /// Some error occured while opening or reading the file.
+    #[error(transparent)]
+    IoError {
+        /// Source Error
+        #[from]
+        source: Error,
+    },
+
+    /// The file contains malformed CBOR data
+    ///
+    /// The remaining items of the sequence cannot be read after this error.
+    #[error("Could not decode a CBOR item")]
+    DecodingError {
+        /// Error message of the parsing library
+        #[source]
+        source: ciborium::de::Error<io::Error>,
+    },
+
+    /// A CBOR item could not be converted into the requested type
+    #[error("Could not deserialize a CBOR item")]
+    ParsingError {
+        /// Error message of the parsing library
+        #[from]
+        #[source]
+        source: ciborium::value::Error,
+    },
+}
+
+/// Error type when decoding the escaped notation of [`byteascii`](crate::byteascii::byteascii).
+///
+/// The positions are byte offsets of the backslash which starts the invalid escape sequence.
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum DecodeError {
+    /// The backslash is followed by an unknown character
+    #[error("Unknown escape sequence at position {position}")]
+    UnknownEscape {
+        /// Position of the escape sequence
+        position: usize,
+    },
+    /// The input ends in the middle of an escape sequence
+    #[error("Incomplete escape sequence at position {position}")]
+    IncompleteEscape {
+        /// Position of the escape sequence
+        position: usize,
+    },
+    /// A `\x` escape is not followed by two hexadecimal digits
+    #[error("Invalid hexadecimal escape sequence at position {position}")]
+    InvalidHex {
+        /// Position of the escape sequence
+        position: usize,
+    },
+}
+
+/// Errors which can be classified as transient, such that retrying the operation may succeed.
+///
+/// This allows retry loops to be generic over the error types of this crate.
+pub trait Retryable {
+    /// Return `true` if the error is likely temporary and the operation can be retried.
+    fn is_transient(&self) -> bool;
+}
+
+impl Error {
+    /// Return `true` if the error is likely temporary and the operation can be retried.
+    ///
+    /// An error is transient if the underlying [`io::Error`] indicates an interrupted system call (`EINTR`),
+    /// a non-blocking operation which would block (`EAGAIN`), a timeout, e.g., of a network filesystem,
+    /// or contention on a lock or a busy resource.
+    /// All other errors, like a missing file or a malformed file, are permanent.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::FileIo { source, .. } => source.is_transient(),
+            #[cfg(feature = "cbor")]
+            Error::CborError {
+                source: ciborium::ser::Error::Io(source),
+                ..
+            } => source.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+impl Retryable for Error {
+    fn is_transient(&self) -> bool {
+        Error::is_transient(self)
+    }
+}
+
+impl Retryable for io::Error {
+    fn is_transient(&self) -> bool {
+        use io::ErrorKind;
+
+        if matches!(
+            self.kind(),
+            ErrorKind::Interrupted
+                | ErrorKind::WouldBlock
+                | ErrorKind::TimedOut
+                | ErrorKind::ResourceBusy
+                | ErrorKind::Deadlock
+        ) {
+            return true;
+        }
+        #[cfg(windows)]
+        {
+            // Another process has the file opened or locked a region of it
+            const ERROR_SHARING_VIOLATION: i32 = 32;
+            const ERROR_LOCK_VIOLATION: i32 = 33;
+            if matches!(
+                self.raw_os_error(),
+                Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION)
+            ) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(feature = "jsonl")]
+impl Retryable for MtJsonlError {
+    fn is_transient(&self) -> bool {
+        match self {
+            MtJsonlError::IoError { source } => source.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "csv")]
+impl Retryable for MtCsvError {
+    fn is_transient(&self) -> bool {
+        match self {
+            MtCsvError::IoError { source } => source.is_transient(),
+            MtCsvError::ParsingError { source } => match source.kind() {
+                csv::ErrorKind::Io(source) => source.is_transient(),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "cbor")]
+impl Retryable for MtCborError {
+    fn is_transient(&self) -> bool {
+        match self {
+            MtCborError::IoError { source } => source.is_transient(),
+            MtCborError::DecodingError {
+                source: ciborium::de::Error::Io(source),
+            } => source.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+/// Convert the error into an [`io::Error`] with a matching [`io::ErrorKind`].
+///
+/// The original error is kept as the inner error of the [`io::Error`] and can be recovered with [`io::Error::into_inner`] and downcasting.
+/// This allows returning errors of this crate from functions whose signature is fixed to [`io::Error`].
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        let kind = match &err {
+            Error::NotAFileError { .. } => io::ErrorKind::InvalidInput,
+            Error::FileIo { source, .. } => source.kind(),
+            Error::CompressionNotEnabled { .. } => io::ErrorKind::Unsupported,
+            Error::FormatMismatch { .. } => io::ErrorKind::InvalidData,
+            #[cfg(feature = "file-xz")]
+            Error::XzError { .. } => io::ErrorKind::Other,
+            #[cfg(feature = "file-xz")]
+            Error::XzMemLimitError { .. } => io::ErrorKind::OutOfMemory,
+            #[cfg(any(feature = "json", feature = "jsonl"))]
+            Error::JsonError { source, .. } => match source.classify() {
+                serde_json::error::Category::Io => {
+                    source.io_error_kind().unwrap_or(io::ErrorKind::Other)
+                }
+                serde_json::error::Category::Eof => io::ErrorKind::UnexpectedEof,
+                serde_json::error::Category::Syntax | serde_json::error::Category::Data => {
+                    io::ErrorKind::InvalidData
+                }
+            },
+            #[cfg(feature = "toml")]
+            Error::TomlError { .. } => io::ErrorKind::InvalidData,
+            #[cfg(feature = "yaml")]
+            Error::YamlError { .. } => io::ErrorKind::InvalidData,
+            #[cfg(feature = "cbor")]
+            Error::CborError { source, .. } => match source {
+                ciborium::ser::Error::Io(source) => source.kind(),
+                ciborium::ser::Error::Value(_) => io::ErrorKind::InvalidData,
+            },
+            #[cfg(feature = "parquet")]
+            Error::ArrowError { .. } => io::ErrorKind::InvalidData,
+            #[cfg(feature = "parquet")]
+            Error::ParquetError { .. } => io::ErrorKind::Other,
+            #[cfg(feature = "async-fs")]
+            Error::GlobPatternError { .. } => io::ErrorKind::InvalidInput,
+            #[cfg(feature = "async-fs")]
+            Error::JoinError { .. } => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+/// Serialize an error as a map with the keys `kind`, `path`, `os_error`, and `message`.
+///
+/// `kind` is the name of the variant, `path` the affected file if any, `os_error` the raw OS error code if the error was caused by the operating system,
+/// and `message` the error message including the messages of all sources.
+#[cfg(feature = "serde")]
+fn serialize_error<S>(
+    serializer: S,
+    kind: &'static str,
+    path: Option<&std::path::Path>,
+    os_error: Option<i32>,
+    error: &dyn std::error::Error,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    use serde::ser::SerializeStruct;
+
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(err) = source {
+        message.push_str(": ");
+        message.push_str(&err.to_string());
+        source = err.source();
+    }
+
+    let mut s = serializer.serialize_struct("Error", 4)?;
+    s.serialize_field("kind", kind)?;
+    s.serialize_field("path", &path.map(|path| path.to_string_lossy()))?;
+    s.serialize_field("os_error", &os_error)?;
+    s.serialize_field("message", &message)?;
+    s.end()
+}
+
+#[cfg(any(feature = "miette", feature = "serde"))]
+impl Error {
+    /// Return the name of the variant.
+    fn variant_name(&self) -> &'static str {
+        match self {
+            Error::NotAFileError { .. } => "NotAFileError",
+            Error::FileIo { .. } => "FileIo",
+            Error::CompressionNotEnabled { .. } => "CompressionNotEnabled",
+            Error::FormatMismatch { .. } => "FormatMismatch",
+            #[cfg(feature = "file-xz")]
+            Error::XzError { .. } => "XzError",
+            #[cfg(feature = "file-xz")]
+            Error::XzMemLimitError { .. } => "XzMemLimitError",
+            #[cfg(any(feature = "json", feature = "jsonl"))]
+            Error::JsonError { .. } => "JsonError",
+            #[cfg(feature = "toml")]
+            Error::TomlError { .. } => "TomlError",
+            #[cfg(feature = "yaml")]
+            Error::YamlError { .. } => "YamlError",
+            #[cfg(feature = "cbor")]
+            Error::CborError { .. } => "CborError",
+            #[cfg(feature = "parquet")]
+            Error::ArrowError { .. } => "ArrowError",
+            #[cfg(feature = "parquet")]
+            Error::ParquetError { .. } => "ParquetError",
+            #[cfg(feature = "async-fs")]
+            Error::GlobPatternError { .. } => "GlobPatternError",
+            #[cfg(feature = "async-fs")]
+            Error::JoinError { .. } => "JoinError",
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Error {
+    /// Return the name of the variant, the affected file, and the OS error code.
+    fn serialize_fields(&self) -> (&'static str, Option<&std::path::Path>, Option<i32>) {
+        let (path, os_error) = match self {
+            Error::NotAFileError { path } => (Some(&**path), None),
+            Error::FileIo { file, source, .. } => (Some(&**file), source.raw_os_error()),
+            Error::CompressionNotEnabled { file, .. } | Error::FormatMismatch { file, .. } => {
+                (Some(&**file), None)
+            }
+            #[cfg(feature = "file-xz")]
+            Error::XzError { file, .. } | Error::XzMemLimitError { file, .. } => {
+                (Some(&**file), None)
+            }
+            #[cfg(any(feature = "json", feature = "jsonl"))]
+            Error::JsonError { file, .. } => (Some(&**file), None),
+            #[cfg(feature = "toml")]
+            Error::TomlError { file, .. } => (Some(&**file), None),
+            #[cfg(feature = "yaml")]
+            Error::YamlError { file, .. } => (Some(&**file), None),
+            #[cfg(feature = "cbor")]
+            Error::CborError { file, source } => {
+                let os_error = match source {
+                    ciborium::ser::Error::Io(source) => source.raw_os_error(),
+                    _ => None,
+                };
+                (Some(&**file), os_error)
+            }
+            #[cfg(feature = "parquet")]
+            Error::ArrowError { file, .. } | Error::ParquetError { file, .. } => {
+                (Some(&**file), None)
+            }
+            #[cfg(feature = "async-fs")]
+            Error::GlobPatternError { .. } | Error::JoinError { .. } => (None, None),
+        };
+        (self.variant_name(), path, os_error)
+    }
+}
+
+/// Serialize the error as a map with the keys `kind`, `path`, `os_error`, and `message`.
+///
+/// `kind` is the name of the variant, `path` the affected file if any, `os_error` the raw OS error code if the error was caused by the operating system,
+/// and `message` the error message including the messages of all sources.
+///
+/// This implementation is only available if the `serde` feature is enabled.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Error {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let (kind, path, os_error) = self.serialize_fields();
+        serialize_error(serializer, kind, path, os_error, self)
+    }
+}
+
+/// Serialize the error in the same shape as [`Error`].
+///
+/// For [`MtJsonlError::IoError`], the fields of the wrapped [`Error`] are used.
+///
+/// This implementation is only available if the `jsonl` feature is enabled.
+#[cfg(feature = "jsonl")]
+impl serde::Serialize for MtJsonlError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let (kind, path, os_error) = match self {
+            MtJsonlError::NotCompleted => ("NotCompleted", None, None),
+            MtJsonlError::IoError { source } => source.serialize_fields(),
+            MtJsonlError::ParsingError { .. } => ("ParsingError", None, None),
+            MtJsonlError::ValidationError { .. } => ("ValidationError", None, None),
+        };
+        serialize_error(serializer, kind, path, os_error, self)
+    }
+}
+
+/// Diagnostic codes are `misc_utils::` followed by the name of the variant.
+///
+/// This implementation is only available if the `miette` feature is enabled.
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(format!("misc_utils::{}", self.variant_name())))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        // The catch-all arm is unreachable if no optional error variants are enabled.
+        #[allow(unreachable_patterns)]
+        let help = match self {
+            Error::NotAFileError { .. } => "The path must point to a regular file.".to_string(),
+            Error::FileIo { source, .. } => match source.kind() {
+                io::ErrorKind::NotFound => "Check that the file exists.".to_string(),
+                io::ErrorKind::PermissionDenied => {
+                    "Check the permissions of the file and its directory.".to_string()
+                }
+                _ if source.is_transient() => {
+                    "The error is transient, retrying the operation may succeed.".to_string()
+                }
+                _ => return None,
+            },
+            Error::CompressionNotEnabled { technique, .. } => {
+                format!("Enable the `file-{technique}` feature of misc_utils to support this file type.")
+            }
+            Error::FormatMismatch { .. } => {
+                "Rename the file to match its content or disable strict mode with `ReadBuilder::strict`.".to_string()
+            }
+            #[cfg(feature = "file-xz")]
+            Error::XzMemLimitError { .. } => {
+                "Raise the memory limit with `ReadBuilder::xz_memlimit`.".to_string()
+            }
+            _ => return None,
+        };
+        Some(Box::new(help))
+    }
+}
+
+/// This implementation is only available if the `miette` feature is enabled.
+#[cfg(all(feature = "miette", feature = "jsonl"))]
+impl miette::Diagnostic for MtJsonlError {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        match self {
+            MtJsonlError::NotCompleted => Some(Box::new("misc_utils::jsonl::NotCompleted")),
+            MtJsonlError::IoError { source } => miette::Diagnostic::code(source),
+            MtJsonlError::ParsingError { .. } => Some(Box::new("misc_utils::jsonl::ParsingError")),
+            MtJsonlError::ValidationError { .. } => {
+                Some(Box::new("misc_utils::jsonl::ValidationError"))
+            }
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        match self {
+            MtJsonlError::IoError { source } => miette::Diagnostic::help(source),
+            MtJsonlError::ParsingError { line, source, .. } => Some(Box::new(format!(
+                "Fix the JSON value in line {line} at column {}.",
+                source.column()
+            ))),
+            _ => None,
+        }
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        match self {
+            MtJsonlError::IoError { source } => miette::Diagnostic::source_code(source),
+            MtJsonlError::ParsingError { excerpt, .. } => Some(excerpt),
+            _ => None,
+        }
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        match self {
+            MtJsonlError::IoError { source } => miette::Diagnostic::labels(source),
+            MtJsonlError::ParsingError {
+                position: Some(position),
+                source,
+                ..
+            } => {
+                let label = match source.classify() {
+                    serde_json::error::Category::Eof => "the line ends here",
+                    serde_json::error::Category::Data => "unexpected value",
+                    serde_json::error::Category::Syntax | serde_json::error::Category::Io => {
+                        "invalid JSON"
+                    }
+                };
+                Some(Box::new(std::iter::once(miette::LabeledSpan::at_offset(
+                    *position, label,
+                ))))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// This implementation is only available if the `miette` feature is enabled.
+#[cfg(all(feature = "miette", feature = "csv"))]
+impl miette::Diagnostic for MtCsvError {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        match self {
+            MtCsvError::NotCompleted => Some(Box::new("misc_utils::csv::NotCompleted")),
+            MtCsvError::IoError { source } => miette::Diagnostic::code(source),
+            MtCsvError::ParsingError { .. } => Some(Box::new("misc_utils::csv::ParsingError")),
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        match self {
+            MtCsvError::IoError { source } => miette::Diagnostic::help(source),
+            _ => None,
+        }
+    }
+}
+
+/// This implementation is only available if the `miette` feature is enabled.
+#[cfg(all(feature = "miette", feature = "cbor"))]
+impl miette::Diagnostic for MtCborError {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        match self {
+            MtCborError::NotCompleted => Some(Box::new("misc_utils::cbor::NotCompleted")),
+            MtCborError::IoError { source } => miette::Diagnostic::code(source),
+            MtCborError::DecodingError { .. } => Some(Box::new("misc_utils::cbor::DecodingError")),
+            MtCborError::ParsingError { .. } => Some(Box::new("misc_utils::cbor::ParsingError")),
+        }
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        match self {
+            MtCborError::IoError { source } => miette::Diagnostic::help(source),
+            _ => None,
+        }
+    }
+}
+
+/// This implementation is only available if the `miette` feature is enabled.
+#[cfg(feature = "miette")]
+impl miette::Diagnostic for DecodeError {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new("misc_utils::byteascii::DecodeError"))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new(
+            r"Valid escape sequences are \0, \t, \n, \r, \\, and \xHH.",
+        ))
+    }
 }
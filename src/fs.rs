@@ -1,5 +1,7 @@
 //! This module contains functions related to filesystem operations.
 //!
+//! All functions accept paths as `impl AsRef<Path>`, so UTF-8 paths like `camino::Utf8Path` can be used directly.
+//!
 //! ## [`file_open_read`] / [`file_open_read_with_capacity`]
 //!
 //! These functions are convenience wrappers around file I/O. They allow reading of compressed
@@ -9,6 +11,7 @@
 //! The support for for the different file formats is optional.
 //! By default `.gz` and `.xz` are enabled.
 //! The `file-*` features enable support for the corresponding file extensions.
+//! More options, like a strict check of the file extension, are available using [`file_read`].
 //!
 //! The example shows how to read a file into a string:
 //!
@@ -33,6 +36,7 @@
 //!
 //! ```no_run
 //! # use misc_utils::fs::file_write;
+//! # use std::io::Write;
 //! #
 //! # fn main() -> Result<(), anyhow::Error> {
 //! let mut writer = file_write("./text.txt").truncate()?;
@@ -43,6 +47,7 @@
 //!
 //! ```no_run
 //! # use misc_utils::fs::file_write;
+//! # use std::io::Write;
 //! #
 //! # fn main() -> Result<(), anyhow::Error> {
 //! let mut writer = file_write("./text.txt").append()?;
@@ -51,6 +56,14 @@
 //! # }
 //! ```
 //!
+//! ## [`copy`] / [`recompress`]
+//!
+//! Copy a file while converting between compression formats.
+//! The source is decompressed transparently and the destination is compressed based on its extension.
+//! [`recompress`] allows controlling the output format and preserving the metadata of the source file.
+//! [`move_file`] and [`move_recompress`] move files, also across filesystems.
+//! [`mirror`] synchronizes whole directory trees.
+//!
 //! ## [`parse_jsonl_multi_threaded`]
 //!
 //! Create multiple thread reading and parsing a [JSONL] file.
@@ -58,41 +71,158 @@
 //! This function is especially useful if the file is compressed with a high compression (such as
 //! xz2) and the parsing overhead is non-negligible. The inter-thread communication is batched to
 //! reduce overhead.
+//! [`parse_jsonl_multi_worker`] uses multiple parsing threads, while keeping the order of the file.
+//! All options are available using the [`MtJsonlBuilder`].
+//! [`parse_jsonl`] reads and parses the file on the calling thread instead.
+//! [`JsonlWriter`] writes JSONL files, optionally as one gzip member per batch.
+//! [`JsonlAppender`] appends to JSONL files shared between processes.
+//! [`split_jsonl`] splits large JSONL files into shards and [`merge_jsonl_sorted`] merges sorted shards.
+//! [`sample_jsonl`] draws a random sample from a JSONL file.
+//!
+//! ## [`parse_csv_multi_threaded`]
+//!
+//! The same design for CSV files, with the format configured by [`CsvOptions`].
+//! This requires the `csv` feature.
+//!
+//! ## [`parse_cbor_seq`] / [`CborSeqWriter`]
+//!
+//! Read and write [CBOR sequences], again using a reading and a parsing thread.
+//! This requires the `cbor` feature.
+//!
+//! ## [`jsonl_to_parquet`]
+//!
+//! Convert a (compressed) [JSONL] file into a [Parquet] file with a given Arrow schema.
+//! This requires the `parquet` feature.
 //!
 //! [`append`]: WriteBuilder::append
 //! [`truncate`]: WriteBuilder::truncate
 //!
+//! [CBOR sequences]: https://www.rfc-editor.org/rfc/rfc8742
 //! [JSONL]: http://jsonlines.org/
+//! [Parquet]: https://parquet.apache.org/
 
-use crate::error::Error;
-#[cfg(feature = "jsonl")]
-use crate::error::MtJsonlError;
+use crate::error::{Error, Operation};
+#[cfg(windows)]
+use crate::path::PathExt;
 #[cfg(feature = "file-bz2")]
 use bzip2::{bufread::BzDecoder, write::BzEncoder};
 #[cfg(feature = "file-gz")]
 use flate2::{bufread::MultiGzDecoder, write::GzEncoder};
-use log::debug;
-#[cfg(feature = "jsonl")]
-use log::{info, warn};
-#[cfg(feature = "jsonl")]
+use log::{debug, warn};
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
 use serde::de::DeserializeOwned;
-#[cfg(feature = "jsonl")]
-use serde_json::Deserializer;
 use std::{
+    borrow::Cow,
     ffi::OsStr,
-    fs::OpenOptions,
-    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    fmt,
+    fs::{File, FileTimes, OpenOptions},
+    io::{self, BufReader, IoSlice, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
 };
-#[cfg(feature = "jsonl")]
 use std::{io::BufRead, sync::mpsc, thread};
 #[cfg(feature = "file-xz")]
 use xz2::{
     bufread::XzDecoder,
-    stream::{Check, MtStreamBuilder},
+    stream::{Check, MtStreamBuilder, Stream},
     write::XzEncoder,
 };
 
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "csv")]
+mod csv;
+#[cfg(feature = "jsonl")]
+mod jsonl;
+mod mirror;
+#[cfg(feature = "parquet")]
+mod parquet;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod uring;
+
+#[cfg(feature = "cbor")]
+pub use self::cbor::{parse_cbor_seq, CborSeqWriter, MtCborSeq};
+#[cfg(feature = "csv")]
+pub use self::csv::{parse_csv_multi_threaded, CsvOptions, MtCsv};
+#[cfg(feature = "rayon")]
+pub use self::jsonl::parse_jsonl_parallel;
+#[cfg(feature = "jsonl")]
+pub use self::jsonl::{
+    merge_jsonl_sorted, parse_jsonl, parse_jsonl_multi_threaded, parse_jsonl_multi_threaded_many,
+    parse_jsonl_multi_worker, sample_jsonl, split_jsonl, Jsonl, JsonlAppender, JsonlEvent,
+    JsonlProgress, JsonlStats, JsonlWriter, MtJsonl, MtJsonlBuilder, MtJsonlMany,
+    MtJsonlPositioned, MtJsonlRaw, MtJsonlValidatedBuilder, Positioned, ShardSize,
+};
+pub use self::mirror::{mirror, MirrorCompare, MirrorOptions, MirrorStats};
+#[cfg(feature = "parquet")]
+pub use self::parquet::jsonl_to_parquet;
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+use self::uring::FileWriter;
+#[cfg(not(all(feature = "io-uring", target_os = "linux")))]
+use std::io::BufWriter;
+/// Writer for the underlying file of a [`WriteBuilder`].
+#[cfg(not(all(feature = "io-uring", target_os = "linux")))]
+type FileWriter = BufWriter<File>;
+
+/// Path used for the operating system calls.
+///
+/// On Windows, paths exceeding `MAX_PATH` are converted into extended-length paths.
+/// See [`PathExt::to_extended_length`].
+#[cfg(windows)]
+fn os_path(path: &Path) -> Cow<'_, Path> {
+    /// Maximal length of a path, including the terminating NUL character
+    const MAX_PATH: usize = 260;
+
+    match std::path::absolute(path) {
+        Ok(absolute) if absolute.as_os_str().len() >= MAX_PATH => {
+            Cow::Owned(absolute.to_extended_length())
+        }
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// Path used for the operating system calls.
+///
+/// The path is only changed on Windows.
+#[cfg(not(windows))]
+fn os_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+/// Magic bytes at the start of a `xz` file.
+const XZ_MAGIC: [u8; 6] = [0xfd, b'7', b'z', b'X', b'Z', 0x00];
+/// Magic bytes at the start of a `gz` file.
+const GZ_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// Magic bytes at the start of a `bz2` file.
+const BZ2_MAGIC: [u8; 3] = [b'B', b'Z', b'h'];
+
+/// Detect the compression technique based on the first bytes of a file.
+///
+/// Returns `None` if no known compression format matches.
+pub(crate) fn compression_from_magic(buffer: &[u8; 6]) -> Option<&'static str> {
+    if buffer[..6] == XZ_MAGIC {
+        Some("xz")
+    } else if buffer[..2] == GZ_MAGIC {
+        Some("gz")
+    } else if buffer[..3] == BZ2_MAGIC {
+        Some("bz2")
+    } else {
+        None
+    }
+}
+
+/// Detect the compression technique based on the extension of a file.
+///
+/// Returns `None` if the extension does not belong to a known compression format.
+pub(crate) fn compression_from_extension(path: &Path) -> Option<&'static str> {
+    match path.extension().and_then(OsStr::to_str) {
+        Some("xz") => Some("xz"),
+        Some("gzip") | Some("gz") => Some("gz"),
+        Some("bzip") | Some("bz2") => Some("bz2"),
+        _ => None,
+    }
+}
+
 /// Create reader for uncompressed or compressed files transparently.
 ///
 /// This function opens the given `file` and tries to determine the filetype by reading the magic
@@ -107,7 +237,7 @@ pub fn file_open_read<P>(file: P) -> Result<Box<dyn Read>, Error>
 where
     P: AsRef<Path>,
 {
-    do_file_open_read(file.as_ref(), None)
+    Ok(file_read(file).open()?.into_inner())
 }
 
 /// Create reader for uncompressed or compressed files transparently.
@@ -127,12 +257,181 @@ pub fn file_open_read_with_capacity<P>(
 where
     P: AsRef<Path>,
 {
-    do_file_open_read(file.as_ref(), Some(buffer_capacity))
+    Ok(file_read(file)
+        .buffer_capacity(buffer_capacity)
+        .open()?
+        .into_inner())
+}
+
+/// Builder to control how a readable file will be opened.
+#[derive(Debug)]
+pub struct ReadBuilder {
+    /// Controls the buffer size of the [`BufReader`].
+    buffer_capacity: Option<usize>,
+    /// Offset in the decompressed content at which reading starts.
+    offset: u64,
+    /// Path of the file to read.
+    path: PathBuf,
+    /// Number of decoded buffers read ahead on a background thread.
+    prefetch: Option<usize>,
+    /// Error if the file extension and the detected filetype disagree.
+    strict: bool,
+    /// Maximal memory usage of the xz decoder in bytes.
+    xz_memlimit: Option<u64>,
+}
+
+impl ReadBuilder {
+    /// Create a new [`ReadBuilder`] for a given path.
+    ///
+    /// See the individual methods for the available configuration options.
+    pub fn new(path: PathBuf) -> Self {
+        ReadBuilder {
+            path,
+
+            buffer_capacity: Default::default(),
+            offset: 0,
+            prefetch: None,
+            strict: false,
+            xz_memlimit: None,
+        }
+    }
+
+    /// Sets the capacity of the [`BufReader`] to `capacity` in Bytes.
+    pub fn buffer_capacity(&mut self, buffer_capacity: usize) -> &mut Self {
+        self.buffer_capacity = Some(buffer_capacity);
+        self
+    }
+
+    /// Read and decompress the file on a background thread.
+    ///
+    /// The background thread reads ahead up to `buffers` fully decoded buffers, such that disk I/O and decompression overlap with the processing of the data.
+    /// Each buffer is as large as the [`buffer_capacity`](Self::buffer_capacity).
+    /// See [`PrefetchReader`] for details.
+    ///
+    /// Setting this value to `0` has the same effect as setting it to `1`.
+    pub fn prefetch(&mut self, buffers: usize) -> &mut Self {
+        self.prefetch = Some(buffers.max(1));
+        self
+    }
+
+    /// Require the file extension to match the detected filetype.
+    ///
+    /// The filetype is always detected based on the magic bytes at the start of the file.
+    /// By default, a file with a compression extension, like `data.gz`, but different content only logs a warning.
+    ///
+    /// If `strict` is set, opening the file fails with [`Error::FormatMismatch`] instead.
+    /// This also applies to compressed files without a matching extension, like a gzip compressed `data.json`.
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Limit the memory usage of the xz decoder to `bytes`.
+    ///
+    /// This is equivalent to `xz --memlimit-decompress` and bounds the worst-case memory usage when decoding untrusted files.
+    /// If decoding the file requires more memory, reading fails with an [`io::Error`] of kind [`io::ErrorKind::OutOfMemory`].
+    /// It wraps an [`Error::XzMemLimitError`], which can be accessed using [`io::Error::get_ref`] and downcasting.
+    ///
+    /// This option has no effect for other filetypes or if the `file-xz` feature is not enabled.
+    pub fn xz_memlimit(&mut self, bytes: u64) -> &mut Self {
+        self.xz_memlimit = Some(bytes);
+        self
+    }
+
+    /// Start reading at `offset` bytes into the decompressed content.
+    ///
+    /// Plaintext files are read starting at the offset directly.
+    /// Compressed files cannot be seeked, so they are decompressed from the start and the data before the offset is discarded.
+    pub fn offset(&mut self, offset: u64) -> &mut Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Open the file for reading.
+    ///
+    /// See [`file_open_read`] for details how the filetype is detected.
+    /// The returned reader reports the detected [`FileType`].
+    pub fn open(&self) -> Result<DetectedReader, Error> {
+        let mut reader = do_file_open_read(self)?;
+        if self.offset > 0 && reader.filetype != FileType::PlainText {
+            // Plaintext files already start at the offset
+            io::copy(&mut (&mut reader.reader).take(self.offset), &mut io::sink()).map_err(
+                |err| Error::FileIo {
+                    file: self.path.clone(),
+                    operation: Operation::Seek,
+                    source: err,
+                },
+            )?;
+        }
+        if let Some(buffers) = self.prefetch {
+            let buffer_size = self.buffer_capacity.unwrap_or(PREFETCH_BUFFER_SIZE);
+            reader.reader = Box::new(PrefetchReader::new(reader.reader, buffer_size, buffers));
+        }
+        Ok(reader)
+    }
+}
+
+/// Reader for plaintext or compressed files, which knows the detected [`FileType`].
+///
+/// This struct is created by [`ReadBuilder::open`].
+/// The content is decompressed transparently while reading.
+pub struct DetectedReader {
+    reader: Box<dyn Read + Send>,
+    filetype: FileType,
+}
+
+impl DetectedReader {
+    /// The [`FileType`] detected from the magic bytes at the start of the file.
+    pub fn filetype(&self) -> FileType {
+        self.filetype
+    }
+
+    /// Unwrap the underlying reader.
+    pub fn into_inner(self) -> Box<dyn Read + Send> {
+        self.reader
+    }
+}
+
+impl fmt::Debug for DetectedReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DetectedReader")
+            .field("filetype", &self.filetype)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Read for DetectedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        self.reader.read_to_end(buf)
+    }
+
+    fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        self.reader.read_to_string(buf)
+    }
+}
+
+/// Create readers for plaintext or compressed files.
+///
+/// The options to open the file can be controlled with the [`ReadBuilder`].
+/// See the documentation on that type for more details.
+/// [`file_open_read`] is a shorthand if no options are needed.
+pub fn file_read<P>(path: P) -> ReadBuilder
+where
+    P: AsRef<Path>,
+{
+    ReadBuilder::new(path.as_ref().to_path_buf())
 }
 
-fn do_file_open_read(file: &Path, buffer_capacity: Option<usize>) -> Result<Box<dyn Read>, Error> {
+fn do_file_open_read(builder: &ReadBuilder) -> Result<DetectedReader, Error> {
+    let file = &*builder.path;
+    let os_path = os_path(file);
+
     #[cfg(not(unix))]
-    if !file.is_file() {
+    if !os_path.is_file() {
         return Err(Error::NotAFileError {
             path: file.to_path_buf(),
         });
@@ -143,7 +442,7 @@ fn do_file_open_read(file: &Path, buffer_capacity: Option<usize>) -> Result<Box<
         let ft = std::fs::metadata(file)
             .map_err(|err| Error::FileIo {
                 file: file.to_path_buf(),
-                msg: "Accessing file metadata failed.",
+                operation: Operation::Metadata,
                 source: err,
             })?
             .file_type();
@@ -158,13 +457,13 @@ fn do_file_open_read(file: &Path, buffer_capacity: Option<usize>) -> Result<Box<
         .create(false)
         .read(true)
         .write(false)
-        .open(file)
+        .open(&os_path)
         .map_err(|err| Error::FileIo {
             file: file.to_path_buf(),
-            msg: "Could not open file.",
+            operation: Operation::Open,
             source: err,
         })?;
-    let mut bufread = if let Some(size) = buffer_capacity {
+    let mut bufread = if let Some(size) = builder.buffer_capacity {
         BufReader::with_capacity(size, f)
     } else {
         BufReader::new(f)
@@ -177,39 +476,106 @@ fn do_file_open_read(file: &Path, buffer_capacity: Option<usize>) -> Result<Box<
         // this will trigger the plaintext case below
         buffer = [0; 6];
     };
-    // reset the read position
+    let detected = compression_from_magic(&buffer);
+    // reset the read position, plaintext files can start at the offset directly
+    let start = if detected.is_none() {
+        builder.offset
+    } else {
+        0
+    };
     bufread
-        .seek(SeekFrom::Start(0))
+        .seek(SeekFrom::Start(start))
         .map_err(|err| Error::FileIo {
             file: file.to_path_buf(),
-            msg: "Failed to seek to start of file.",
+            operation: Operation::Seek,
             source: err,
         })?;
+    let expected = compression_from_extension(file);
+    if detected != expected {
+        let expected_name = expected.unwrap_or("plaintext");
+        let detected_name = detected.unwrap_or("plaintext");
+        if builder.strict {
+            return Err(Error::FormatMismatch {
+                file: file.to_path_buf(),
+                expected: expected_name,
+                detected: detected_name,
+            });
+        } else if expected.is_some() {
+            warn!(
+                "File {} is expected to have type `{}` based on the extension, but is detected to have type `{}`",
+                file.display(),
+                expected_name,
+                detected_name
+            );
+        }
+    }
+
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    if bufread.get_ref().metadata().is_ok_and(|m| m.is_file()) {
+        match uring::UringReader::new(bufread.into_inner(), builder.buffer_capacity, start) {
+            Ok(reader) => {
+                debug!("Read file {} using io_uring", file.display());
+                return open_decoder(builder, reader, detected);
+            }
+            Err(f) => {
+                bufread = if let Some(size) = builder.buffer_capacity {
+                    BufReader::with_capacity(size, f)
+                } else {
+                    BufReader::new(f)
+                };
+            }
+        }
+    }
+
+    open_decoder(builder, bufread, detected)
+}
 
-    if buffer[..6] == [0xfd, b'7', b'z', b'X', b'Z', 0x00] {
+/// Wrap `bufread` into the decoder for the `detected` compression technique.
+fn open_decoder<R>(
+    builder: &ReadBuilder,
+    bufread: R,
+    detected: Option<&'static str>,
+) -> Result<DetectedReader, Error>
+where
+    R: 'static + BufRead + Send,
+{
+    let file = &*builder.path;
+    if detected == Some("xz") {
         debug!("File {} is detected to have type `xz`", file.display());
         #[cfg(feature = "file-xz")]
-        return Ok(Box::new(XzDecoder::new(bufread)));
+        return Ok(DetectedReader {
+            reader: match builder.xz_memlimit {
+                Some(memlimit) => Box::new(XzLimitedDecoder::new(file, bufread, memlimit)?),
+                None => Box::new(XzDecoder::new(bufread)),
+            },
+            filetype: FileType::Xz,
+        });
         #[cfg(not(feature = "file-xz"))]
         return Err(Error::CompressionNotEnabled {
             file: file.to_path_buf(),
             technique: "xz",
         });
     }
-    if buffer[..2] == [0x1f, 0x8b] {
+    if detected == Some("gz") {
         debug!("File {} is detected to have type `gz`", file.display());
         #[cfg(feature = "file-gz")]
-        return Ok(Box::new(MultiGzDecoder::new(bufread)));
+        return Ok(DetectedReader {
+            reader: Box::new(MultiGzDecoder::new(bufread)),
+            filetype: FileType::Gz,
+        });
         #[cfg(not(feature = "file-gz"))]
         return Err(Error::CompressionNotEnabled {
             file: file.to_path_buf(),
             technique: "gz",
         });
     }
-    if buffer[..3] == [b'B', b'Z', b'h'] {
+    if detected == Some("bz2") {
         debug!("File {} is detected to have type `bz2`", file.display());
         #[cfg(feature = "file-bz2")]
-        return Ok(Box::new(BzDecoder::new(bufread)));
+        return Ok(DetectedReader {
+            reader: Box::new(BzDecoder::new(bufread)),
+            filetype: FileType::Bz2,
+        });
         #[cfg(not(feature = "file-bz2"))]
         return Err(Error::CompressionNotEnabled {
             file: file.to_path_buf(),
@@ -218,7 +584,168 @@ fn do_file_open_read(file: &Path, buffer_capacity: Option<usize>) -> Result<Box<
     }
 
     debug!("Open file {} as plaintext", file.display());
-    Ok(Box::new(bufread))
+    Ok(DetectedReader {
+        reader: Box::new(bufread),
+        filetype: FileType::PlainText,
+    })
+}
+
+/// Xz decoder with a memory limit.
+///
+/// Exceeding the limit is reported as [`Error::XzMemLimitError`] wrapped in an [`io::Error`].
+#[cfg(feature = "file-xz")]
+struct XzLimitedDecoder<R> {
+    decoder: XzDecoder<R>,
+    file: PathBuf,
+    memlimit: u64,
+}
+
+#[cfg(feature = "file-xz")]
+impl<R: BufRead> XzLimitedDecoder<R> {
+    fn new(file: &Path, bufread: R, memlimit: u64) -> Result<Self, Error> {
+        let stream = Stream::new_stream_decoder(memlimit, 0).map_err(|err| Error::XzError {
+            file: file.to_path_buf(),
+            source: err,
+        })?;
+        Ok(Self {
+            decoder: XzDecoder::new_stream(bufread, stream),
+            file: file.to_path_buf(),
+            memlimit,
+        })
+    }
+}
+
+#[cfg(feature = "file-xz")]
+impl<R: BufRead> Read for XzLimitedDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.decoder.read(buf).map_err(|err| {
+            let is_memlimit = err
+                .get_ref()
+                .and_then(|inner| inner.downcast_ref::<xz2::stream::Error>())
+                .is_some_and(|inner| matches!(inner, xz2::stream::Error::MemLimit));
+            if is_memlimit {
+                io::Error::new(
+                    io::ErrorKind::OutOfMemory,
+                    Error::XzMemLimitError {
+                        file: self.file.clone(),
+                        memlimit: self.memlimit,
+                    },
+                )
+            } else {
+                err
+            }
+        })
+    }
+}
+
+/// Default size of the buffers passed between the threads of the [`PrefetchReader`].
+const PREFETCH_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Reader which reads ahead on a background thread.
+///
+/// The background thread fills buffers from the underlying reader and passes them through a bounded channel.
+/// This allows overlapping CPU heavy work, like decompressing xz files, with the processing of the data.
+/// The background thread stops if the [`PrefetchReader`] is dropped.
+///
+/// The reader can be created using [`ReadBuilder::prefetch`] or [`PrefetchReader::new`].
+pub struct PrefetchReader {
+    receiver: mpsc::Receiver<Option<io::Result<Vec<u8>>>>,
+    buffer: Vec<u8>,
+    pos: usize,
+    did_complete: bool,
+}
+
+impl PrefetchReader {
+    /// Start reading from `reader` on a background thread.
+    ///
+    /// The background thread reads buffers of `buffer_size` bytes and keeps up to `buffers` of them ready.
+    pub fn new<R>(mut reader: R, buffer_size: usize, buffers: usize) -> Self
+    where
+        R: 'static + Read + Send,
+    {
+        let buffer_size = buffer_size.max(1);
+        let (sender, receiver) = mpsc::sync_channel(buffers.max(1));
+
+        thread::spawn(move || loop {
+            let mut buffer = vec![0; buffer_size];
+            let mut filled = 0;
+            // Fill the whole buffer to keep the number of channel operations low
+            while filled < buffer_size {
+                match reader.read(&mut buffer[filled..]) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => {
+                        // cannot communicate channel failures
+                        let _ = sender.send(Some(Err(err)));
+                        return;
+                    }
+                }
+            }
+
+            if filled == 0 {
+                // signal the successful end of the file
+                let _ = sender.send(None);
+                return;
+            }
+            buffer.truncate(filled);
+            if sender.send(Some(Ok(buffer))).is_err() {
+                // The reader was dropped
+                return;
+            }
+        });
+
+        Self {
+            receiver,
+            buffer: Vec::new(),
+            pos: 0,
+            did_complete: false,
+        }
+    }
+}
+
+impl fmt::Debug for PrefetchReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrefetchReader")
+            .field("buffered", &(self.buffer.len() - self.pos))
+            .field("did_complete", &self.did_complete)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Read for PrefetchReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for PrefetchReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos >= self.buffer.len() && !self.did_complete {
+            match self.receiver.recv() {
+                Ok(Some(Ok(buffer))) => {
+                    self.buffer = buffer;
+                    self.pos = 0;
+                }
+                Ok(Some(Err(err))) => return Err(err),
+                Ok(None) => self.did_complete = true,
+                Err(_) => {
+                    return Err(io::Error::other(
+                        "Background reading thread terminated unexpectedly.",
+                    ))
+                }
+            }
+        }
+        Ok(&self.buffer[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.buffer.len());
+    }
 }
 
 /// Specify the output filetype.
@@ -335,6 +862,8 @@ impl From<Compression> for XzCompression {
 /// Builder to control how the writeable file will be opened.
 #[derive(Debug)]
 pub struct WriteBuilder {
+    /// Create the file without a name and link it into place on [`CompressedWriter::finish`].
+    anonymous: bool,
     /// Controls the buffer size of the [`BufWriter`].
     buffer_capacity: Option<usize>,
     /// Compression level of the file.
@@ -349,10 +878,20 @@ pub struct WriteBuilder {
     path: PathBuf,
     /// Controls how the file will be opened.
     open_options: OpenOptions,
+    /// Mirrors the `create_new` flag of the `open_options`.
+    create_new: bool,
+    /// Create the missing parent directories before opening the file.
+    create_parents: bool,
+    /// Upper bound for the memory usage of the multithreaded encoder.
+    memory_budget: Option<u64>,
+    /// Number of bytes to reserve on disk before writing.
+    preallocate: Option<u64>,
     /// Number of threads used during compression.
     ///
     /// Ignored for [`FileType::PlainText`].
     threads: u8,
+    /// Choose the number of threads based on the available parallelism.
+    threads_auto: bool,
 }
 
 impl WriteBuilder {
@@ -368,84 +907,113 @@ impl WriteBuilder {
             filetype: None,
             open_options,
 
+            anonymous: false,
             buffer_capacity: Default::default(),
             compression_level: Default::default(),
+            create_new: false,
+            create_parents: false,
+            memory_budget: None,
+            preallocate: None,
             threads: 1,
+            threads_auto: false,
         }
     }
 
     /// Open the file in *append* mode.
-    pub fn append(&mut self) -> Result<Box<dyn Write>, Error> {
+    pub fn append(&mut self) -> Result<CompressedWriter, Error> {
         self.open_options.append(true);
-        self.open()
+        self.open(true)
     }
 
     /// Open the file in *truncate* mode.
-    pub fn truncate(&mut self) -> Result<Box<dyn Write>, Error> {
+    pub fn truncate(&mut self) -> Result<CompressedWriter, Error> {
         self.open_options.truncate(true);
-        self.open()
+        self.open(false)
     }
 
-    fn open(&mut self) -> Result<Box<dyn Write>, Error> {
+    fn open(&mut self, append: bool) -> Result<CompressedWriter, Error> {
         use self::FileType::*;
 
         if self.filetype.is_none() {
             self.filetype = Some(guess_file_type(&self.path)?);
         }
 
-        let file = self
-            .open_options
-            .open(&self.path)
-            .map_err(|err| Error::FileIo {
+        if self.create_parents {
+            ensure_parent_dirs(&self.path)?;
+        }
+        let anonymous = self.anonymous && !append;
+        let file = if anonymous {
+            open_anonymous(&self.path)?
+        } else {
+            self.open_options
+                .open(os_path(&self.path))
+                .map_err(|err| Error::FileIo {
+                    file: self.path.to_path_buf(),
+                    operation: Operation::Open,
+                    source: err,
+                })?
+        };
+        if let Some(len) = self.preallocate {
+            let offset = if append {
+                file.metadata().map(|m| m.len()).unwrap_or_default()
+            } else {
+                0
+            };
+            preallocate(&file, offset, len).map_err(|err| Error::FileIo {
                 file: self.path.to_path_buf(),
-                msg: "Could not open file.",
+                operation: Operation::Write,
                 source: err,
             })?;
+        }
+        #[cfg(all(feature = "io-uring", target_os = "linux"))]
+        let bufwrite = FileWriter::new(file, self.buffer_capacity, append);
+        #[cfg(not(all(feature = "io-uring", target_os = "linux")))]
         let bufwrite = if let Some(size) = self.buffer_capacity {
             BufWriter::with_capacity(size, file)
         } else {
             BufWriter::new(file)
         };
 
-        match self
+        let filetype = self
             .filetype
-            .expect("FileType is set based on extension if it was None")
-        {
+            .expect("FileType is set based on extension if it was None");
+        let encoder = match filetype {
             #[cfg(feature = "file-bz2")]
             Bz2 => {
                 let level = self.compression_level.into();
-                Ok(Box::new(BzEncoder::new(bufwrite, level)))
+                Encoder::Bz2(BzEncoder::new(bufwrite, level))
             }
             #[cfg(feature = "file-gz")]
             Gz => {
                 let level = self.compression_level.into();
-                Ok(Box::new(GzEncoder::new(bufwrite, level)))
+                Encoder::Gz(GzEncoder::new(bufwrite, level))
             }
-            PlainText => Ok(Box::new(bufwrite)),
+            PlainText => Encoder::PlainText(bufwrite),
             #[cfg(feature = "file-xz")]
             Xz => {
                 let level: XzCompression = self.compression_level.into();
-                let threads = Ord::clamp(self.threads, 1, u8::MAX);
+                let threads = self.xz_threads(level.0);
                 if threads == 1 {
-                    Ok(Box::new(XzEncoder::new(bufwrite, level.0)))
+                    Encoder::Xz(XzEncoder::new(bufwrite, level.0))
                 } else {
-                    let stream = MtStreamBuilder::new()
-                        .preset(level.0)
-                        .threads(u32::from(threads))
-                        // let LZMA2 choose the best blocksize
-                        .block_size(0)
-                        // use the same value as the xz command line tool
-                        .timeout_ms(300)
-                        .check(Check::Crc64)
-                        .encoder()
-                        .map_err(|err| Error::XzError {
+                    let stream = xz_mt_builder(level.0, threads).encoder().map_err(|err| {
+                        Error::XzError {
                             file: self.path.to_path_buf(),
                             source: err,
-                        })?;
-                    Ok(Box::new(XzEncoder::new_stream(bufwrite, stream)))
+                        }
+                    })?;
+                    Encoder::Xz(XzEncoder::new_stream(bufwrite, stream))
                 }
             }
-        }
+        };
+        Ok(CompressedWriter {
+            encoder,
+            anonymous,
+            bytes_written: 0,
+            create_new: self.create_new,
+            filetype,
+            path: self.path.clone(),
+        })
     }
 
     /// Sets the capacity of the [`BufWriter`] to `capacity` in Bytes.
@@ -476,6 +1044,45 @@ impl WriteBuilder {
     /// [`truncate()`]: Self::truncate
     pub fn create_new(&mut self, create_new: bool) -> &mut Self {
         self.open_options.create_new(create_new);
+        self.create_new = create_new;
+        self
+    }
+
+    /// Create all missing parent directories of the path before opening the file.
+    ///
+    /// See [`ensure_parent_dirs`].
+    pub fn create_parents(&mut self, create_parents: bool) -> &mut Self {
+        self.create_parents = create_parents;
+        self
+    }
+
+    /// Create the file without a name and only link it into place on [`CompressedWriter::finish`].
+    ///
+    /// The file is created using `O_TMPFILE` in the directory of the path.
+    /// [`CompressedWriter::finish`] syncs the content to disk and then atomically gives the file its name.
+    /// Readers never observe a partially written file and after a crash no partial file remains.
+    /// If the writer is dropped without calling [`CompressedWriter::finish`], the file is discarded.
+    ///
+    /// An existing file is replaced, unless [`create_new()`](Self::create_new) is set.
+    /// The option has no effect in *append* mode.
+    /// Opening the file fails if the filesystem does not support `O_TMPFILE`.
+    #[cfg(target_os = "linux")]
+    pub fn anonymous_until_finish(&mut self) -> &mut Self {
+        self.anonymous = true;
+        self
+    }
+
+    /// Reserve `bytes` of disk space before writing.
+    ///
+    /// Opening the file fails if there is not enough free disk space, instead of failing after writing large parts of the file.
+    /// The space is reserved after the current end of the file, i.e., at the start in *truncate* mode and at the end in *append* mode.
+    /// The size of the file is not changed, so reserving more space than written does not add any trailing bytes.
+    /// For compressed files, the value should be the expected size of the compressed output.
+    ///
+    /// This uses `fallocate` on Linux and `SetFileInformationByHandle` on Windows.
+    /// On other platforms and on filesystems without support for preallocation, this option has no effect.
+    pub fn preallocate(&mut self, bytes: u64) -> &mut Self {
+        self.preallocate = Some(bytes);
         self
     }
 
@@ -504,273 +1111,453 @@ impl WriteBuilder {
     /// Setting this value to `0` has the same effect as setting it to `1`.
     pub fn threads(&mut self, threads: u8) -> &mut Self {
         self.threads = if threads == 0 { 1 } else { threads };
+        self.threads_auto = false;
+        self
+    }
+
+    /// Choose the number of threads used for compression automatically.
+    ///
+    /// The number of threads is based on [`std::thread::available_parallelism`].
+    /// Combine it with [`memory_budget()`](Self::memory_budget) to bound the memory usage, since each thread of the xz encoder can use hundreds of MiB at high compression levels.
+    /// Calling [`threads()`](Self::threads) overrides this option.
+    pub fn threads_auto(&mut self) -> &mut Self {
+        self.threads_auto = true;
+        self
+    }
+
+    /// Limit the memory usage of the multithreaded encoder to roughly `bytes`.
+    ///
+    /// The number of threads, set by [`threads()`](Self::threads) or [`threads_auto()`](Self::threads_auto), is reduced until the estimated memory usage of the encoder fits into the budget.
+    /// At least one thread is always used, even if it exceeds the budget.
+    /// This feature is currently only used with `xz`.
+    pub fn memory_budget(&mut self, bytes: u64) -> &mut Self {
+        self.memory_budget = Some(bytes);
         self
     }
+
+    /// Number of threads for the xz encoder at compression level `preset`.
+    #[cfg(feature = "file-xz")]
+    fn xz_threads(&self, preset: u32) -> u32 {
+        let mut threads = if self.threads_auto {
+            thread::available_parallelism().map_or(1, |n| n.get().min(u8::MAX.into()) as u32)
+        } else {
+            u32::from(self.threads.max(1))
+        };
+        if let Some(budget) = self.memory_budget {
+            while threads > 1 && xz_mt_builder(preset, threads).memusage() > budget {
+                threads -= 1;
+            }
+        }
+        threads
+    }
 }
 
-/// Create writers for plaintext or compressed files.
-///
-/// This function can open a file with different compressors enabled.
-/// The options to open and write the file can be controlled with the [`WriteBuilder`].
-/// See the documentation on that type for more details.
-/// The filetype will be guessed from the extension.
-/// The guessing can be disabled by explicitly setting a filetype using [`WriteBuilder::filetype`].
-///
-/// File I/O will always be buffered using a [`BufReader`].
-///
-/// Flushing the writer will not write all the data to file.
-/// Archives require some finalizer which is only written if the writer is being dropped.
-pub fn file_write<P>(path: P) -> WriteBuilder
-where
-    P: AsRef<Path>,
-{
-    WriteBuilder::new(path.as_ref().to_path_buf())
+/// Configure the multithreaded xz encoder.
+#[cfg(feature = "file-xz")]
+fn xz_mt_builder(preset: u32, threads: u32) -> MtStreamBuilder {
+    let mut builder = MtStreamBuilder::new();
+    builder
+        .preset(preset)
+        .threads(threads)
+        // let LZMA2 choose the best blocksize
+        .block_size(0)
+        // use the same value as the xz command line tool
+        .timeout_ms(300)
+        .check(Check::Crc64);
+    builder
 }
 
-/// Result type for [`parse_jsonl_multi_threaded`].
+/// Writer for plaintext or compressed files.
 ///
-/// This enum encapsulates certain error conditions which can occur either during file I/O or JSON
-/// parsing and the data produced by it. Every user of the [`parse_jsonl_multi_threaded`] **must**
-/// verify that the last element of the iteration is the `Complete` variant, to ensure the whole
-/// file has been read and all lines could successfully parsed.
-#[cfg(feature = "jsonl")]
-#[derive(Debug)]
-enum ProcessingStatus<T>
-where
-    T: 'static + Send,
-{
-    /// Indicates a successful completion of all steps.
-    /// Every user **must** verify that this element occurs during iteration.
-    Completed,
-    /// Wrapper for any user-defined datatype
-    Data(T),
-    Error(MtJsonlError),
+/// This struct is created by [`WriteBuilder::append`] and [`WriteBuilder::truncate`].
+/// Next to implementing [`Write`], it provides access to some statistics about the written file.
+///
+/// Archives require some finalizer which is only written if the writer is being dropped.
+pub struct CompressedWriter {
+    encoder: Encoder,
+    /// The file has no name until `finish` is called
+    anonymous: bool,
+    /// Number of uncompressed bytes written so far
+    bytes_written: u64,
+    /// Do not replace an existing file when linking an anonymous file
+    create_new: bool,
+    filetype: FileType,
+    path: PathBuf,
 }
 
-/// An iterator over deserialized JSON objects
+impl CompressedWriter {
+    /// Write all remaining data and finalize the file.
+    ///
+    /// Dropping the writer also finalizes the file, but cannot report any errors.
+    /// For files opened with [`WriteBuilder::anonymous_until_finish`], this syncs the content to disk and links the file into place.
+    pub fn finish(self) -> Result<(), Error> {
+        let path = self.path;
+        let map_err = |err| Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::Flush,
+            source: err,
+        };
+
+        // Without any compression features, only the `PlainText` variant exists
+        #[allow(clippy::infallible_destructuring_match)]
+        let mut inner = match self.encoder {
+            #[cfg(feature = "file-bz2")]
+            Encoder::Bz2(w) => w.finish().map_err(map_err)?,
+            #[cfg(feature = "file-gz")]
+            Encoder::Gz(w) => w.finish().map_err(map_err)?,
+            Encoder::PlainText(w) => w,
+            #[cfg(feature = "file-xz")]
+            Encoder::Xz(w) => w.finish().map_err(map_err)?,
+        };
+        inner.flush().map_err(map_err)?;
+
+        if self.anonymous {
+            inner.get_ref().sync_all().map_err(map_err)?;
+            link_anonymous(inner.get_ref(), &path, !self.create_new)?;
+        }
+        Ok(())
+    }
+
+    /// Number of bytes written into this writer.
+    ///
+    /// This counts the uncompressed bytes, not the number of bytes in the file.
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// The [`FileType`] of the written file.
+    pub fn filetype(&self) -> FileType {
+        self.filetype
+    }
+
+    /// Path of the written file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Determines if this writer has an efficient [`write_vectored`](Write::write_vectored) implementation.
+    ///
+    /// This mirrors the unstable [`Write::is_write_vectored`] method.
+    /// Plaintext files copy all slices into the write buffer at once, so they return `true`.
+    /// The compression encoders receive the slices one by one, so compressed files return `false`.
+    pub fn is_write_vectored(&self) -> bool {
+        self.encoder.is_write_vectored()
+    }
+}
+
+impl fmt::Debug for CompressedWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CompressedWriter")
+            .field("bytes_written", &self.bytes_written)
+            .field("filetype", &self.filetype)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.encoder.write(buf)?;
+        self.bytes_written += written as u64;
+        Ok(written)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let written = self.encoder.write_vectored(bufs)?;
+        self.bytes_written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.encoder.flush()
+    }
+}
+
+/// The different writers which can be created by the [`WriteBuilder`].
 ///
-/// This struct is created by the [`parse_jsonl_multi_threaded`] function.
-#[cfg(feature = "jsonl")]
-#[derive(Debug)]
-pub struct MtJsonl<T>
-where
-    T: 'static + DeserializeOwned + Send,
-{
-    iter: mpsc::IntoIter<ProcessingStatus<Vec<Result<T, MtJsonlError>>>>,
-    tmp_state: std::vec::IntoIter<Result<T, MtJsonlError>>,
-    did_complete: bool,
+/// Using an enum instead of a trait object allows forwarding all [`Write`] methods to the concrete writer.
+enum Encoder {
+    #[cfg(feature = "file-bz2")]
+    Bz2(BzEncoder<FileWriter>),
+    #[cfg(feature = "file-gz")]
+    Gz(GzEncoder<FileWriter>),
+    PlainText(FileWriter),
+    #[cfg(feature = "file-xz")]
+    Xz(XzEncoder<FileWriter>),
 }
 
-#[cfg(feature = "jsonl")]
-impl<T> MtJsonl<T>
-where
-    T: 'static + DeserializeOwned + Send,
-{
-    fn new(iter: mpsc::IntoIter<ProcessingStatus<Vec<Result<T, MtJsonlError>>>>) -> Self {
-        Self {
-            iter,
-            tmp_state: vec![].into_iter(),
-            did_complete: false,
+impl Encoder {
+    /// Determines if the writer has an efficient [`write_vectored`](Write::write_vectored) implementation.
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            // The slices are passed to the encoders one by one, see `write_slices`
+            #[cfg(feature = "file-bz2")]
+            Encoder::Bz2(_) => false,
+            #[cfg(feature = "file-gz")]
+            Encoder::Gz(_) => false,
+            // `BufWriter` and the io_uring writer copy all slices into their buffer
+            Encoder::PlainText(_) => true,
+            #[cfg(feature = "file-xz")]
+            Encoder::Xz(_) => false,
         }
     }
 }
 
-#[cfg(feature = "jsonl")]
-impl<T> Iterator for MtJsonl<T>
-where
-    T: 'static + DeserializeOwned + Send,
-{
-    type Item = Result<T, MtJsonlError>;
+impl Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            #[cfg(feature = "file-bz2")]
+            Encoder::Bz2(w) => w.write(buf),
+            #[cfg(feature = "file-gz")]
+            Encoder::Gz(w) => w.write(buf),
+            Encoder::PlainText(w) => w.write(buf),
+            #[cfg(feature = "file-xz")]
+            Encoder::Xz(w) => w.write(buf),
+        }
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            if let Some(res) = self.tmp_state.next() {
-                return Some(match res {
-                    Ok(x) => Ok(x),
-                    Err(err) => {
-                        info!("{:?}", err);
-                        Err(err)
-                    }
-                });
-            } else if let Some(state) = self.iter.next() {
-                match state {
-                    ProcessingStatus::Data(data) => self.tmp_state = data.into_iter(),
-                    ProcessingStatus::Completed => self.did_complete = true,
-                    // path through error
-                    ProcessingStatus::Error(err) => return Some(Err(err)),
-                }
-                continue;
-            }
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        match self {
+            #[cfg(feature = "file-bz2")]
+            Encoder::Bz2(w) => write_slices(w, bufs),
+            #[cfg(feature = "file-gz")]
+            Encoder::Gz(w) => write_slices(w, bufs),
+            Encoder::PlainText(w) => w.write_vectored(bufs),
+            #[cfg(feature = "file-xz")]
+            Encoder::Xz(w) => write_slices(w, bufs),
+        }
+    }
 
-            // No more data to read from underlying iterators
-            return if self.did_complete {
-                None
-            } else {
-                Some(Err(MtJsonlError::NotCompleted))
-            };
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            #[cfg(feature = "file-bz2")]
+            Encoder::Bz2(w) => w.flush(),
+            #[cfg(feature = "file-gz")]
+            Encoder::Gz(w) => w.flush(),
+            Encoder::PlainText(w) => w.flush(),
+            #[cfg(feature = "file-xz")]
+            Encoder::Xz(w) => w.flush(),
         }
     }
 }
 
-/// Create a multi-threaded [JSONL] parser.
-///
-/// This returns an iterator over `Result<T>`. If any reading errors of the file or parsing errors
-/// happen they will be passed to the caller of the iterator.
+/// Reserve `len` bytes of disk space starting at `offset`, without changing the file size.
+#[cfg(target_os = "linux")]
+fn preallocate(file: &File, offset: u64, len: u64) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    if len == 0 {
+        return Ok(());
+    }
+    let (Ok(offset), Ok(len)) = (offset.try_into(), len.try_into()) else {
+        return Err(io::ErrorKind::InvalidInput.into());
+    };
+    loop {
+        // SAFETY: The file descriptor is valid for the lifetime of `file`.
+        let res =
+            unsafe { libc::fallocate(file.as_raw_fd(), libc::FALLOC_FL_KEEP_SIZE, offset, len) };
+        if res == 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::EINTR) => {}
+            Some(libc::EOPNOTSUPP) => {
+                debug!("Filesystem does not support preallocation: {err}");
+                return Ok(());
+            }
+            _ => return Err(err),
+        }
+    }
+}
+
+/// Reserve `len` bytes of disk space starting at `offset`, without changing the file size.
+#[cfg(windows)]
+fn preallocate(file: &File, offset: u64, len: u64) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileAllocationInfo, SetFileInformationByHandle, FILE_ALLOCATION_INFO,
+    };
+
+    if len == 0 {
+        return Ok(());
+    }
+    let Some(size) = offset
+        .checked_add(len)
+        .and_then(|size| i64::try_from(size).ok())
+    else {
+        return Err(io::ErrorKind::InvalidInput.into());
+    };
+    let info = FILE_ALLOCATION_INFO {
+        AllocationSize: size,
+    };
+    // SAFETY: The handle is valid for the lifetime of `file` and `info` matches the information class.
+    let res = unsafe {
+        SetFileInformationByHandle(
+            file.as_raw_handle(),
+            FileAllocationInfo,
+            std::ptr::addr_of!(info).cast(),
+            size_of::<FILE_ALLOCATION_INFO>() as u32,
+        )
+    };
+    if res == 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Reserve `len` bytes of disk space starting at `offset`, without changing the file size.
 ///
-/// Internally this will spawn two threads. The first thread is responsible for reading from the
-/// underlying file. It uses [`file_open_read`] for this task, thus it also supports compressed
-/// files transparently. The second thread receives multiple lines as [`String`] and parses them
-/// into a `Vec<Result<T>>`. Then they are passed to the caller as a single iterator.
+/// Not supported on this platform, so this does nothing.
+#[cfg(not(any(target_os = "linux", windows)))]
+fn preallocate(_file: &File, _offset: u64, _len: u64) -> io::Result<()> {
+    Ok(())
+}
+
+/// Create an unnamed file in the directory of `path`.
+#[cfg(target_os = "linux")]
+fn open_anonymous(path: &Path) -> Result<File, Error> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_TMPFILE)
+        .mode(0o666)
+        .open(dir)
+        .map_err(|err| Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::Open,
+            source: err,
+        })
+}
+
+/// Create an unnamed file in the directory of `path`.
 ///
-/// Since the processing is based on thread the communication overhead should be minimal. For this
-/// the `batchsize` can be specifies, which controls how many lines are read before passing them to
-/// the second thread and thus how large the vector will be.
+/// Only supported on Linux.
+#[cfg(not(target_os = "linux"))]
+fn open_anonymous(path: &Path) -> Result<File, Error> {
+    Err(Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::Open,
+        source: io::ErrorKind::Unsupported.into(),
+    })
+}
+
+/// Give the anonymous `file` the name `path`.
 ///
-/// [JSONL]: http://jsonlines.org/
-#[cfg(feature = "jsonl")]
-pub fn parse_jsonl_multi_threaded<P, T>(path: P, batchsize: u32) -> MtJsonl<T>
-where
-    P: AsRef<Path>,
-    T: 'static + DeserializeOwned + Send,
-{
-    let path = path.as_ref().to_path_buf();
-    const CHAN_BUFSIZE: usize = 2;
-
-    // create channels
-    let (lines_sender, lines_receiver) = mpsc::sync_channel(CHAN_BUFSIZE);
-    #[allow(clippy::type_complexity)]
-    let (struct_sender, struct_receiver): (
-        _,
-        mpsc::Receiver<ProcessingStatus<Vec<Result<T, MtJsonlError>>>>,
-    ) = mpsc::sync_channel(CHAN_BUFSIZE);
-
-    // spawn reader thread of file
-    thread::spawn(move || {
-        info!(
-            "Start background reading thread: {:?}",
-            thread::current().id()
-        );
-        let mut rdr = match file_open_read(&path) {
-            Ok(rdr) => BufReader::new(rdr),
-            Err(err) => {
-                warn!(
-                    "Background reading thread cannot open file {} {:?}",
-                    path.display(),
-                    thread::current().id()
-                );
-                // cannot communicate channel failures
-                let _ = lines_sender.send(ProcessingStatus::Error(err.into()));
-                return;
-            }
+/// If `replace` is set, the file is first linked under a temporary name and then renamed over `path`.
+#[cfg(target_os = "linux")]
+fn link_anonymous(file: &File, path: &Path, replace: bool) -> Result<(), Error> {
+    use std::{
+        ffi::CString,
+        os::unix::{ffi::OsStrExt, io::AsRawFd},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// Distinguish the temporary names within one process
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let link = |target: &Path| -> io::Result<()> {
+        let fd_path = CString::new(format!("/proc/self/fd/{}", file.as_raw_fd()))?;
+        let target = CString::new(target.as_os_str().as_bytes())?;
+        // SAFETY: Both paths are valid NUL-terminated strings.
+        let res = unsafe {
+            libc::linkat(
+                libc::AT_FDCWD,
+                fd_path.as_ptr(),
+                libc::AT_FDCWD,
+                target.as_ptr(),
+                libc::AT_SYMLINK_FOLLOW,
+            )
         };
-        let mut is_eof = false;
-        while !is_eof {
-            let mut batch = String::new();
-            for _ in 0..batchsize {
-                match rdr.read_line(&mut batch) {
-                    Ok(0) => {
-                        is_eof = true;
-                        break;
-                    }
-                    Ok(_) => {}
-                    Err(err) => {
-                        warn!(
-                            "Background reading thread cannot read line {:?}",
-                            thread::current().id()
-                        );
-                        // cannot communicate channel failures
-                        let _ = lines_sender.send(ProcessingStatus::Error(
-                            Error::FileIo {
-                                file: path.to_path_buf(),
-                                msg: "Background reading thread cannot read line.",
-                                source: err,
-                            }
-                            .into(),
-                        ));
-                        return;
-                    }
-                }
-            }
-            // cannot communicate channel failures
-            if lines_sender.send(ProcessingStatus::Data(batch)).is_err() {
-                // kill on sent error
-                return;
-            }
-            info!(
-                "Background reading thread: sent batch {:?}",
-                thread::current().id()
-            );
+        if res == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
         }
-        // cannot communicate channel failures
-        let _ = lines_sender.send(ProcessingStatus::Completed);
-        info!(
-            "Background reading thread: successful processed file {:?} {:?}",
-            path,
-            thread::current().id()
-        );
-    });
+    };
+    let map_err = |err| Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::Rename,
+        source: err,
+    };
 
-    // spawn JSONL parser
-    thread::spawn(move || {
-        info!(
-            "Start background parsing thread {:?}",
-            thread::current().id()
-        );
-        let mut channel_successful_completed = false;
-        lines_receiver.iter().for_each(|batch| {
-            match batch {
-                ProcessingStatus::Error(e) => {
-                    info!(
-                        "Background parsing thread: pass through error {:?}",
-                        thread::current().id()
-                    );
-                    // cannot communicate channel failures
-                    let _ = struct_sender.send(ProcessingStatus::Error(e));
-                }
-                // not the success status for future use
-                ProcessingStatus::Completed => channel_successful_completed = true,
-                ProcessingStatus::Data(batch) => {
-                    let batch: Vec<Result<T, MtJsonlError>> = Deserializer::from_str(&batch)
-                        .into_iter()
-                        .map(|v| v.map_err(|err| MtJsonlError::ParsingError { source: err }))
-                        .collect();
-
-                    info!(
-                        "Background parsing thread: batch parsed {:?}",
-                        thread::current().id()
-                    );
-                    // cannot communicate channel failures
-                    if struct_sender.send(ProcessingStatus::Data(batch)).is_err() {
-                        warn!(
-                            "Background parsing thread: sent channel error {:?}",
-                            thread::current().id()
-                        );
-                        // kill on send error
-                    }
+    if !replace {
+        return link(path).map_err(map_err);
+    }
+
+    let mut tmp_name = OsStr::new(".").to_os_string();
+    tmp_name.push(path.file_name().unwrap_or_default());
+    tmp_name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp_path = path.with_file_name(tmp_name);
+    link(&tmp_path).map_err(map_err)?;
+    std::fs::rename(&tmp_path, path).map_err(|err| {
+        let _ = std::fs::remove_file(&tmp_path);
+        map_err(err)
+    })
+}
+
+/// Give the anonymous `file` the name `path`.
+///
+/// Only supported on Linux.
+#[cfg(not(target_os = "linux"))]
+fn link_anonymous(_file: &File, path: &Path, _replace: bool) -> Result<(), Error> {
+    Err(Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::Rename,
+        source: io::ErrorKind::Unsupported.into(),
+    })
+}
+
+/// Write as many slices as possible into the encoder `w`.
+///
+/// The default implementation of [`Write::write_vectored`] only writes the first non-empty slice.
+/// The encoders buffer internally, such that writing all slices in one call avoids many small calls.
+/// Stops at the first short write or error, errors are only returned if nothing was written.
+#[cfg(any(feature = "file-bz2", feature = "file-gz", feature = "file-xz"))]
+fn write_slices<W: Write>(w: &mut W, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+    let mut written = 0;
+    for buf in bufs {
+        match w.write(buf) {
+            Ok(n) => {
+                written += n;
+                if n < buf.len() {
+                    break;
                 }
             }
-        });
-        if channel_successful_completed {
-            info!(
-                "Background parsing thread: successfully completed {:?}",
-                thread::current().id()
-            );
-            if struct_sender.send(ProcessingStatus::Completed).is_err() {
-                warn!(
-                    "Background parsing thread: sent channel error {:?}",
-                    thread::current().id()
-                );
-                // kill on send error
-            }
-        } else {
-            warn!(
-                "Background parsing thread: did not receive complete message from underlying reader {:?}",
-                thread::current().id()
-            );
+            Err(err) if written == 0 => return Err(err),
+            Err(_) => break,
         }
-    });
+    }
+    Ok(written)
+}
 
-    MtJsonl::new(struct_receiver.into_iter())
+/// Create writers for plaintext or compressed files.
+///
+/// This function can open a file with different compressors enabled.
+/// The options to open and write the file can be controlled with the [`WriteBuilder`].
+/// See the documentation on that type for more details.
+/// The filetype will be guessed from the extension.
+/// The guessing can be disabled by explicitly setting a filetype using [`WriteBuilder::filetype`].
+///
+/// File I/O will always be buffered using a [`BufReader`].
+///
+/// Flushing the writer will not write all the data to file.
+/// Archives require some finalizer which is only written if the writer is being dropped.
+pub fn file_write<P>(path: P) -> WriteBuilder
+where
+    P: AsRef<Path>,
+{
+    WriteBuilder::new(path.as_ref().to_path_buf())
 }
 
 /// Read the entire contents of a file into a bytes vector.
@@ -785,12 +1572,57 @@ pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<u8>, Error> {
         .read_to_end(&mut buffer)
         .map_err(|err| Error::FileIo {
             file: path.as_ref().to_path_buf(),
-            msg: "Could not read file.",
+            operation: Operation::Read,
             source: err,
         })?;
     Ok(buffer)
 }
 
+/// Count the lines of a file.
+///
+/// This function supports opening compressed files transparently.
+///
+/// The lines are counted like in [`BufRead::lines`], i.e., a final line without a line terminator is counted, too.
+/// The content is neither validated as UTF-8 nor copied, and the line terminators are searched using [`memchr`], which makes this much faster than iterating over the lines.
+pub fn count_lines<P: AsRef<Path>>(path: P) -> Result<u64, Error> {
+    let path = path.as_ref();
+    let mut reader = file_read(path).open()?;
+    let mut buffer = vec![0; 64 * 1024];
+    let mut lines = 0;
+    let mut last = b'\n';
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => {
+                return Err(Error::FileIo {
+                    file: path.to_path_buf(),
+                    operation: Operation::Read,
+                    source: err,
+                })
+            }
+        };
+        lines += memchr::memchr_iter(b'\n', &buffer[..read]).count() as u64;
+        last = buffer[read - 1];
+    }
+    if last != b'\n' {
+        lines += 1;
+    }
+    Ok(lines)
+}
+
+/// Read the entire contents of a file into [`Bytes`](bytes::Bytes).
+///
+/// This function supports opening compressed files transparently.
+///
+/// The content is read like [`read()`] and then converted without copying.
+/// The returned value can be cheaply cloned and sliced, e.g., to use it as the body of a network response.
+#[cfg(feature = "bytes")]
+pub fn read_bytes<P: AsRef<Path>>(path: P) -> Result<bytes::Bytes, Error> {
+    read(path).map(bytes::Bytes::from)
+}
+
 /// Read the entire contents of a file into a string.
 ///
 /// This function supports opening compressed files transparently.
@@ -805,12 +1637,115 @@ pub fn read_to_string<P: AsRef<Path>>(path: P) -> Result<String, Error> {
         .read_to_string(&mut buffer)
         .map_err(|err| Error::FileIo {
             file: path.to_path_buf(),
-            msg: "Could not read file.",
+            operation: Operation::Read,
             source: err,
         })?;
     Ok(buffer)
 }
 
+/// Read a JSON file and deserialize it into `T`.
+///
+/// This function supports opening compressed files transparently.
+/// Parsing errors are reported as [`Error::JsonError`] and contain the path of the file.
+#[cfg(feature = "json")]
+pub fn read_json<P, T>(path: P) -> Result<T, Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let content = read(path)?;
+    serde_json::from_slice(&content).map_err(|err| Error::JsonError {
+        file: path.to_path_buf(),
+        source: err,
+    })
+}
+
+/// Serialize `value` as JSON and write it as the entire contents of a file.
+///
+/// The filetype is chosen based on the extension like in [`write()`], e.g., `results.json.xz` is compressed using xz.
+/// The file is truncated before writing.
+/// See [`write_json_pretty`] for a human-readable output.
+#[cfg(feature = "json")]
+pub fn write_json<P, T>(path: P, value: &T) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    T: ?Sized + serde::Serialize,
+{
+    let path = path.as_ref();
+    let mut writer = file_write(path).truncate()?;
+    serde_json::to_writer(&mut writer, value).map_err(|err| json_write_error(path, err))?;
+    writer.finish()
+}
+
+/// Serialize `value` as pretty-printed JSON and write it as the entire contents of a file.
+///
+/// Behaves like [`write_json`], but indents the output.
+#[cfg(feature = "json")]
+pub fn write_json_pretty<P, T>(path: P, value: &T) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+    T: ?Sized + serde::Serialize,
+{
+    let path = path.as_ref();
+    let mut writer = file_write(path).truncate()?;
+    serde_json::to_writer_pretty(&mut writer, value).map_err(|err| json_write_error(path, err))?;
+    writer.finish()
+}
+
+/// Convert the error of serializing into `path` into the crate error.
+#[cfg(feature = "json")]
+fn json_write_error(path: &Path, err: serde_json::Error) -> Error {
+    if err.is_io() {
+        Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::Write,
+            source: err.into(),
+        }
+    } else {
+        Error::JsonError {
+            file: path.to_path_buf(),
+            source: err,
+        }
+    }
+}
+
+/// Read a TOML file and deserialize it into `T`.
+///
+/// This function supports opening compressed files transparently.
+/// Parsing errors are reported as [`Error::TomlError`] and contain the path of the file.
+#[cfg(feature = "toml")]
+pub fn read_toml<P, T>(path: P) -> Result<T, Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let content = read_to_string(path)?;
+    toml::from_str(&content).map_err(|err| Error::TomlError {
+        file: path.to_path_buf(),
+        source: Box::new(err),
+    })
+}
+
+/// Read a YAML file and deserialize it into `T`.
+///
+/// This function supports opening compressed files transparently.
+/// Parsing errors are reported as [`Error::YamlError`] and contain the path of the file.
+#[cfg(feature = "yaml")]
+pub fn read_yaml<P, T>(path: P) -> Result<T, Error>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let content = read(path)?;
+    serde_yaml_ng::from_slice(&content).map_err(|err| Error::YamlError {
+        file: path.to_path_buf(),
+        source: err,
+    })
+}
+
 /// Write a slice as the entire contents of a file.
 ///
 /// The functions chooses the filetype based on the extension.
@@ -829,12 +1764,12 @@ pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<(),
         .write_all(contents.as_ref())
         .map_err(|err| Error::FileIo {
             file: path.to_path_buf(),
-            msg: "Could not write content to file.",
+            operation: Operation::Write,
             source: err,
         })?;
     writer.flush().map_err(|err| Error::FileIo {
         file: path.to_path_buf(),
-        msg: "Could not write content to file.",
+        operation: Operation::Write,
         source: err,
     })?;
     drop(writer);
@@ -854,27 +1789,470 @@ pub fn append<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> Result<()
         .write_all(contents.as_ref())
         .map_err(|err| Error::FileIo {
             file: path.to_path_buf(),
-            msg: "Could not append to file.",
+            operation: Operation::Write,
             source: err,
         })?;
     writer.flush().map_err(|err| Error::FileIo {
         file: path.to_path_buf(),
-        msg: "Could not append to file.",
+        operation: Operation::Write,
         source: err,
     })?;
     drop(writer);
     Ok(())
 }
 
+/// Options controlling how [`recompress`] writes the destination file.
+///
+/// The default options choose the filetype based on the extension of the destination, use the default compression level, and do not preserve any metadata.
+#[derive(Clone, Copy, Debug)]
+pub struct RecompressOptions {
+    /// Compression level of the destination file.
+    pub(crate) compression_level: Compression,
+    /// FileType of the destination file.
+    ///
+    /// The filetype is guessed from the file extensions using [`guess_file_type`] if unset.
+    pub(crate) filetype: Option<FileType>,
+    /// Carry over timestamps and permissions from the source file.
+    pub(crate) preserve_metadata: bool,
+    /// Number of threads used during compression.
+    pub(crate) threads: u8,
+}
+
+impl RecompressOptions {
+    /// Create a new set of options with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the compression level of the destination file.
+    ///
+    /// See [`WriteBuilder::compression_level`].
+    pub fn compression_level(&mut self, compression_level: Compression) -> &mut Self {
+        self.compression_level = compression_level;
+        self
+    }
+
+    /// Sets the filetype of the destination file.
+    ///
+    /// See [`WriteBuilder::filetype`].
+    pub fn filetype(&mut self, filetype: FileType) -> &mut Self {
+        self.filetype = Some(filetype);
+        self
+    }
+
+    /// Carry over the metadata of the source file to the destination file.
+    ///
+    /// This copies the modification and access times as well as the permissions (including the Unix mode bits).
+    /// The metadata is captured before the source file is read, such that reading does not alter the access time.
+    pub fn preserve_metadata(&mut self, preserve_metadata: bool) -> &mut Self {
+        self.preserve_metadata = preserve_metadata;
+        self
+    }
+
+    /// Specify the maximal number of threads used for compression.
+    ///
+    /// See [`WriteBuilder::threads`].
+    pub fn threads(&mut self, threads: u8) -> &mut Self {
+        self.threads = if threads == 0 { 1 } else { threads };
+        self
+    }
+}
+
+impl Default for RecompressOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: Default::default(),
+            filetype: None,
+            preserve_metadata: false,
+            threads: 1,
+        }
+    }
+}
+
+/// Copy the contents of one file to another file, converting the compression on the way.
+///
+/// The source file is opened with [`file_open_read`], thus compressed files are decompressed transparently.
+/// The destination filetype is chosen based on the extension, like in [`file_write`].
+/// The destination file is truncated before writing.
+///
+/// Returns the number of uncompressed bytes copied.
+/// See [`recompress`] for more options.
+///
+/// The API mirrors the function in [`std::fs::copy`] except for the error type.
+pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> Result<u64, Error> {
+    recompress(src, dst, &RecompressOptions::default())
+}
+
+/// Copy the contents of one file to another file, re-encoding it according to `options`.
+///
+/// The source file is opened with [`file_open_read`], thus compressed files are decompressed transparently.
+/// The destination file is truncated before writing.
+/// See [`RecompressOptions`] for the available options.
+///
+/// If both the source and the destination are plaintext files, the content is copied without passing through userspace buffers.
+/// On Linux this uses `copy_file_range`/`sendfile`, which can share the data extents on filesystems supporting reflinks.
+///
+/// Returns the number of uncompressed bytes copied.
+pub fn recompress<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dst: Q,
+    options: &RecompressOptions,
+) -> Result<u64, Error> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+
+    // Capture the metadata before reading changes the access time
+    let metadata = if options.preserve_metadata {
+        Some(std::fs::metadata(src).map_err(|err| Error::FileIo {
+            file: src.to_path_buf(),
+            operation: Operation::Metadata,
+            source: err,
+        })?)
+    } else {
+        None
+    };
+
+    let dst_filetype = match options.filetype {
+        Some(filetype) => filetype,
+        None => guess_file_type(dst)?,
+    };
+
+    let copied = if dst_filetype == FileType::PlainText && is_plaintext(src)? {
+        debug!(
+            "Copy plaintext file {} to {} without re-encoding",
+            src.display(),
+            dst.display()
+        );
+        copy_plaintext(src, dst)?
+    } else {
+        let mut reader = file_open_read(src)?;
+        let mut writer = file_write(dst)
+            .compression_level(options.compression_level)
+            .filetype(dst_filetype)
+            .threads(options.threads)
+            .truncate()?;
+        let copied = io::copy(&mut reader, &mut writer).map_err(|err| Error::FileIo {
+            file: dst.to_path_buf(),
+            operation: Operation::Write,
+            source: err,
+        })?;
+        // Dropping the writer would hide errors while finishing the compression
+        writer.finish()?;
+        copied
+    };
+
+    if let Some(metadata) = metadata {
+        copy_metadata(&metadata, dst)?;
+    }
+    Ok(copied)
+}
+
+/// Move a file to a new location, also across filesystems.
+///
+/// The file is renamed if possible.
+/// If `src` and `dst` are on different filesystems, the content is copied, synced to disk, and only then `src` is removed.
+/// The timestamps and permissions are preserved in this case.
+/// The content is moved unchanged, see [`move_recompress`] to convert the compression.
+///
+/// The API mirrors the function [`std::fs::rename`] except for the error type and the fallback.
+pub fn move_file<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> Result<(), Error> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+
+    match std::fs::rename(src, dst) {
+        Ok(()) => return Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            debug!(
+                "Cannot rename {} to {} across filesystems, fall back to copying",
+                src.display(),
+                dst.display()
+            );
+        }
+        Err(err) => {
+            return Err(Error::FileIo {
+                file: src.to_path_buf(),
+                operation: Operation::Rename,
+                source: err,
+            })
+        }
+    }
+
+    let metadata = std::fs::metadata(src).map_err(|err| Error::FileIo {
+        file: src.to_path_buf(),
+        operation: Operation::Metadata,
+        source: err,
+    })?;
+    copy_plaintext(src, dst)?;
+    sync_file(dst)?;
+    copy_metadata(&metadata, dst)?;
+    remove_file(src)
+}
+
+/// Move a file to a new location, re-encoding it according to `options`.
+///
+/// If the source already has the compression of the destination, the file is moved using [`move_file`].
+/// Otherwise the file is converted like in [`recompress`], the result is synced to disk, and then `src` is removed.
+/// The destination filetype is chosen based on the extension, unless set in the `options`.
+pub fn move_recompress<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P,
+    dst: Q,
+    options: &RecompressOptions,
+) -> Result<(), Error> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+
+    let dst_filetype = match options.filetype {
+        Some(filetype) => filetype,
+        None => guess_file_type(dst)?,
+    };
+    let src_filetype = file_type_from_compression(src, read_magic(src)?)?;
+    if src_filetype == dst_filetype {
+        return move_file(src, dst);
+    }
+
+    let metadata = std::fs::metadata(src).map_err(|err| Error::FileIo {
+        file: src.to_path_buf(),
+        operation: Operation::Metadata,
+        source: err,
+    })?;
+    // The metadata is applied after syncing, since it might make the file read-only
+    let mut recompress_options = *options;
+    recompress_options
+        .filetype(dst_filetype)
+        .preserve_metadata(false);
+    recompress(src, dst, &recompress_options)?;
+    sync_file(dst)?;
+    if options.preserve_metadata {
+        copy_metadata(&metadata, dst)?;
+    }
+    remove_file(src)
+}
+
+/// Remove a file, if it exists.
+///
+/// Returns `true` if the file was removed and `false` if it did not exist.
+/// All other errors, like missing permissions, are returned.
+pub fn remove_file_if_exists<P: AsRef<Path>>(path: P) -> Result<bool, Error> {
+    let path = path.as_ref();
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::Remove,
+            source: err,
+        }),
+    }
+}
+
+/// Recursively create a directory and all of its parents, if they do not exist.
+///
+/// Succeeds if the directory already exists, even if it is created concurrently by another process.
+/// Fails if the path exists, but is not a directory.
+///
+/// The API mirrors the function [`std::fs::create_dir_all`] except for the error type.
+pub fn create_dir_all_idempotent<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    match std::fs::create_dir_all(path) {
+        Ok(()) => Ok(()),
+        Err(_) if path.is_dir() => Ok(()),
+        Err(err) => Err(Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::CreateDir,
+            source: err,
+        }),
+    }
+}
+
+/// Create all missing parent directories of `path`.
+///
+/// This allows creating a file at `path` afterwards, e.g., using [`write`] or [`file_write`].
+/// Nothing happens if `path` has no parent.
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::fs::{self, ensure_parent_dirs};
+/// # let tmpdir = tempfile::Builder::new().tempdir()?;
+/// # let path = tmpdir.path().join("out/new/dir/file.gz");
+/// ensure_parent_dirs(&path)?;
+/// fs::write(&path, "Hello World")?;
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn ensure_parent_dirs<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    match path.as_ref().parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => create_dir_all_idempotent(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Make sure `path` is an empty directory.
+///
+/// The directory is created if it does not exist.
+/// If it exists, all of its content is removed.
+/// Symlinks inside the directory are removed, but not followed.
+pub fn ensure_empty_dir<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    create_dir_all_idempotent(path)?;
+
+    let entries = std::fs::read_dir(path).map_err(|err| Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::ReadDir,
+        source: err,
+    })?;
+    for entry in entries {
+        let entry = entry.map_err(|err| Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::ReadDir,
+            source: err,
+        })?;
+        let entry_path = entry.path();
+        // `DirEntry::file_type` does not follow symlinks
+        if entry.file_type().is_ok_and(|ft| ft.is_dir()) {
+            std::fs::remove_dir_all(&entry_path).map_err(|err| Error::FileIo {
+                file: entry_path.clone(),
+                operation: Operation::Remove,
+                source: err,
+            })?;
+        } else {
+            remove_file(&entry_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Flush the content of the file `path` to disk.
+fn sync_file(path: &Path) -> Result<(), Error> {
+    OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.sync_all())
+        .map_err(|err| Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::Sync,
+            source: err,
+        })
+}
+
+/// Remove the file `path`.
+fn remove_file(path: &Path) -> Result<(), Error> {
+    std::fs::remove_file(path).map_err(|err| Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::Remove,
+        source: err,
+    })
+}
+
+/// Test if `file` is a regular file without any known compression magic bytes.
+fn is_plaintext(file: &Path) -> Result<bool, Error> {
+    let metadata = std::fs::metadata(file).map_err(|err| Error::FileIo {
+        file: file.to_path_buf(),
+        operation: Operation::Metadata,
+        source: err,
+    })?;
+    if !metadata.is_file() {
+        return Ok(false);
+    }
+    Ok(read_magic(file)?.is_none())
+}
+
+/// Detect the compression technique of `file` based on the magic bytes.
+fn read_magic(file: &Path) -> Result<Option<&'static str>, Error> {
+    let mut f = File::open(file).map_err(|err| Error::FileIo {
+        file: file.to_path_buf(),
+        operation: Operation::Open,
+        source: err,
+    })?;
+    let mut buffer = [0; 6];
+    if f.read_exact(&mut buffer).is_err() {
+        // Files shorter than the magic bytes cannot be compressed
+        return Ok(None);
+    }
+    Ok(compression_from_magic(&buffer))
+}
+
+/// Copy the content of the plaintext file `src` into `dst` without any buffering in userspace.
+///
+/// Copying between two [`std::fs::File`]s allows [`io::copy`] to use the kernel fast paths, like `copy_file_range` and `sendfile` on Linux.
+/// Depending on the filesystem `copy_file_range` shares the data extents (reflink) instead of duplicating them.
+/// [`io::copy`] falls back to a streaming copy if no fast path is available.
+fn copy_plaintext(src: &Path, dst: &Path) -> Result<u64, Error> {
+    let mut src_file = File::open(src).map_err(|err| Error::FileIo {
+        file: src.to_path_buf(),
+        operation: Operation::Open,
+        source: err,
+    })?;
+    let mut dst_file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(dst)
+        .map_err(|err| Error::FileIo {
+            file: dst.to_path_buf(),
+            operation: Operation::Open,
+            source: err,
+        })?;
+    io::copy(&mut src_file, &mut dst_file).map_err(|err| Error::FileIo {
+        file: dst.to_path_buf(),
+        operation: Operation::Write,
+        source: err,
+    })
+}
+
+/// Apply the timestamps and permissions from `metadata` onto the file `dst`.
+pub(crate) fn copy_metadata(metadata: &std::fs::Metadata, dst: &Path) -> Result<(), Error> {
+    let mut times = FileTimes::new();
+    // Not all platforms support all timestamps
+    if let Ok(accessed) = metadata.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = metadata.modified() {
+        times = times.set_modified(modified);
+    }
+
+    // Set the times before the permissions, since the permissions might make the file read-only
+    let file = OpenOptions::new()
+        .write(true)
+        .open(dst)
+        .map_err(|err| Error::FileIo {
+            file: dst.to_path_buf(),
+            operation: Operation::Open,
+            source: err,
+        })?;
+    file.set_times(times).map_err(|err| Error::FileIo {
+        file: dst.to_path_buf(),
+        operation: Operation::SetMetadata,
+        source: err,
+    })?;
+    drop(file);
+    std::fs::set_permissions(dst, metadata.permissions()).map_err(|err| Error::FileIo {
+        file: dst.to_path_buf(),
+        operation: Operation::SetMetadata,
+        source: err,
+    })
+}
+
 /// Guess the [`FileType`] from the path extension
 ///
 /// The function will error if a compressed extension is recognized but the corresponding `file-*` feature is not enabled.
 /// The function falls back to [`FileType::PlainText`] if the extension is not recognized.
+pub(crate) fn guess_file_type(path: &Path) -> Result<FileType, Error> {
+    file_type_from_compression(path, compression_from_extension(path))
+}
+
+/// Convert the name of a compression technique into the [`FileType`].
+///
+/// `None` is the [`FileType::PlainText`].
+/// The function will error if the `file-*` feature of the compression technique is not enabled.
 //
 // The warning triggers with --all-features as then all error conditions are removed.
 #[allow(clippy::unnecessary_wraps)]
-fn guess_file_type(path: &Path) -> Result<FileType, Error> {
-    match path.extension().and_then(OsStr::to_str) {
+#[cfg_attr(
+    all(feature = "file-bz2", feature = "file-gz", feature = "file-xz"),
+    allow(unused_variables)
+)]
+fn file_type_from_compression(
+    path: &Path,
+    compression: Option<&'static str>,
+) -> Result<FileType, Error> {
+    match compression {
         Some("xz") => {
             #[cfg(feature = "file-xz")]
             {
@@ -889,7 +2267,7 @@ fn guess_file_type(path: &Path) -> Result<FileType, Error> {
             }
         }
 
-        Some("gzip") | Some("gz") => {
+        Some("gz") => {
             #[cfg(feature = "file-gz")]
             {
                 Ok(FileType::Gz)
@@ -903,7 +2281,7 @@ fn guess_file_type(path: &Path) -> Result<FileType, Error> {
             }
         }
 
-        Some("bzip") | Some("bz2") => {
+        Some("bz2") => {
             #[cfg(feature = "file-bz2")]
             {
                 Ok(FileType::Bz2)
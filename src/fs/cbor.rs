@@ -0,0 +1,312 @@
+//! Reading and writing of [CBOR sequences].
+//!
+//! A CBOR sequence is the concatenation of zero or more CBOR items, without any separators.
+//! Reading uses the same architecture as the JSONL parser:
+//! A reading thread decodes the items into generic [`Value`]s and a parsing thread deserializes them into the requested type.
+//!
+//! [CBOR sequences]: https://www.rfc-editor.org/rfc/rfc8742
+
+use super::{file_open_read, file_write, CompressedWriter};
+use crate::error::{Error, MtCborError, Operation};
+use ciborium::Value;
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+};
+
+/// Number of batches buffered in the channels.
+const CHAN_BUFSIZE: usize = 2;
+/// Number of items passed to the parsing thread at once.
+const BATCH_SIZE: usize = 1024;
+
+/// Message passed between the threads.
+///
+/// Every user of the channel **must** verify that the `Completed` variant occurs, to ensure the whole file has been read.
+#[derive(Debug)]
+enum ProcessingStatus<T>
+where
+    T: 'static + Send,
+{
+    /// Indicates a successful completion of all steps.
+    Completed,
+    /// Wrapper for any user-defined datatype
+    Data(T),
+    Error(MtCborError),
+}
+
+/// An iterator over deserialized CBOR items
+///
+/// This struct is created by the [`parse_cbor_seq`] function.
+#[derive(Debug)]
+pub struct MtCborSeq<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    iter: mpsc::IntoIter<ProcessingStatus<Vec<Result<T, MtCborError>>>>,
+    tmp_state: std::vec::IntoIter<Result<T, MtCborError>>,
+    did_complete: bool,
+}
+
+impl<T> Iterator for MtCborSeq<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    type Item = Result<T, MtCborError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(res) = self.tmp_state.next() {
+                return Some(res);
+            } else if self.did_complete {
+                return None;
+            } else if let Some(state) = self.iter.next() {
+                match state {
+                    ProcessingStatus::Data(data) => self.tmp_state = data.into_iter(),
+                    ProcessingStatus::Completed => self.did_complete = true,
+                    // path through error
+                    ProcessingStatus::Error(err) => return Some(Err(err)),
+                }
+                continue;
+            }
+
+            // No more data to read from underlying iterators
+            return Some(Err(MtCborError::NotCompleted));
+        }
+    }
+}
+
+/// Create a multi-threaded parser for [CBOR sequences].
+///
+/// This returns an iterator over `Result<T>`.
+/// If any reading errors of the file or parsing errors happen they will be passed to the caller of the iterator.
+///
+/// This works like [`parse_jsonl_multi_threaded`](super::parse_jsonl_multi_threaded).
+/// The first thread decodes the items of the file, using [`file_open_read`], thus it also supports compressed files transparently.
+/// The second thread deserializes the items into `T`.
+/// Malformed CBOR data ends the iteration with a [`MtCborError::DecodingError`], since the start of the next item cannot be found.
+///
+/// This function is only available with the `cbor` feature.
+///
+/// [CBOR sequences]: https://www.rfc-editor.org/rfc/rfc8742
+pub fn parse_cbor_seq<P, T>(path: P) -> MtCborSeq<T>
+where
+    P: AsRef<Path>,
+    T: 'static + DeserializeOwned + Send,
+{
+    let path = path.as_ref().to_path_buf();
+
+    // create channels
+    let (values_sender, values_receiver) = mpsc::sync_channel(CHAN_BUFSIZE);
+    let (struct_sender, struct_receiver) = mpsc::sync_channel(CHAN_BUFSIZE);
+
+    // spawn reader thread of file
+    thread::spawn(move || read_batches(&path, &values_sender));
+
+    // spawn CBOR parser
+    thread::spawn(move || parse_batches::<T>(&values_receiver, &struct_sender));
+
+    MtCborSeq {
+        iter: struct_receiver.into_iter(),
+        tmp_state: vec![].into_iter(),
+        did_complete: false,
+    }
+}
+
+/// Decode batches of items from the file and send them to the parsing thread.
+fn read_batches(path: &Path, values_sender: &mpsc::SyncSender<ProcessingStatus<Vec<Value>>>) {
+    info!(
+        "Start background reading thread: {:?}",
+        thread::current().id()
+    );
+    let mut rdr = match file_open_read(path) {
+        Ok(rdr) => BufReader::new(rdr),
+        Err(err) => {
+            warn!(
+                "Background reading thread cannot open file {} {:?}",
+                path.display(),
+                thread::current().id()
+            );
+            // cannot communicate channel failures
+            let _ = values_sender.send(ProcessingStatus::Error(err.into()));
+            return;
+        }
+    };
+
+    let mut is_eof = false;
+    while !is_eof {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        while batch.len() < BATCH_SIZE {
+            match read_value(path, &mut rdr) {
+                Ok(Some(value)) => batch.push(value),
+                Ok(None) => {
+                    is_eof = true;
+                    break;
+                }
+                Err(err) => {
+                    warn!(
+                        "Background reading thread cannot decode item {:?}",
+                        thread::current().id()
+                    );
+                    // Send the items decoded so far, the error ends the sequence
+                    let _ = values_sender.send(ProcessingStatus::Data(batch));
+                    // cannot communicate channel failures
+                    let _ = values_sender.send(ProcessingStatus::Error(err));
+                    return;
+                }
+            }
+        }
+        // cannot communicate channel failures
+        if values_sender.send(ProcessingStatus::Data(batch)).is_err() {
+            // kill on sent error
+            return;
+        }
+        info!(
+            "Background reading thread: sent batch {:?}",
+            thread::current().id()
+        );
+    }
+    // cannot communicate channel failures
+    let _ = values_sender.send(ProcessingStatus::Completed);
+    info!(
+        "Background reading thread: successful processed file {:?} {:?}",
+        path,
+        thread::current().id()
+    );
+}
+
+/// Decode the next item, returns `None` at the end of the file.
+fn read_value(path: &Path, rdr: &mut impl BufRead) -> Result<Option<Value>, MtCborError> {
+    let io_error = |err| Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::Read,
+        source: err,
+    };
+    if rdr.fill_buf().map_err(io_error)?.is_empty() {
+        return Ok(None);
+    }
+    match ciborium::from_reader(rdr) {
+        Ok(value) => Ok(Some(value)),
+        // A truncated item is malformed data, not an I/O problem
+        Err(ciborium::de::Error::Io(err)) if err.kind() != io::ErrorKind::UnexpectedEof => {
+            Err(io_error(err).into())
+        }
+        Err(err) => Err(MtCborError::DecodingError { source: err }),
+    }
+}
+
+/// Parse the batches received from the reading thread until the channel closes.
+fn parse_batches<T>(
+    values_receiver: &mpsc::Receiver<ProcessingStatus<Vec<Value>>>,
+    struct_sender: &mpsc::SyncSender<ProcessingStatus<Vec<Result<T, MtCborError>>>>,
+) where
+    T: 'static + DeserializeOwned + Send,
+{
+    info!(
+        "Start background parsing thread {:?}",
+        thread::current().id()
+    );
+    for status in values_receiver {
+        let status = match status {
+            ProcessingStatus::Error(e) => ProcessingStatus::Error(e),
+            ProcessingStatus::Completed => {
+                info!(
+                    "Background parsing thread: successfully completed {:?}",
+                    thread::current().id()
+                );
+                ProcessingStatus::Completed
+            }
+            ProcessingStatus::Data(batch) => ProcessingStatus::Data(
+                batch
+                    .iter()
+                    .map(|value| Ok(value.deserialized()?))
+                    .collect(),
+            ),
+        };
+        // cannot communicate channel failures
+        if struct_sender.send(status).is_err() {
+            warn!(
+                "Background parsing thread: sent channel error {:?}",
+                thread::current().id()
+            );
+            // kill on send error
+            return;
+        }
+    }
+}
+
+/// Writer for [CBOR sequences].
+///
+/// The file is compressed based on the file extension, like [`file_write`].
+/// Call [`CborSeqWriter::finish`] to ensure all data is written to the file.
+///
+/// This type is only available with the `cbor` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::CborSeqWriter;
+/// #
+/// # fn main() -> Result<(), anyhow::Error> {
+/// let mut writer = CborSeqWriter::create("./telemetry.cbor.xz")?;
+/// for reading in [1.5, 2.25, 3.0] {
+///     writer.write(&reading)?;
+/// }
+/// writer.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [CBOR sequences]: https://www.rfc-editor.org/rfc/rfc8742
+#[derive(Debug)]
+pub struct CborSeqWriter {
+    path: PathBuf,
+    writer: CompressedWriter,
+}
+
+impl CborSeqWriter {
+    /// Create a new file, truncating any existing file.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: file_write(path).truncate()?,
+        })
+    }
+
+    /// Append items to an existing file.
+    ///
+    /// A CBOR sequence stays valid when items are appended.
+    /// Appending only works for plaintext and gzip files, see [`WriteBuilder::append`](super::WriteBuilder::append).
+    pub fn append<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: file_write(path).append()?,
+        })
+    }
+
+    /// Serialize `value` and append it to the sequence.
+    ///
+    /// The item is serialized completely before it is written, such that a serialization error does not leave a partial item in the file.
+    pub fn write<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let mut item = Vec::new();
+        ciborium::into_writer(value, &mut item).map_err(|err| Error::CborError {
+            file: self.path.clone(),
+            source: err,
+        })?;
+        self.writer.write_all(&item).map_err(|err| Error::FileIo {
+            file: self.path.clone(),
+            operation: Operation::Write,
+            source: err,
+        })
+    }
+
+    /// Flush the remaining data and finish the compression.
+    pub fn finish(self) -> Result<(), Error> {
+        self.writer.finish()
+    }
+}
@@ -0,0 +1,361 @@
+//! Multi-threaded parsing of CSV files.
+//!
+//! A reading thread splits the file into batches of records.
+//! A parsing thread deserializes the records of each batch.
+
+use super::file_open_read;
+use crate::error::{Error, MtCsvError, Operation};
+use ::csv::{ByteRecord, ReaderBuilder};
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use std::{
+    path::Path,
+    sync::{mpsc, Arc},
+    thread,
+};
+
+/// Number of batches buffered in the channels.
+const CHAN_BUFSIZE: usize = 2;
+
+/// Message passed between the threads.
+///
+/// Every user of the channel **must** verify that the `Completed` variant occurs, to ensure the whole file has been read.
+#[derive(Debug)]
+enum ProcessingStatus<T>
+where
+    T: 'static + Send,
+{
+    /// Indicates a successful completion of all steps.
+    Completed,
+    /// Wrapper for any user-defined datatype
+    Data(T),
+    Error(MtCsvError),
+}
+
+/// Records read from the file, together with the header record.
+#[derive(Debug)]
+struct RecordBatch {
+    headers: Option<Arc<ByteRecord>>,
+    records: Vec<Result<ByteRecord, ::csv::Error>>,
+}
+
+/// Options for [`parse_csv_multi_threaded`].
+#[derive(Clone, Copy, Debug)]
+pub struct CsvOptions {
+    /// Number of records passed to the parsing thread at once.
+    batch_size: u32,
+    /// Field delimiter
+    delimiter: u8,
+    /// Allow records with a different number of fields.
+    flexible: bool,
+    /// The first record contains the field names.
+    has_headers: bool,
+    /// Quote character
+    quote: u8,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 1024,
+            delimiter: b',',
+            flexible: false,
+            has_headers: true,
+            quote: b'"',
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Create a new set of options with the default values.
+    ///
+    /// By default, the fields are separated by `,`, quoted with `"`, and the first record contains the field names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of records passed to the parsing thread at once.
+    ///
+    /// Setting this value to `0` has the same effect as setting it to `1`.
+    pub fn batch_size(&mut self, batch_size: u32) -> &mut Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the field delimiter, e.g., `b'\t'` for TSV files.
+    pub fn delimiter(&mut self, delimiter: u8) -> &mut Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Allow records with a different number of fields.
+    pub fn flexible(&mut self, flexible: bool) -> &mut Self {
+        self.flexible = flexible;
+        self
+    }
+
+    /// Sets whether the first record contains the field names.
+    ///
+    /// The field names are used to deserialize structs by name.
+    /// Without headers, the fields are deserialized by position.
+    pub fn has_headers(&mut self, has_headers: bool) -> &mut Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Sets the quote character.
+    pub fn quote(&mut self, quote: u8) -> &mut Self {
+        self.quote = quote;
+        self
+    }
+
+    fn reader_builder(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder
+            .delimiter(self.delimiter)
+            .flexible(self.flexible)
+            .has_headers(self.has_headers)
+            .quote(self.quote);
+        builder
+    }
+}
+
+/// An iterator over deserialized CSV records
+///
+/// This struct is created by the [`parse_csv_multi_threaded`] function.
+#[derive(Debug)]
+pub struct MtCsv<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    iter: mpsc::IntoIter<ProcessingStatus<Vec<Result<T, MtCsvError>>>>,
+    tmp_state: std::vec::IntoIter<Result<T, MtCsvError>>,
+    did_complete: bool,
+}
+
+impl<T> Iterator for MtCsv<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    type Item = Result<T, MtCsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(res) = self.tmp_state.next() {
+                return Some(res);
+            } else if self.did_complete {
+                return None;
+            } else if let Some(state) = self.iter.next() {
+                match state {
+                    ProcessingStatus::Data(data) => self.tmp_state = data.into_iter(),
+                    ProcessingStatus::Completed => self.did_complete = true,
+                    // path through error
+                    ProcessingStatus::Error(err) => return Some(Err(err)),
+                }
+                continue;
+            }
+
+            // No more data to read from underlying iterators
+            return Some(Err(MtCsvError::NotCompleted));
+        }
+    }
+}
+
+/// Create a multi-threaded CSV parser.
+///
+/// This returns an iterator over `Result<T>`.
+/// If any reading errors of the file or parsing errors happen they will be passed to the caller of the iterator.
+///
+/// This works like [`parse_jsonl_multi_threaded`](super::parse_jsonl_multi_threaded).
+/// The first thread reads the records from the file, using [`file_open_read`], thus it also supports compressed files transparently.
+/// The second thread deserializes the records into `T`.
+/// Use [`CsvOptions`] to configure the format of the file and the size of the batches passed between the threads.
+///
+/// This function is only available with the `csv` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::{parse_csv_multi_threaded, CsvOptions};
+/// # use serde::Deserialize;
+/// #
+/// #[derive(Deserialize)]
+/// struct Row {
+///     name: String,
+///     count: u64,
+/// }
+///
+/// # fn main() -> Result<(), anyhow::Error> {
+/// for row in parse_csv_multi_threaded::<_, Row>("./data.csv.gz", &CsvOptions::new()) {
+///     let row = row?;
+///     println!("{}: {}", row.name, row.count);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_csv_multi_threaded<P, T>(path: P, options: &CsvOptions) -> MtCsv<T>
+where
+    P: AsRef<Path>,
+    T: 'static + DeserializeOwned + Send,
+{
+    let path = path.as_ref().to_path_buf();
+    let options = *options;
+
+    // create channels
+    let (records_sender, records_receiver) = mpsc::sync_channel(CHAN_BUFSIZE);
+    let (struct_sender, struct_receiver) = mpsc::sync_channel(CHAN_BUFSIZE);
+
+    // spawn reader thread of file
+    thread::spawn(move || read_batches(&path, &options, &records_sender));
+
+    // spawn CSV parser
+    thread::spawn(move || parse_batches::<T>(&records_receiver, &struct_sender));
+
+    MtCsv {
+        iter: struct_receiver.into_iter(),
+        tmp_state: vec![].into_iter(),
+        did_complete: false,
+    }
+}
+
+/// Read batches of records from the file and send them to the parsing thread.
+fn read_batches(
+    path: &Path,
+    options: &CsvOptions,
+    records_sender: &mpsc::SyncSender<ProcessingStatus<RecordBatch>>,
+) {
+    info!(
+        "Start background reading thread: {:?}",
+        thread::current().id()
+    );
+    let mut rdr = match file_open_read(path) {
+        Ok(rdr) => options.reader_builder().from_reader(rdr),
+        Err(err) => {
+            warn!(
+                "Background reading thread cannot open file {} {:?}",
+                path.display(),
+                thread::current().id()
+            );
+            // cannot communicate channel failures
+            let _ = records_sender.send(ProcessingStatus::Error(err.into()));
+            return;
+        }
+    };
+    let headers = if options.has_headers {
+        match rdr.byte_headers() {
+            Ok(headers) => Some(Arc::new(headers.clone())),
+            Err(err) => {
+                // cannot communicate channel failures
+                let _ = records_sender.send(ProcessingStatus::Error(read_error(path, err)));
+                return;
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut is_eof = false;
+    while !is_eof {
+        let mut batch = RecordBatch {
+            headers: headers.clone(),
+            records: Vec::new(),
+        };
+        for _ in 0..options.batch_size {
+            let mut record = ByteRecord::new();
+            match rdr.read_byte_record(&mut record) {
+                Ok(false) => {
+                    is_eof = true;
+                    break;
+                }
+                Ok(true) => batch.records.push(Ok(record)),
+                Err(err) if err.is_io_error() => {
+                    warn!(
+                        "Background reading thread cannot read record {:?}",
+                        thread::current().id()
+                    );
+                    // cannot communicate channel failures
+                    let _ = records_sender.send(ProcessingStatus::Error(read_error(path, err)));
+                    return;
+                }
+                // Malformed records only affect the record itself
+                Err(err) => batch.records.push(Err(err)),
+            }
+        }
+        // cannot communicate channel failures
+        if records_sender.send(ProcessingStatus::Data(batch)).is_err() {
+            // kill on sent error
+            return;
+        }
+        info!(
+            "Background reading thread: sent batch {:?}",
+            thread::current().id()
+        );
+    }
+    // cannot communicate channel failures
+    let _ = records_sender.send(ProcessingStatus::Completed);
+    info!(
+        "Background reading thread: successful processed file {:?} {:?}",
+        path,
+        thread::current().id()
+    );
+}
+
+/// Convert an error of the CSV reader, keeping the path for I/O errors.
+fn read_error(path: &Path, err: ::csv::Error) -> MtCsvError {
+    if !err.is_io_error() {
+        return err.into();
+    }
+    match err.into_kind() {
+        ::csv::ErrorKind::Io(err) => Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::Read,
+            source: err,
+        }
+        .into(),
+        _ => unreachable!("is_io_error guarantees the Io kind"),
+    }
+}
+
+/// Parse the batches received from the reading thread until the channel closes.
+fn parse_batches<T>(
+    records_receiver: &mpsc::Receiver<ProcessingStatus<RecordBatch>>,
+    struct_sender: &mpsc::SyncSender<ProcessingStatus<Vec<Result<T, MtCsvError>>>>,
+) where
+    T: 'static + DeserializeOwned + Send,
+{
+    info!(
+        "Start background parsing thread {:?}",
+        thread::current().id()
+    );
+    for status in records_receiver {
+        let status = match status {
+            ProcessingStatus::Error(e) => ProcessingStatus::Error(e),
+            ProcessingStatus::Completed => {
+                info!(
+                    "Background parsing thread: successfully completed {:?}",
+                    thread::current().id()
+                );
+                ProcessingStatus::Completed
+            }
+            ProcessingStatus::Data(batch) => {
+                let headers = batch.headers.as_deref();
+                ProcessingStatus::Data(
+                    batch
+                        .records
+                        .into_iter()
+                        .map(|record| Ok(record?.deserialize(headers)?))
+                        .collect(),
+                )
+            }
+        };
+        // cannot communicate channel failures
+        if struct_sender.send(status).is_err() {
+            warn!(
+                "Background parsing thread: sent channel error {:?}",
+                thread::current().id()
+            );
+            // kill on send error
+            return;
+        }
+    }
+}
@@ -0,0 +1,2089 @@
+//! Multi-threaded parsing of [JSONL] files.
+//!
+//! A reading thread splits the file into batches of lines.
+//! One or more parsing threads deserialize the batches.
+//! Every batch carries a sequence number, such that [`MtJsonl`] can restore the order of the input, even if multiple parsing threads finish out of order.
+//! It also carries the line number and byte offset of its first line, such that the parsing threads can report the position of every value.
+//!
+//! [JSONL]: http://jsonlines.org/
+
+#[cfg(doc)]
+use super::file_open_read;
+#[cfg(feature = "file-gz")]
+use super::FileType;
+use super::{file_read, file_write, CompressedWriter, Compression, DetectedReader};
+use crate::error::{Error, MtJsonlError, Operation};
+use crate::path::PathExt;
+#[cfg(feature = "file-gz")]
+use flate2::write::GzEncoder;
+use log::{info, warn};
+#[cfg(feature = "rayon")]
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Deserializer;
+use std::{
+    cmp,
+    collections::{BTreeMap, BinaryHeap},
+    error::Error as StdError,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Number of messages buffered in the channels per parsing thread.
+const CHAN_BUFSIZE: usize = 2;
+
+/// Result type for [`parse_jsonl_multi_threaded`].
+///
+/// This enum encapsulates certain error conditions which can occur either during file I/O or JSON
+/// parsing and the data produced by it. Every user of the [`parse_jsonl_multi_threaded`] **must**
+/// verify that the last element of the iteration is the `Complete` variant, to ensure the whole
+/// file has been read and all lines could successfully parsed.
+#[derive(Debug)]
+enum ProcessingStatus<T>
+where
+    T: 'static + Send,
+{
+    /// Indicates a successful completion of all steps.
+    /// Every user **must** verify that this element occurs during iteration.
+    Completed,
+    /// Wrapper for any user-defined datatype
+    Data(T),
+    Error(MtJsonlError),
+    /// All lines of the file were sent, used by [`MtJsonlBuilder::parse_many`].
+    FileCompleted(PathBuf),
+}
+
+/// Message passed between the threads, tagged with the sequence number of the batch.
+type Sequenced<T> = (u64, ProcessingStatus<T>);
+
+/// Lines read from the file, together with the position of the first line.
+#[derive(Debug)]
+struct LineBatch {
+    /// Line number of the first line, starting at 1
+    line: u64,
+    /// Byte offset of the first line
+    offset: u64,
+    /// Content of the lines, including the line terminators
+    text: String,
+    /// Line number and byte offset of every line, if lines were removed by a filter
+    positions: Option<Vec<(u64, u64)>>,
+}
+
+impl LineBatch {
+    /// Line number and byte offset of the `index`-th line of the batch, starting at 0.
+    ///
+    /// `line_start` is the byte index of the line in `text`.
+    fn position(&self, index: usize, line_start: usize) -> (u64, u64) {
+        match &self.positions {
+            Some(positions) => positions
+                .get(index)
+                .or(positions.last())
+                .copied()
+                .unwrap_or((self.line, self.offset)),
+            None => (self.line + index as u64, self.offset + line_start as u64),
+        }
+    }
+}
+
+/// A parsed value of a batch.
+#[derive(Debug)]
+struct Record<T> {
+    /// Content of the line without the line terminator, only kept for [`MtJsonlBuilder::parse_raw`]
+    raw: Option<String>,
+    result: Result<Positioned<T>, MtJsonlError>,
+}
+
+/// Parsed values of a batch.
+type Batch<T> = Vec<Record<T>>;
+
+/// Element returned by [`MtJsonl::next_entry`].
+enum Entry<T> {
+    Record(Record<T>),
+    FileCompleted(PathBuf),
+}
+
+/// Element returned by the [`MtJsonlMany`] iterator.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum JsonlEvent<T> {
+    /// A value parsed from the current file
+    Value(T),
+    /// All values of the file were returned
+    FileCompleted(PathBuf),
+}
+
+/// A value together with its position in the [JSONL] file.
+///
+/// Returned by the [`MtJsonlPositioned`] iterator, see [`MtJsonl::with_positions`].
+///
+/// [JSONL]: http://jsonlines.org/
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Positioned<T> {
+    /// Line number of the value in the file, starting at 1
+    pub line: u64,
+    /// Byte offset of the start of the line in the decompressed file
+    pub offset: u64,
+    /// The parsed value
+    pub value: T,
+}
+
+/// Callback for lines which cannot be parsed.
+type OnError = dyn Fn(&str, &serde_json::Error) + Send + Sync;
+
+/// Filter for the lines, which should be parsed.
+type LineFilter = dyn Fn(&str) -> bool + Send + Sync;
+
+/// Validation of the parsed values, see [`MtJsonlBuilder::validate`].
+type Validator<T> = dyn Fn(&T) -> Result<(), Box<dyn StdError + Send + Sync>> + Send + Sync;
+
+/// Progress of a multi-threaded [JSONL] parser.
+///
+/// The counters are updated by the background threads while they read and parse the file.
+/// Since the threads work ahead, the counters can be larger than the number of values returned by the iterator so far.
+/// Use [`MtJsonl::progress`] to obtain it.
+///
+/// [JSONL]: http://jsonlines.org/
+#[derive(Debug, Default)]
+pub struct JsonlProgress {
+    bytes_read: AtomicU64,
+    lines_read: AtomicU64,
+    values_parsed: AtomicU64,
+    errors: AtomicU64,
+    /// Runtime of the reading thread in nanoseconds
+    read_nanos: AtomicU64,
+    /// Runtime of the last finishing parsing thread in nanoseconds
+    parse_nanos: AtomicU64,
+}
+
+impl JsonlProgress {
+    /// Number of bytes of the decompressed content read from the file.
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    /// Number of lines read from the file.
+    ///
+    /// This includes lines removed by [`MtJsonlBuilder::filter_lines`].
+    pub fn lines_read(&self) -> u64 {
+        self.lines_read.load(Ordering::Relaxed)
+    }
+
+    /// Number of successfully parsed values.
+    pub fn values_parsed(&self) -> u64 {
+        self.values_parsed.load(Ordering::Relaxed)
+    }
+
+    /// Number of parsing errors.
+    ///
+    /// This includes lines skipped by [`MtJsonlBuilder::skip_errors`].
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+}
+
+/// Summary statistics of a multi-threaded [JSONL] parser.
+///
+/// Use [`MtJsonl::finish_stats`] to obtain it.
+///
+/// [JSONL]: http://jsonlines.org/
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct JsonlStats {
+    /// Number of bytes of the decompressed content read from the file, see [`JsonlProgress::bytes_read`].
+    pub bytes_read: u64,
+    /// Number of lines read from the file, see [`JsonlProgress::lines_read`].
+    pub lines_read: u64,
+    /// Number of successfully parsed values, see [`JsonlProgress::values_parsed`].
+    pub values_parsed: u64,
+    /// Number of parsing errors, see [`JsonlProgress::errors`].
+    pub errors: u64,
+    /// Wall time of the reading thread.
+    pub read_time: Duration,
+    /// Wall time of the parsing threads, until the last one finished.
+    pub parse_time: Duration,
+    /// Wall time from creating the iterator until [`MtJsonl::finish_stats`] was called.
+    pub total_time: Duration,
+}
+
+/// An iterator over deserialized JSON objects
+///
+/// This struct is created by the [`parse_jsonl_multi_threaded`] and [`parse_jsonl_multi_worker`] functions.
+/// The values are returned in the order of the file, independent of the number of parsing threads.
+///
+/// Dropping the iterator stops the background threads and waits for them to finish, see [`MtJsonl::abort`].
+#[derive(Debug)]
+pub struct MtJsonl<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    /// Receiver of the parsed batches, `None` after aborting
+    iter: Option<mpsc::IntoIter<Sequenced<Batch<T>>>>,
+    /// Background threads
+    handles: Vec<thread::JoinHandle<()>>,
+    /// Signals the background threads to stop
+    stop: Arc<AtomicBool>,
+    /// Shared with the background threads
+    progress: Arc<JsonlProgress>,
+    /// Creation time of the iterator
+    started: Instant,
+    /// Batches which arrived before their predecessors
+    pending: BTreeMap<u64, ProcessingStatus<Batch<T>>>,
+    /// Sequence number of the next batch to return
+    next_seq: u64,
+    tmp_state: std::vec::IntoIter<Record<T>>,
+    did_complete: bool,
+}
+
+/// An iterator over deserialized JSON objects and their positions in the file
+///
+/// This struct is created by [`MtJsonl::with_positions`].
+#[derive(Debug)]
+pub struct MtJsonlPositioned<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    inner: MtJsonl<T>,
+}
+
+/// An iterator over the deserialized JSON objects of multiple files
+///
+/// This struct is created by [`MtJsonlBuilder::parse_many`] and [`parse_jsonl_multi_threaded_many`].
+#[derive(Debug)]
+pub struct MtJsonlMany<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    inner: MtJsonl<T>,
+}
+
+/// An iterator over the raw lines and the deserialized JSON objects
+///
+/// This struct is created by [`MtJsonlBuilder::parse_raw`].
+#[derive(Debug)]
+pub struct MtJsonlRaw<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    inner: MtJsonl<T>,
+}
+
+impl<T> MtJsonl<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    fn new(
+        iter: mpsc::IntoIter<Sequenced<Batch<T>>>,
+        handles: Vec<thread::JoinHandle<()>>,
+        stop: Arc<AtomicBool>,
+        progress: Arc<JsonlProgress>,
+        started: Instant,
+    ) -> Self {
+        Self {
+            iter: Some(iter),
+            handles,
+            stop,
+            progress,
+            started,
+            pending: BTreeMap::new(),
+            next_seq: 0,
+            tmp_state: vec![].into_iter(),
+            did_complete: false,
+        }
+    }
+
+    /// Receive the next batch in sequence order.
+    ///
+    /// Returns `None` if all parsing threads stopped.
+    fn next_in_sequence(&mut self) -> Option<ProcessingStatus<Batch<T>>> {
+        loop {
+            if let Some(state) = self.pending.remove(&self.next_seq) {
+                self.next_seq += 1;
+                return Some(state);
+            }
+            let (seq, state) = self.iter.as_mut()?.next()?;
+            self.pending.insert(seq, state);
+        }
+    }
+
+    /// Return the line number and byte offset together with every value.
+    ///
+    /// The line numbers start at 1.
+    /// The byte offset points to the start of the line in the decompressed file.
+    pub fn with_positions(self) -> MtJsonlPositioned<T> {
+        MtJsonlPositioned { inner: self }
+    }
+
+    /// Observe the progress of the background threads.
+    ///
+    /// The returned value stays up to date while parsing continues, such that it can be polled periodically, e.g., from a logging thread.
+    pub fn progress(&self) -> Arc<JsonlProgress> {
+        Arc::clone(&self.progress)
+    }
+
+    /// Stop the background threads and wait for them to finish.
+    ///
+    /// The iterator does not return any further values afterwards.
+    /// The threads stop after finishing their current batch, so this blocks while the reading thread waits for a slow file.
+    /// Dropping the iterator calls this function.
+    pub fn abort(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        // Unblock parsing threads waiting to send their batches
+        self.iter = None;
+        self.pending.clear();
+        self.tmp_state = vec![].into_iter();
+        self.did_complete = true;
+        for handle in self.handles.drain(..) {
+            // A panic in a background thread already shows up as a missing batch
+            let _ = handle.join();
+        }
+    }
+
+    /// Stop the background threads and return summary statistics.
+    ///
+    /// Call this after the iterator returned `None` to obtain a report of the whole file.
+    /// Calling it earlier stops the background threads like [`MtJsonl::abort`], and the statistics only cover the part of the file processed so far.
+    pub fn finish_stats(&mut self) -> JsonlStats {
+        self.abort();
+        let progress = &self.progress;
+        JsonlStats {
+            bytes_read: progress.bytes_read(),
+            lines_read: progress.lines_read(),
+            values_parsed: progress.values_parsed(),
+            errors: progress.errors(),
+            read_time: Duration::from_nanos(progress.read_nanos.load(Ordering::Relaxed)),
+            parse_time: Duration::from_nanos(progress.parse_nanos.load(Ordering::Relaxed)),
+            total_time: self.started.elapsed(),
+        }
+    }
+
+    fn next_record(&mut self) -> Option<Record<T>> {
+        loop {
+            match self.next_entry()? {
+                Entry::Record(record) => return Some(record),
+                Entry::FileCompleted(_) => continue,
+            }
+        }
+    }
+
+    fn next_entry(&mut self) -> Option<Entry<T>> {
+        loop {
+            if let Some(record) = self.tmp_state.next() {
+                if let Err(err) = &record.result {
+                    info!("{:?}", err);
+                }
+                return Some(Entry::Record(record));
+            } else if self.did_complete {
+                return None;
+            } else if let Some(state) = self.next_in_sequence() {
+                match state {
+                    ProcessingStatus::Data(data) => self.tmp_state = data.into_iter(),
+                    ProcessingStatus::Completed => self.did_complete = true,
+                    // path through error
+                    ProcessingStatus::Error(err) => {
+                        return Some(Entry::Record(Record {
+                            raw: None,
+                            result: Err(err),
+                        }))
+                    }
+                    ProcessingStatus::FileCompleted(path) => {
+                        return Some(Entry::FileCompleted(path))
+                    }
+                }
+                continue;
+            }
+
+            // No more data to read from underlying iterators
+            return Some(Entry::Record(Record {
+                raw: None,
+                result: Err(MtJsonlError::NotCompleted),
+            }));
+        }
+    }
+}
+
+impl<T> Drop for MtJsonl<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    fn drop(&mut self) {
+        self.abort();
+    }
+}
+
+impl<T> MtJsonlPositioned<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    /// Observe the progress of the background threads.
+    ///
+    /// See [`MtJsonl::progress`].
+    pub fn progress(&self) -> Arc<JsonlProgress> {
+        self.inner.progress()
+    }
+
+    /// Stop the background threads and wait for them to finish.
+    ///
+    /// See [`MtJsonl::abort`].
+    pub fn abort(&mut self) {
+        self.inner.abort();
+    }
+
+    /// Stop the background threads and return summary statistics.
+    ///
+    /// See [`MtJsonl::finish_stats`].
+    pub fn finish_stats(&mut self) -> JsonlStats {
+        self.inner.finish_stats()
+    }
+}
+
+impl<T> MtJsonlMany<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    /// Observe the progress of the background threads.
+    ///
+    /// The counters are summed over all files.
+    /// See [`MtJsonl::progress`].
+    pub fn progress(&self) -> Arc<JsonlProgress> {
+        self.inner.progress()
+    }
+
+    /// Stop the background threads and wait for them to finish.
+    ///
+    /// See [`MtJsonl::abort`].
+    pub fn abort(&mut self) {
+        self.inner.abort();
+    }
+
+    /// Stop the background threads and return summary statistics.
+    ///
+    /// See [`MtJsonl::finish_stats`].
+    pub fn finish_stats(&mut self) -> JsonlStats {
+        self.inner.finish_stats()
+    }
+}
+
+impl<T> MtJsonlRaw<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    /// Observe the progress of the background threads.
+    ///
+    /// See [`MtJsonl::progress`].
+    pub fn progress(&self) -> Arc<JsonlProgress> {
+        self.inner.progress()
+    }
+
+    /// Stop the background threads and wait for them to finish.
+    ///
+    /// See [`MtJsonl::abort`].
+    pub fn abort(&mut self) {
+        self.inner.abort();
+    }
+
+    /// Stop the background threads and return summary statistics.
+    ///
+    /// See [`MtJsonl::finish_stats`].
+    pub fn finish_stats(&mut self) -> JsonlStats {
+        self.inner.finish_stats()
+    }
+}
+
+impl<T> Iterator for MtJsonl<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    type Item = Result<T, MtJsonlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record()
+            .map(|record| record.result.map(|positioned| positioned.value))
+    }
+}
+
+impl<T> Iterator for MtJsonlPositioned<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    type Item = Result<Positioned<T>, MtJsonlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_record().map(|record| record.result)
+    }
+}
+
+impl<T> Iterator for MtJsonlMany<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    type Item = Result<JsonlEvent<T>, MtJsonlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match self.inner.next_entry()? {
+            Entry::Record(record) => record
+                .result
+                .map(|positioned| JsonlEvent::Value(positioned.value)),
+            Entry::FileCompleted(path) => Ok(JsonlEvent::FileCompleted(path)),
+        })
+    }
+}
+
+impl<T> Iterator for MtJsonlRaw<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    type Item = (String, Result<T, MtJsonlError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_record().map(|record| {
+            (
+                record.raw.unwrap_or_default(),
+                record.result.map(|positioned| positioned.value),
+            )
+        })
+    }
+}
+
+/// Builder to configure the multi-threaded [JSONL] parser.
+///
+/// The defaults match [`parse_jsonl_multi_threaded`] with a `batchsize` of 1024.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::MtJsonlBuilder;
+/// #
+/// # fn main() -> Result<(), anyhow::Error> {
+/// let iter = MtJsonlBuilder::new("./data.jsonl.xz".into())
+///     .batch_size(4096)
+///     .channel_capacity(16)
+///     .threads(4)
+///     .parse::<serde_json::Value>();
+/// for value in iter {
+///     println!("{}", value?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [JSONL]: http://jsonlines.org/
+#[derive(Clone)]
+pub struct MtJsonlBuilder {
+    /// Approximate number of bytes passed to the parsing threads at once.
+    batch_bytes: Option<usize>,
+    /// Number of lines passed to the parsing threads at once.
+    batch_size: u32,
+    /// Controls the buffer size of the reader of the file.
+    buffer_capacity: Option<usize>,
+    /// Number of batches buffered in each channel.
+    channel_capacity: Option<usize>,
+    /// Only parse lines matching the filter.
+    filter: Option<Arc<LineFilter>>,
+    /// Paths of the files to parse.
+    paths: Vec<PathBuf>,
+    /// Skip unparseable lines and pass them to the callback.
+    on_error: Option<Arc<OnError>>,
+    /// Line number and byte offset at which parsing starts.
+    start: (u64, u64),
+    /// Number of parsing threads.
+    threads: usize,
+}
+
+impl fmt::Debug for MtJsonlBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MtJsonlBuilder")
+            .field("batch_bytes", &self.batch_bytes)
+            .field("batch_size", &self.batch_size)
+            .field("buffer_capacity", &self.buffer_capacity)
+            .field("channel_capacity", &self.channel_capacity)
+            .field("filter_lines", &self.filter.is_some())
+            .field("paths", &self.paths)
+            .field("skip_errors", &self.on_error.is_some())
+            .field("start", &self.start)
+            .field("threads", &self.threads)
+            .finish()
+    }
+}
+
+impl MtJsonlBuilder {
+    /// Create a new [`MtJsonlBuilder`] for a given path.
+    ///
+    /// See the individual methods for the available configuration options.
+    pub fn new(path: PathBuf) -> Self {
+        Self::new_many(vec![path])
+    }
+
+    /// Create a new [`MtJsonlBuilder`] for multiple files.
+    ///
+    /// The files are parsed one after the other, through the same threads.
+    /// Use [`MtJsonlBuilder::parse_many`] to learn when a file is completed.
+    pub fn new_many<I>(paths: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        MtJsonlBuilder {
+            paths: paths
+                .into_iter()
+                .map(|path| path.as_ref().to_path_buf())
+                .collect(),
+
+            batch_bytes: None,
+            batch_size: 1024,
+            buffer_capacity: None,
+            channel_capacity: None,
+            filter: None,
+            on_error: None,
+            start: (1, 0),
+            threads: 1,
+        }
+    }
+
+    /// Sets the number of lines passed to the parsing threads at once.
+    ///
+    /// Larger batches reduce the communication overhead between the threads.
+    /// Setting this value to `0` has the same effect as setting it to `1`.
+    pub fn batch_size(&mut self, batch_size: u32) -> &mut Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Limit the size of a batch to approximately `batch_bytes` bytes.
+    ///
+    /// A batch ends after the line, which reaches the limit, so a batch always contains at least one line.
+    /// This bounds the memory used by each batch, even if the length of the lines varies a lot.
+    /// The limit of [`MtJsonlBuilder::batch_size`] still applies, whichever is reached first.
+    /// Use `batch_size(u32::MAX)` to only batch by size.
+    pub fn batch_bytes(&mut self, batch_bytes: usize) -> &mut Self {
+        self.batch_bytes = Some(batch_bytes);
+        self
+    }
+
+    /// Sets the buffer size of the reader of the file in Bytes.
+    ///
+    /// See [`ReadBuilder::buffer_capacity`](super::ReadBuilder::buffer_capacity).
+    pub fn buffer_capacity(&mut self, buffer_capacity: usize) -> &mut Self {
+        self.buffer_capacity = Some(buffer_capacity);
+        self
+    }
+
+    /// Sets the number of batches, which can be buffered between the threads.
+    ///
+    /// A larger capacity allows the reading thread to read further ahead, which helps with high-latency storage.
+    /// By default, two batches per parsing thread are buffered.
+    /// Setting this value to `0` has the same effect as setting it to `1`.
+    pub fn channel_capacity(&mut self, channel_capacity: usize) -> &mut Self {
+        self.channel_capacity = Some(channel_capacity.max(1));
+        self
+    }
+
+    /// Sets the number of parsing threads.
+    ///
+    /// Multiple threads help if deserializing the values is more expensive than reading the file.
+    /// The values are returned in the same order as in the file, independent of the number of threads.
+    /// Setting this value to `0` has the same effect as setting it to `1`.
+    pub fn threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Only parse lines for which `filter` returns `true`.
+    ///
+    /// The filter runs on the reading thread and receives the line without the line terminator.
+    /// A cheap check, like searching for a substring, avoids deserializing lines, which are not relevant.
+    /// Removed lines still count towards [`MtJsonlBuilder::batch_size`] and do not affect the reported positions.
+    pub fn filter_lines<F>(&mut self, filter: F) -> &mut Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Start parsing at the line `line`, which starts at the byte offset `offset`.
+    ///
+    /// This allows resuming an interrupted run from a checkpoint.
+    /// Pass the position of the first value which has not been processed yet, as returned by [`MtJsonl::with_positions`].
+    /// The line number is only used to report the positions, the file is not checked.
+    ///
+    /// Plaintext files are read starting at the offset directly.
+    /// Compressed files are decompressed from the start, but the content before the offset is not parsed.
+    /// See [`ReadBuilder::offset`](super::ReadBuilder::offset).
+    ///
+    /// With multiple files, this applies to the first file.
+    pub fn resume_from(&mut self, line: u64, offset: u64) -> &mut Self {
+        self.start = (line, offset);
+        self
+    }
+
+    /// Skip lines, which cannot be parsed, instead of returning an error.
+    ///
+    /// The callback `on_error` receives the raw line and the parsing error.
+    /// It is called on the parsing threads, so it must be [`Send`] and [`Sync`].
+    ///
+    /// In this mode, every line is parsed separately, such that an error does not affect the other lines of the batch.
+    /// Empty lines are ignored.
+    /// Errors while reading the file are still returned by the iterator.
+    pub fn skip_errors<F>(&mut self, on_error: F) -> &mut Self
+    where
+        F: Fn(&str, &serde_json::Error) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(on_error));
+        self
+    }
+
+    /// Validate every parsed value.
+    ///
+    /// The callback `validator` runs on the parsing threads for every successfully parsed value.
+    /// If it returns an error, the value is replaced by a [`MtJsonlError::ValidationError`] containing the position of the line.
+    /// This flags lines, which are valid JSON but violate other constraints of the application, already during parsing.
+    ///
+    /// The validator fixes the type of the parsed values.
+    /// The returned builder starts the parser with the current configuration.
+    pub fn validate<T, F, E>(&self, validator: F) -> MtJsonlValidatedBuilder<T>
+    where
+        F: Fn(&T) -> Result<(), E> + Send + Sync + 'static,
+        E: Into<Box<dyn StdError + Send + Sync>>,
+    {
+        MtJsonlValidatedBuilder {
+            builder: self.clone(),
+            validator: Arc::new(move |value: &T| validator(value).map_err(Into::into)),
+        }
+    }
+
+    /// Start reading and parsing the file in background threads.
+    ///
+    /// Returns an iterator over the parsed values.
+    /// See [`parse_jsonl_multi_threaded`] for details.
+    pub fn parse<T>(&self) -> MtJsonl<T>
+    where
+        T: 'static + DeserializeOwned + Send,
+    {
+        self.spawn(false, None)
+    }
+
+    /// Start reading and parsing all files in background threads.
+    ///
+    /// Returns an iterator over the parsed values, which also reports when all values of a file were returned.
+    /// Errors while opening or reading a file end the iteration, like for a single file.
+    pub fn parse_many<T>(&self) -> MtJsonlMany<T>
+    where
+        T: 'static + DeserializeOwned + Send,
+    {
+        MtJsonlMany {
+            inner: self.spawn(false, None),
+        }
+    }
+
+    /// Start reading and parsing the file in background threads, keeping the raw lines.
+    ///
+    /// Returns an iterator over the content of every line, without the line terminator, and the value parsed from it.
+    /// This allows writing unparseable lines verbatim to another file or passing on lines unchanged without serializing the value again.
+    /// Errors while reading the file are returned with an empty line.
+    ///
+    /// In this mode, every line is parsed separately, like with [`MtJsonlBuilder::skip_errors`].
+    /// Empty lines are ignored.
+    pub fn parse_raw<T>(&self) -> MtJsonlRaw<T>
+    where
+        T: 'static + DeserializeOwned + Send,
+    {
+        MtJsonlRaw {
+            inner: self.spawn(true, None),
+        }
+    }
+
+    /// Spawn the reading and parsing threads.
+    fn spawn<T>(&self, keep_raw: bool, validator: Option<Arc<Validator<T>>>) -> MtJsonl<T>
+    where
+        T: 'static + DeserializeOwned + Send,
+    {
+        let started = Instant::now();
+        let channel_capacity = self.channel_capacity.unwrap_or(CHAN_BUFSIZE * self.threads);
+
+        // create channels
+        let (lines_sender, lines_receiver) = mpsc::sync_channel(channel_capacity);
+        let (struct_sender, struct_receiver) = mpsc::sync_channel(channel_capacity);
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let progress = Arc::new(JsonlProgress::default());
+        let mut handles = Vec::with_capacity(self.threads + 1);
+
+        // spawn reader thread of file
+        let builder = self.clone();
+        let reader_stop = Arc::clone(&stop);
+        let reader_progress = Arc::clone(&progress);
+        handles.push(thread::spawn(move || {
+            read_batches(&builder, lines_sender, &reader_stop, &reader_progress);
+            reader_progress
+                .read_nanos
+                .store(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        }));
+
+        // spawn JSONL parsers
+        let lines_receiver = Arc::new(Mutex::new(lines_receiver));
+        for _ in 0..self.threads {
+            let lines_receiver = Arc::clone(&lines_receiver);
+            let struct_sender = struct_sender.clone();
+            let on_error = self.on_error.clone();
+            let validator = validator.clone();
+            let stop = Arc::clone(&stop);
+            let progress = Arc::clone(&progress);
+            handles.push(thread::spawn(move || {
+                parse_batches::<T>(
+                    &lines_receiver,
+                    &struct_sender,
+                    on_error.as_deref(),
+                    validator.as_deref(),
+                    keep_raw,
+                    &stop,
+                    &progress,
+                );
+                progress
+                    .parse_nanos
+                    .fetch_max(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+            }));
+        }
+        // The iterator ends once all parsing threads dropped their senders
+        drop(struct_sender);
+
+        MtJsonl::new(
+            struct_receiver.into_iter(),
+            handles,
+            stop,
+            progress,
+            started,
+        )
+    }
+}
+
+/// A [`MtJsonlBuilder`] which validates every parsed value of type `T`.
+///
+/// This is created by [`MtJsonlBuilder::validate`].
+/// The validator fixes the type of the parsed values, so only values of type `T` can be parsed.
+pub struct MtJsonlValidatedBuilder<T> {
+    builder: MtJsonlBuilder,
+    validator: Arc<Validator<T>>,
+}
+
+// Manual implementation, since `T` does not need to implement `Clone`
+impl<T> Clone for MtJsonlValidatedBuilder<T> {
+    fn clone(&self) -> Self {
+        Self {
+            builder: self.builder.clone(),
+            validator: Arc::clone(&self.validator),
+        }
+    }
+}
+
+impl<T> fmt::Debug for MtJsonlValidatedBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MtJsonlValidatedBuilder")
+            .field("builder", &self.builder)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> MtJsonlValidatedBuilder<T>
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    /// Start reading and parsing the file in background threads.
+    ///
+    /// See [`MtJsonlBuilder::parse`] for details.
+    pub fn parse(&self) -> MtJsonl<T> {
+        self.builder.spawn(false, Some(Arc::clone(&self.validator)))
+    }
+
+    /// Start reading and parsing all files in background threads.
+    ///
+    /// See [`MtJsonlBuilder::parse_many`] for details.
+    pub fn parse_many(&self) -> MtJsonlMany<T> {
+        MtJsonlMany {
+            inner: self.parse(),
+        }
+    }
+
+    /// Start reading and parsing the file in background threads, keeping the raw lines.
+    ///
+    /// See [`MtJsonlBuilder::parse_raw`] for details.
+    pub fn parse_raw(&self) -> MtJsonlRaw<T> {
+        MtJsonlRaw {
+            inner: self.builder.spawn(true, Some(Arc::clone(&self.validator))),
+        }
+    }
+}
+
+/// Create a multi-threaded [JSONL] parser.
+///
+/// This returns an iterator over `Result<T>`. If any reading errors of the file or parsing errors
+/// happen they will be passed to the caller of the iterator.
+///
+/// Internally this will spawn two threads. The first thread is responsible for reading from the
+/// underlying file. It uses [`file_open_read`] for this task, thus it also supports compressed
+/// files transparently. The second thread receives multiple lines as [`String`] and parses them
+/// into a `Vec<Result<T>>`. Then they are passed to the caller as a single iterator.
+///
+/// Since the processing is based on thread the communication overhead should be minimal. For this
+/// the `batchsize` can be specifies, which controls how many lines are read before passing them to
+/// the second thread and thus how large the vector will be.
+///
+/// Use [`MtJsonlBuilder`] for more options.
+///
+/// [JSONL]: http://jsonlines.org/
+pub fn parse_jsonl_multi_threaded<P, T>(path: P, batchsize: u32) -> MtJsonl<T>
+where
+    P: AsRef<Path>,
+    T: 'static + DeserializeOwned + Send,
+{
+    MtJsonlBuilder::new(path.as_ref().to_path_buf())
+        .batch_size(batchsize)
+        .parse()
+}
+
+/// Create a multi-threaded [JSONL] parser with multiple parsing threads.
+///
+/// This works like [`parse_jsonl_multi_threaded`], but spawns `workers` threads, which parse the batches in parallel.
+/// This helps if deserializing the values is more expensive than reading the file.
+/// The iterator returns the values in the same order as in the file.
+///
+/// Setting `workers` to `0` has the same effect as setting it to `1`.
+/// This is a shorthand for [`MtJsonlBuilder::threads`].
+///
+/// [JSONL]: http://jsonlines.org/
+pub fn parse_jsonl_multi_worker<P, T>(path: P, batchsize: u32, workers: usize) -> MtJsonl<T>
+where
+    P: AsRef<Path>,
+    T: 'static + DeserializeOwned + Send,
+{
+    MtJsonlBuilder::new(path.as_ref().to_path_buf())
+        .batch_size(batchsize)
+        .threads(workers)
+        .parse()
+}
+
+/// Create a multi-threaded [JSONL] parser for multiple files.
+///
+/// This works like [`parse_jsonl_multi_threaded`], but parses the files one after the other, in the given order.
+/// After all values of a file, the iterator returns a [`JsonlEvent::FileCompleted`] marker for the file.
+/// This is a shorthand for [`MtJsonlBuilder::new_many`] and [`MtJsonlBuilder::parse_many`].
+///
+/// [JSONL]: http://jsonlines.org/
+pub fn parse_jsonl_multi_threaded_many<I, T>(paths: I, batchsize: u32) -> MtJsonlMany<T>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+    T: 'static + DeserializeOwned + Send,
+{
+    MtJsonlBuilder::new_many(paths)
+        .batch_size(batchsize)
+        .parse_many()
+}
+
+/// Create a single-threaded [JSONL] parser.
+///
+/// This returns an iterator over `Result<T>`, like [`parse_jsonl_multi_threaded`], but reads and parses the file on the calling thread.
+/// This is useful in async code or other environments, where spawning threads is undesirable.
+/// The file is decompressed transparently, like with [`file_open_read`].
+///
+/// Errors while opening or reading the file are returned as an [`MtJsonlError::IoError`] and end the iteration.
+/// Since no threads are involved, [`MtJsonlError::NotCompleted`] is never returned.
+///
+/// [JSONL]: http://jsonlines.org/
+pub fn parse_jsonl<P, T>(path: P) -> Jsonl<T>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    let batches = LineBatches::open(
+        &MtJsonlBuilder::new(path.as_ref().to_path_buf()),
+        path.as_ref(),
+        (1, 0),
+    );
+    let (batches, error) = match batches {
+        Ok(batches) => (Some(batches), None),
+        Err(err) => (None, Some(err.into())),
+    };
+    Jsonl {
+        batches,
+        error,
+        tmp_state: vec![].into_iter(),
+    }
+}
+
+/// An iterator over deserialized JSON objects, which parses on the calling thread
+///
+/// This struct is created by the [`parse_jsonl`] function.
+pub struct Jsonl<T> {
+    /// Batches of the file, `None` after an error
+    batches: Option<LineBatches>,
+    /// Error opening the file
+    error: Option<MtJsonlError>,
+    tmp_state: std::vec::IntoIter<Record<T>>,
+}
+
+impl<T> fmt::Debug for Jsonl<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Jsonl")
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Iterator for Jsonl<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T, MtJsonlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.tmp_state.next() {
+                return Some(record.result.map(|positioned| positioned.value));
+            }
+            if let Some(err) = self.error.take() {
+                return Some(Err(err));
+            }
+            match self.batches.as_mut()?.next()? {
+                Ok(batch) => self.tmp_state = parse_stream(&batch).into_iter(),
+                Err(err) => {
+                    self.batches = None;
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+    }
+}
+
+/// Create a [JSONL] parser running on the [rayon] thread pool.
+///
+/// This returns a [`ParallelIterator`] over `Result<T>`.
+/// The file is read in batches of `batchsize` lines, which are parsed on the rayon thread pool.
+/// No additional threads are spawned, which makes this a good fit for programs which already use rayon.
+///
+/// Unlike [`parse_jsonl_multi_threaded`], the values are **not** returned in the order of the file.
+/// Errors while opening or reading the file are returned as an [`MtJsonlError::IoError`] and end the iteration.
+///
+/// This function is only available with the `rayon` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::parse_jsonl_parallel;
+/// # use rayon::iter::ParallelIterator;
+/// #
+/// # fn main() -> Result<(), anyhow::Error> {
+/// let sum: u64 = parse_jsonl_parallel::<_, u64>("./numbers.jsonl.xz", 1024)
+///     .collect::<Result<Vec<_>, _>>()?
+///     .into_iter()
+///     .sum();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [JSONL]: http://jsonlines.org/
+/// [rayon]: https://docs.rs/rayon
+#[cfg(feature = "rayon")]
+pub fn parse_jsonl_parallel<P, T>(
+    path: P,
+    batchsize: u32,
+) -> impl ParallelIterator<Item = Result<T, MtJsonlError>>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned + Send,
+{
+    let builder = MtJsonlBuilder::new(path.as_ref().to_path_buf())
+        .batch_size(batchsize)
+        .clone();
+    let batches: Box<dyn Iterator<Item = Result<LineBatch, Error>> + Send> =
+        match LineBatches::open(&builder, path.as_ref(), (1, 0)) {
+            Ok(batches) => Box::new(batches),
+            Err(err) => Box::new(std::iter::once(Err(err))),
+        };
+    batches.par_bridge().flat_map_iter(|batch| match batch {
+        Ok(batch) => parse_stream::<T>(&batch)
+            .into_iter()
+            .map(|record| record.result.map(|positioned| positioned.value))
+            .collect(),
+        Err(err) => vec![Err(err.into())],
+    })
+}
+
+/// Read batches of lines from the file and send them to the parsing threads.
+fn read_batches(
+    builder: &MtJsonlBuilder,
+    lines_sender: mpsc::SyncSender<Sequenced<LineBatch>>,
+    stop: &AtomicBool,
+    progress: &JsonlProgress,
+) {
+    info!(
+        "Start background reading thread: {:?}",
+        thread::current().id()
+    );
+    let mut seq = 0;
+    let mut start = builder.start;
+    // Progress of the previous files
+    let (mut lines_read, mut bytes_read) = (0, 0);
+    for path in &builder.paths {
+        let mut batches = match LineBatches::open(builder, path, start) {
+            Ok(batches) => batches,
+            Err(err) => {
+                warn!(
+                    "Background reading thread cannot open file {} {:?}",
+                    path.display(),
+                    thread::current().id()
+                );
+                // cannot communicate channel failures
+                let _ = lines_sender.send((seq, ProcessingStatus::Error(err.into())));
+                return;
+            }
+        };
+        while let Some(batch) = batches.next() {
+            progress
+                .lines_read
+                .store(lines_read + batches.line - start.0, Ordering::Relaxed);
+            progress
+                .bytes_read
+                .store(bytes_read + batches.offset - start.1, Ordering::Relaxed);
+            if stop.load(Ordering::Relaxed) {
+                info!(
+                    "Background reading thread: stopped {:?}",
+                    thread::current().id()
+                );
+                return;
+            }
+            let batch = match batch {
+                Ok(batch) => batch,
+                Err(err) => {
+                    warn!(
+                        "Background reading thread cannot read line {:?}",
+                        thread::current().id()
+                    );
+                    // cannot communicate channel failures
+                    let _ = lines_sender.send((seq, ProcessingStatus::Error(err.into())));
+                    return;
+                }
+            };
+            // cannot communicate channel failures
+            if lines_sender
+                .send((seq, ProcessingStatus::Data(batch)))
+                .is_err()
+            {
+                // kill on sent error
+                return;
+            }
+            seq += 1;
+            info!(
+                "Background reading thread: sent batch {:?}",
+                thread::current().id()
+            );
+        }
+        lines_read += batches.line - start.0;
+        bytes_read += batches.offset - start.1;
+        start = (1, 0);
+
+        // cannot communicate channel failures
+        if lines_sender
+            .send((seq, ProcessingStatus::FileCompleted(path.clone())))
+            .is_err()
+        {
+            // kill on sent error
+            return;
+        }
+        seq += 1;
+        info!(
+            "Background reading thread: successful processed file {:?} {:?}",
+            path,
+            thread::current().id()
+        );
+    }
+    // cannot communicate channel failures
+    let _ = lines_sender.send((seq, ProcessingStatus::Completed));
+}
+
+/// Iterator over the batches of lines of a file.
+///
+/// The iterator ends after the first error.
+struct LineBatches {
+    path: PathBuf,
+    /// Reader of the file, `None` once the end of the file or an error is reached
+    reader: Option<BufReader<DetectedReader>>,
+    batch_size: u32,
+    batch_bytes: Option<usize>,
+    filter: Option<Arc<LineFilter>>,
+    /// Line number of the next line
+    line: u64,
+    /// Byte offset of the next line
+    offset: u64,
+}
+
+impl LineBatches {
+    /// Open the file at `path` with the options of `builder`, starting at the line number and offset `start`.
+    fn open(builder: &MtJsonlBuilder, path: &Path, start: (u64, u64)) -> Result<Self, Error> {
+        let (line, offset) = start;
+        let mut file_read = file_read(path);
+        file_read.offset(offset);
+        if let Some(buffer_capacity) = builder.buffer_capacity {
+            file_read.buffer_capacity(buffer_capacity);
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader: Some(BufReader::new(file_read.open()?)),
+            batch_size: builder.batch_size,
+            batch_bytes: builder.batch_bytes,
+            filter: builder.filter.clone(),
+            line,
+            offset,
+        })
+    }
+}
+
+impl Iterator for LineBatches {
+    type Item = Result<LineBatch, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let reader = self.reader.as_mut()?;
+        let mut batch = LineBatch {
+            line: self.line,
+            offset: self.offset,
+            text: String::new(),
+            positions: self.filter.as_ref().map(|_| Vec::new()),
+        };
+        let mut lines_read = 0;
+        for _ in 0..self.batch_size {
+            let line_start = batch.text.len();
+            match reader.read_line(&mut batch.text) {
+                Ok(0) => {
+                    self.reader = None;
+                    break;
+                }
+                Ok(len) => {
+                    if let (Some(filter), Some(positions)) = (&self.filter, &mut batch.positions) {
+                        let line = &batch.text[line_start..];
+                        let line = line.strip_suffix('\n').unwrap_or(line);
+                        let line = line.strip_suffix('\r').unwrap_or(line);
+                        if filter(line) {
+                            positions.push((self.line, self.offset));
+                        } else {
+                            batch.text.truncate(line_start);
+                        }
+                    }
+                    lines_read += 1;
+                    self.line += 1;
+                    self.offset += len as u64;
+                    if self
+                        .batch_bytes
+                        .is_some_and(|batch_bytes| batch.text.len() >= batch_bytes)
+                    {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    self.reader = None;
+                    return Some(Err(Error::FileIo {
+                        file: self.path.clone(),
+                        operation: Operation::Read,
+                        source: err,
+                    }));
+                }
+            }
+        }
+        if lines_read == 0 {
+            return None;
+        }
+        Some(Ok(batch))
+    }
+}
+
+/// Parse the batches received from the reading thread until the channel closes.
+fn parse_batches<T>(
+    lines_receiver: &Mutex<mpsc::Receiver<Sequenced<LineBatch>>>,
+    struct_sender: &mpsc::SyncSender<Sequenced<Batch<T>>>,
+    on_error: Option<&OnError>,
+    validator: Option<&Validator<T>>,
+    keep_raw: bool,
+    stop: &AtomicBool,
+    progress: &JsonlProgress,
+) where
+    T: 'static + DeserializeOwned + Send,
+{
+    info!(
+        "Start background parsing thread {:?}",
+        thread::current().id()
+    );
+    loop {
+        // Only hold the lock while waiting for the next batch, not while parsing
+        let received = match lines_receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        let Ok((seq, status)) = received else {
+            // The reading thread finished
+            return;
+        };
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let status = match status {
+            ProcessingStatus::Error(e) => {
+                info!(
+                    "Background parsing thread: pass through error {:?}",
+                    thread::current().id()
+                );
+                ProcessingStatus::Error(e)
+            }
+            ProcessingStatus::Completed => {
+                info!(
+                    "Background parsing thread: successfully completed {:?}",
+                    thread::current().id()
+                );
+                ProcessingStatus::Completed
+            }
+            ProcessingStatus::FileCompleted(path) => ProcessingStatus::FileCompleted(path),
+            ProcessingStatus::Data(batch) => {
+                let mut batch: Batch<T> = if on_error.is_none() && !keep_raw {
+                    parse_stream(&batch)
+                } else {
+                    parse_lines(&batch, on_error, keep_raw, progress)
+                };
+                if let Some(validator) = validator {
+                    for record in &mut batch {
+                        if let Ok(positioned) = &record.result {
+                            if let Err(source) = validator(&positioned.value) {
+                                record.result = Err(MtJsonlError::ValidationError {
+                                    line: positioned.line,
+                                    offset: positioned.offset,
+                                    source,
+                                });
+                            }
+                        }
+                    }
+                }
+                let errors = batch.iter().filter(|record| record.result.is_err()).count() as u64;
+                progress
+                    .values_parsed
+                    .fetch_add(batch.len() as u64 - errors, Ordering::Relaxed);
+                progress.errors.fetch_add(errors, Ordering::Relaxed);
+
+                info!(
+                    "Background parsing thread: batch parsed {:?}",
+                    thread::current().id()
+                );
+                ProcessingStatus::Data(batch)
+            }
+        };
+        // cannot communicate channel failures
+        if struct_sender.send((seq, status)).is_err() {
+            warn!(
+                "Background parsing thread: sent channel error {:?}",
+                thread::current().id()
+            );
+            // kill on send error
+            return;
+        }
+    }
+}
+
+/// Parse the `batch` as a stream of JSON values.
+///
+/// Parsing stops at the first error.
+fn parse_stream<T>(batch: &LineBatch) -> Batch<T>
+where
+    T: DeserializeOwned,
+{
+    let text = &*batch.text;
+    let mut stream = Deserializer::from_str(text).into_iter();
+    let mut positions = LinePositions::new(batch);
+    let mut values = Vec::new();
+    loop {
+        // The value starts after the whitespace following the previous value
+        let rest = &text[stream.byte_offset()..];
+        let start = text.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+        match stream.next() {
+            None => break,
+            Some(Ok(value)) => {
+                let (line, offset) = positions.position_of(start);
+                values.push(Record {
+                    raw: None,
+                    result: Ok(Positioned {
+                        line,
+                        offset,
+                        value,
+                    }),
+                });
+            }
+            Some(Err(err)) => {
+                // serde_json counts the lines of the batch starting at 1
+                let err_line = err.line().max(1);
+                let (line, offset) = positions.position_of_line(err_line as u64);
+                let line_text = text.split('\n').nth(err_line - 1).unwrap_or_default();
+                let line_text = line_text.strip_suffix('\r').unwrap_or(line_text);
+                values.push(Record {
+                    raw: None,
+                    result: Err(MtJsonlError::parsing_error(line, offset, line_text, err)),
+                });
+            }
+        }
+    }
+    values
+}
+
+/// Parse every line of the `batch` separately.
+///
+/// Unparseable lines are passed to `on_error` if set, otherwise an error is returned for them.
+fn parse_lines<T>(
+    batch: &LineBatch,
+    on_error: Option<&OnError>,
+    keep_raw: bool,
+    progress: &JsonlProgress,
+) -> Batch<T>
+where
+    T: DeserializeOwned,
+{
+    let mut line_start = 0;
+    let mut values = Vec::new();
+    for (index, raw) in batch.text.split_inclusive('\n').enumerate() {
+        let (line, line_offset) = batch.position(index, line_start);
+        line_start += raw.len();
+
+        let raw = raw.strip_suffix('\n').unwrap_or(raw);
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        if raw.trim().is_empty() {
+            continue;
+        }
+        let result = match serde_json::from_str(raw) {
+            Ok(value) => Ok(Positioned {
+                line,
+                offset: line_offset,
+                value,
+            }),
+            Err(err) => match on_error {
+                Some(on_error) => {
+                    on_error(raw, &err);
+                    progress.errors.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                None => Err(MtJsonlError::parsing_error(line, line_offset, raw, err)),
+            },
+        };
+        values.push(Record {
+            raw: keep_raw.then(|| raw.to_string()),
+            result,
+        });
+    }
+    values
+}
+
+/// Translate byte indices of a [`LineBatch`] into line numbers and byte offsets of the file.
+///
+/// The indices must be queried in increasing order.
+struct LinePositions<'a> {
+    batch: &'a LineBatch,
+    /// Byte index up to which the lines are counted
+    index: usize,
+    /// Number of lines before `index`
+    line_index: usize,
+    /// Byte index of the start of the line at `index`
+    line_start: usize,
+}
+
+impl<'a> LinePositions<'a> {
+    fn new(batch: &'a LineBatch) -> Self {
+        Self {
+            batch,
+            index: 0,
+            line_index: 0,
+            line_start: 0,
+        }
+    }
+
+    /// Line number and byte offset of the line containing the byte index `index`.
+    fn position_of(&mut self, index: usize) -> (u64, u64) {
+        let index = index.clamp(self.index, self.batch.text.len());
+        for (i, _) in self.batch.text[self.index..index].match_indices('\n') {
+            self.line_index += 1;
+            self.line_start = self.index + i + 1;
+        }
+        self.index = index;
+        self.batch.position(self.line_index, self.line_start)
+    }
+
+    /// Line number and byte offset of the `line`-th line of the batch, starting at 1.
+    ///
+    /// Lines after the end of the batch, e.g., for errors about a truncated value, are reported as the last line.
+    fn position_of_line(&self, line: u64) -> (u64, u64) {
+        let mut position = (0, 0);
+        let mut line_start = 0;
+        let lines = self.batch.text.split_inclusive('\n');
+        for (index, raw) in lines.enumerate().take(line.max(1) as usize) {
+            position = (index, line_start);
+            line_start += raw.len();
+        }
+        self.batch.position(position.0, position.1)
+    }
+}
+
+/// Writer for [JSONL] files.
+///
+/// The values are collected into batches, which are written once they exceed [`JsonlWriter::batch_bytes`] or by calling [`JsonlWriter::flush_batch`].
+/// With [`JsonlWriter::create_gzip_members`], every batch is compressed as an independent gzip member.
+/// The members only end at line boundaries, such that readers can decompress and parse them in parallel.
+/// All gzip readers, including [`file_read`], can read the whole file, since concatenated gzip members form a valid gzip file.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::{Compression, JsonlWriter};
+/// #
+/// # fn main() -> Result<(), anyhow::Error> {
+/// let mut writer = JsonlWriter::create_gzip_members("./events.jsonl.gz", Compression::Default)?;
+/// for chunk in [[1, 2, 3], [4, 5, 6]] {
+///     writer.write_batch(&chunk)?;
+/// }
+/// writer.finish()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [JSONL]: http://jsonlines.org/
+#[derive(Debug)]
+pub struct JsonlWriter {
+    path: PathBuf,
+    writer: CompressedWriter,
+    /// Compression level of the gzip members, `None` if the batches are written as they are
+    gzip_members: Option<Compression>,
+    /// Serialized lines of the current batch
+    batch: Vec<u8>,
+    batch_bytes: usize,
+}
+
+impl JsonlWriter {
+    /// Create a new file, truncating any existing file.
+    ///
+    /// The filetype is chosen based on the extension like in [`file_write`].
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        Ok(Self::new(path, file_write(path).truncate()?, None))
+    }
+
+    /// Create a new file consisting of one gzip member per batch, truncating any existing file.
+    ///
+    /// The file is always gzip compressed, independent of the extension.
+    #[cfg(feature = "file-gz")]
+    pub fn create_gzip_members<P: AsRef<Path>>(
+        path: P,
+        compression_level: Compression,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let writer = file_write(path).filetype(FileType::PlainText).truncate()?;
+        Ok(Self::new(path, writer, Some(compression_level)))
+    }
+
+    fn new(path: &Path, writer: CompressedWriter, gzip_members: Option<Compression>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            writer,
+            gzip_members,
+            batch: Vec::new(),
+            batch_bytes: 1024 * 1024,
+        }
+    }
+
+    /// Write the batch once it exceeds `batch_bytes` bytes (default 1 MiB).
+    pub fn batch_bytes(&mut self, batch_bytes: usize) -> &mut Self {
+        self.batch_bytes = batch_bytes;
+        self
+    }
+
+    /// Serialize `value` as a single line and add it to the current batch.
+    ///
+    /// If serialization fails, the batch is left unchanged.
+    pub fn write<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        let len = self.batch.len();
+        serde_json::to_writer(&mut self.batch, value).map_err(|err| {
+            // Remove the partially serialized value
+            self.batch.truncate(len);
+            Error::JsonError {
+                file: self.path.clone(),
+                source: err,
+            }
+        })?;
+        self.batch.push(b'\n');
+        if self.batch.len() >= self.batch_bytes {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    /// Write all `values` and end the batch afterwards.
+    ///
+    /// Large batches are split according to [`JsonlWriter::batch_bytes`].
+    pub fn write_batch<I>(&mut self, values: I) -> Result<(), Error>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        for value in values {
+            self.write(&value)?;
+        }
+        self.flush_batch()
+    }
+
+    /// Write the current batch into the file.
+    ///
+    /// With gzip members, this finishes the member.
+    /// Empty batches are not written.
+    pub fn flush_batch(&mut self) -> Result<(), Error> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        let map_err = |err| Error::FileIo {
+            file: self.path.clone(),
+            operation: Operation::Write,
+            source: err,
+        };
+        match self.gzip_members {
+            #[cfg(feature = "file-gz")]
+            Some(level) => {
+                let mut encoder =
+                    GzEncoder::new(Vec::with_capacity(self.batch.len() / 4), level.into());
+                let member = encoder
+                    .write_all(&self.batch)
+                    .and_then(|()| encoder.finish())
+                    .map_err(map_err)?;
+                self.writer.write_all(&member).map_err(map_err)?;
+            }
+            _ => self.writer.write_all(&self.batch).map_err(map_err)?,
+        }
+        self.batch.clear();
+        Ok(())
+    }
+
+    /// Write the remaining batch and finish the file.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.flush_batch()?;
+        self.writer.finish()
+    }
+}
+
+/// Appends [JSONL] records to a file, which is shared with other processes.
+///
+/// Every batch is serialized completely, before it is written while holding an exclusive advisory lock on the file.
+/// Together with opening the file in append mode, this ensures that batches of different processes do not interleave and lines are never split.
+/// The lock is only advisory, i.e., it only protects against writers which also take the lock, like other [`JsonlAppender`]s.
+///
+/// Only plaintext files are supported and the records are not buffered between calls.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::JsonlAppender;
+/// #
+/// # fn main() -> Result<(), anyhow::Error> {
+/// let mut appender = JsonlAppender::open("./events.jsonl")?;
+/// appender.append(&"started")?;
+/// appender.append_batch(["step 1", "step 2"])?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [JSONL]: http://jsonlines.org/
+#[derive(Debug)]
+pub struct JsonlAppender {
+    path: PathBuf,
+    file: File,
+    /// Serialized lines of the current batch
+    batch: Vec<u8>,
+}
+
+impl JsonlAppender {
+    /// Open the file for appending, creating it if it does not exist.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| Error::FileIo {
+                file: path.to_path_buf(),
+                operation: Operation::Open,
+                source: err,
+            })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            batch: Vec::new(),
+        })
+    }
+
+    /// Append `value` as a single line.
+    pub fn append<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        self.append_batch(std::iter::once(value))
+    }
+
+    /// Append all `values` as one batch.
+    ///
+    /// Nothing is written if any value cannot be serialized.
+    pub fn append_batch<I>(&mut self, values: I) -> Result<(), Error>
+    where
+        I: IntoIterator,
+        I::Item: Serialize,
+    {
+        self.batch.clear();
+        for value in values {
+            serde_json::to_writer(&mut self.batch, &value).map_err(|err| Error::JsonError {
+                file: self.path.clone(),
+                source: err,
+            })?;
+            self.batch.push(b'\n');
+        }
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+
+        self.file.lock().map_err(|err| Error::FileIo {
+            file: self.path.clone(),
+            operation: Operation::Lock,
+            source: err,
+        })?;
+        let written = self.file.write_all(&self.batch);
+        let unlocked = self.file.unlock();
+        written.map_err(|err| Error::FileIo {
+            file: self.path.clone(),
+            operation: Operation::Write,
+            source: err,
+        })?;
+        unlocked.map_err(|err| Error::FileIo {
+            file: self.path.clone(),
+            operation: Operation::Unlock,
+            source: err,
+        })
+    }
+}
+
+/// Maximal size of a shard created by [`split_jsonl`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ShardSize {
+    /// Number of lines per shard
+    ///
+    /// Every shard contains at least one line, so `Records(0)` behaves like `Records(1)`.
+    Records(u64),
+    /// Number of decompressed bytes per shard
+    ///
+    /// A shard only exceeds this size if it consists of a single longer line.
+    Bytes(u64),
+}
+
+/// Split the [JSONL] file `src` into multiple shards on line boundaries.
+///
+/// The shards are named by appending a five digit counter and the extensions of `src` to `prefix`.
+/// For example, splitting `events.jsonl.xz` with the prefix `shards/events-` creates the files `shards/events-00000.jsonl.xz`, `shards/events-00001.jsonl.xz`, and so on.
+/// The extensions determine the filetype of the shards like in [`file_write`], so shards of compressed files are compressed in the same way.
+/// Existing files are truncated.
+///
+/// The file is streamed, such that it is never fully loaded into memory.
+/// The lines are copied without parsing them.
+/// The return value are the paths of the shards in order.
+///
+/// [JSONL]: http://jsonlines.org/
+pub fn split_jsonl<P, Q>(src: P, prefix: Q, shard_size: ShardSize) -> Result<Vec<PathBuf>, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let src = src.as_ref();
+    let extensions = src.full_extension();
+    let shard_path = |index: usize| {
+        let mut path = prefix.as_ref().as_os_str().to_owned();
+        path.push(format!("{index:05}"));
+        if let Some(extensions) = extensions {
+            path.push(".");
+            path.push(extensions);
+        }
+        PathBuf::from(path)
+    };
+
+    let mut reader = BufReader::new(file_read(src).open()?);
+    let mut line = Vec::new();
+    let mut shards = Vec::new();
+    // Current shard with the number of lines and bytes written
+    let mut shard: Option<(CompressedWriter, u64)> = None;
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|err| Error::FileIo {
+                file: src.to_path_buf(),
+                operation: Operation::Read,
+                source: err,
+            })?;
+        if read == 0 {
+            break;
+        }
+
+        let is_full = |(writer, lines): &(CompressedWriter, u64)| match shard_size {
+            ShardSize::Records(records) => *lines >= records.max(1),
+            ShardSize::Bytes(bytes) => {
+                *lines > 0 && writer.bytes_written() + line.len() as u64 > bytes
+            }
+        };
+        if let Some((writer, _)) = shard.take_if(|shard| is_full(shard)) {
+            writer.finish()?;
+        }
+        let (writer, lines) = match &mut shard {
+            Some(shard) => shard,
+            None => {
+                let path = shard_path(shards.len());
+                let writer = file_write(&path).truncate()?;
+                shards.push(path);
+                shard.insert((writer, 0))
+            }
+        };
+        writer.write_all(&line).map_err(|err| Error::FileIo {
+            file: writer.path().to_path_buf(),
+            operation: Operation::Write,
+            source: err,
+        })?;
+        *lines += 1;
+    }
+    if let Some((writer, _)) = shard {
+        writer.finish()?;
+    }
+    Ok(shards)
+}
+
+/// Merge the sorted [JSONL] files `paths` into the file `dst`.
+///
+/// Every file must be sorted by the key returned by `key_fn`.
+/// The merged file is sorted by the same key, where records with equal keys keep the order of `paths`.
+/// This allows external sorting, by sorting shards of a file in memory and merging them afterwards, see [`split_jsonl`].
+///
+/// The files are streamed, such that only one record per file is kept in memory.
+/// The records are parsed as `T` to determine the key, but the original lines are written to `dst`.
+/// Empty lines are skipped.
+/// `dst` is truncated and can be compressed like in [`file_write`].
+/// The return value is the number of records written.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::merge_jsonl_sorted;
+/// # use serde::Deserialize;
+/// #[derive(Deserialize)]
+/// struct Event {
+///     timestamp: u64,
+/// }
+///
+/// let shards = ["events-00000.jsonl.gz", "events-00001.jsonl.gz"];
+/// merge_jsonl_sorted(&shards, |event: &Event| event.timestamp, "events.jsonl.gz")?;
+/// # Ok::<_, misc_utils::error::Error>(())
+/// ```
+///
+/// [JSONL]: http://jsonlines.org/
+pub fn merge_jsonl_sorted<I, T, K, F, P>(paths: I, mut key_fn: F, dst: P) -> Result<u64, Error>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+    T: DeserializeOwned,
+    K: Ord,
+    F: FnMut(&T) -> K,
+    P: AsRef<Path>,
+{
+    /// Read the next non-empty line and determine its key.
+    fn next_line<T, K>(
+        path: &Path,
+        reader: &mut dyn BufRead,
+        key_fn: &mut dyn FnMut(&T) -> K,
+    ) -> Result<Option<(K, Vec<u8>)>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|err| Error::FileIo {
+                    file: path.to_path_buf(),
+                    operation: Operation::Read,
+                    source: err,
+                })?;
+            if read == 0 {
+                return Ok(None);
+            }
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+            let value: T = serde_json::from_slice(&line).map_err(|err| Error::JsonError {
+                file: path.to_path_buf(),
+                source: err,
+            })?;
+            if line.last() != Some(&b'\n') {
+                line.push(b'\n');
+            }
+            return Ok(Some((key_fn(&value), line)));
+        }
+    }
+
+    let paths: Vec<PathBuf> = paths
+        .into_iter()
+        .map(|path| path.as_ref().to_path_buf())
+        .collect();
+    let mut readers = Vec::with_capacity(paths.len());
+    let mut heap = BinaryHeap::with_capacity(paths.len());
+    for (index, path) in paths.iter().enumerate() {
+        let mut reader = BufReader::new(file_read(path).open()?);
+        if let Some((key, line)) = next_line(path, &mut reader, &mut key_fn)? {
+            heap.push(MergeEntry { key, index, line });
+        }
+        readers.push(reader);
+    }
+
+    let dst = dst.as_ref();
+    let mut writer = file_write(dst).truncate()?;
+    let mut records = 0;
+    while let Some(MergeEntry { index, line, .. }) = heap.pop() {
+        writer.write_all(&line).map_err(|err| Error::FileIo {
+            file: dst.to_path_buf(),
+            operation: Operation::Write,
+            source: err,
+        })?;
+        records += 1;
+        if let Some((key, line)) = next_line(&paths[index], &mut readers[index], &mut key_fn)? {
+            heap.push(MergeEntry { key, index, line });
+        }
+    }
+    writer.finish()?;
+    Ok(records)
+}
+
+/// Next record of a file in [`merge_jsonl_sorted`].
+///
+/// The entries are ordered in reverse, such that the [`BinaryHeap`] returns the smallest key first.
+struct MergeEntry<K> {
+    key: K,
+    /// Index of the file, which breaks ties between equal keys
+    index: usize,
+    line: Vec<u8>,
+}
+
+impl<K: Ord> Ord for MergeEntry<K> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        (&other.key, other.index).cmp(&(&self.key, self.index))
+    }
+}
+
+impl<K: Ord> PartialOrd for MergeEntry<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord> PartialEq for MergeEntry<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == cmp::Ordering::Equal
+    }
+}
+
+impl<K: Ord> Eq for MergeEntry<K> {}
+
+/// Draw a uniform random sample of `k` records from the [JSONL] file `path`.
+///
+/// The file is read once using reservoir sampling.
+/// Only the lines of the sample are kept in memory and parsed, such that sampling from large files is cheap.
+/// The same `seed` always results in the same sample of a file.
+/// The sample is returned in the order of the file.
+/// If the file contains fewer than `k` records, all of them are returned.
+/// Empty lines are skipped.
+///
+/// [JSONL]: http://jsonlines.org/
+pub fn sample_jsonl<P, T>(path: P, k: usize, seed: u64) -> Result<Vec<T>, MtJsonlError>
+where
+    P: AsRef<Path>,
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let mut reader = BufReader::new(file_read(path).open()?);
+    let mut rng = SplitMix64(seed);
+    // Line number, byte offset, and content of the sampled lines
+    let mut sample: Vec<(u64, u64, Vec<u8>)> = Vec::with_capacity(k);
+    let mut records: u64 = 0;
+    let (mut line_number, mut offset) = (0, 0);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|err| Error::FileIo {
+                file: path.to_path_buf(),
+                operation: Operation::Read,
+                source: err,
+            })?;
+        if read == 0 {
+            break;
+        }
+        line_number += 1;
+        let line_offset = offset;
+        offset += read as u64;
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+
+        records += 1;
+        let slot = if sample.len() < k {
+            sample.push(Default::default());
+            sample.len() - 1
+        } else {
+            // Replace a sampled line with probability k / records
+            match rng.below(records) {
+                idx if idx < k as u64 => idx as usize,
+                _ => continue,
+            }
+        };
+        let entry = &mut sample[slot];
+        entry.0 = line_number;
+        entry.1 = line_offset;
+        entry.2.clone_from(&line);
+    }
+
+    sample.sort_unstable_by_key(|(line, _, _)| *line);
+    sample
+        .into_iter()
+        .map(|(line, offset, content)| {
+            serde_json::from_slice(&content).map_err(|source| {
+                let text = String::from_utf8_lossy(&content);
+                let text = text.trim_end_matches(['\n', '\r']);
+                MtJsonlError::parsing_error(line, offset, text, source)
+            })
+        })
+        .collect()
+}
+
+/// Small seedable random number generator for [`sample_jsonl`].
+///
+/// See <https://prng.di.unimi.it/splitmix64.c>.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Random number in the range `0..n`.
+    ///
+    /// The bias of the multiplication method is negligible for the sample sizes used here.
+    fn below(&mut self, n: u64) -> u64 {
+        ((u128::from(self.next_u64()) * u128::from(n)) >> 64) as u64
+    }
+}
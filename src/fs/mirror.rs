@@ -0,0 +1,295 @@
+//! One-way synchronization of directory trees.
+
+use super::{
+    file_open_read, file_type_from_compression, guess_file_type, read_magic, recompress,
+    remove_file, RecompressOptions,
+};
+use crate::error::{Error, Operation};
+use log::debug;
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs::Metadata,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+/// Strategy to decide whether a destination file is up to date.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub enum MirrorCompare {
+    /// Compare the modification time and the size (default).
+    ///
+    /// The size is only compared if the file is copied without converting the compression.
+    #[default]
+    Metadata,
+    /// Compare the decompressed content of both files.
+    ///
+    /// This is slower than [`MirrorCompare::Metadata`] as both files need to be read completely.
+    Content,
+}
+
+/// Options for [`mirror`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MirrorOptions {
+    /// Strategy to detect changed files.
+    compare: MirrorCompare,
+    /// Remove files in the destination which do not exist in the source.
+    delete: bool,
+    /// Options used for copying files.
+    recompress: RecompressOptions,
+}
+
+impl MirrorOptions {
+    /// Create a new set of options with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the strategy to detect changed files.
+    ///
+    /// See [`MirrorCompare`] for the available strategies.
+    pub fn compare(&mut self, compare: MirrorCompare) -> &mut Self {
+        self.compare = compare;
+        self
+    }
+
+    /// Remove files and directories in the destination, which do not exist in the source.
+    pub fn delete(&mut self, delete: bool) -> &mut Self {
+        self.delete = delete;
+        self
+    }
+
+    /// Sets the options used for copying changed files.
+    ///
+    /// The filetype of the options applies to all files.
+    /// Without it, the filetype is chosen based on the extension of each file.
+    /// The metadata is always preserved, such that the modification times can be compared.
+    pub fn recompress(&mut self, recompress: RecompressOptions) -> &mut Self {
+        self.recompress = recompress;
+        self
+    }
+}
+
+/// Statistics about the files processed by [`mirror`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct MirrorStats {
+    /// Number of files copied, because they were new or changed.
+    pub copied: u64,
+    /// Number of files which were already up to date.
+    pub unchanged: u64,
+    /// Number of files and directories removed from the destination.
+    pub deleted: u64,
+}
+
+/// Mirror the directory tree `src_dir` into `dst_dir`.
+///
+/// New and changed files are copied using [`recompress`], such that the files can be converted between compression formats.
+/// Unchanged files are detected as configured by [`MirrorOptions::compare`].
+/// Files in `dst_dir`, which are missing in `src_dir`, are only removed if [`MirrorOptions::delete`] is set.
+///
+/// Only regular files and directories are mirrored, other entries like symlinks are skipped.
+/// Entries in `dst_dir` with the same name as a skipped entry are never removed.
+/// Missing directories are created, including `dst_dir` itself.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::{mirror, MirrorCompare, MirrorOptions};
+/// #
+/// # fn main() -> Result<(), anyhow::Error> {
+/// let stats = mirror(
+///     "./data",
+///     "./backup",
+///     MirrorOptions::new().compare(MirrorCompare::Content).delete(true),
+/// )?;
+/// println!("Copied {} files", stats.copied);
+/// # Ok(())
+/// # }
+/// ```
+pub fn mirror<P: AsRef<Path>, Q: AsRef<Path>>(
+    src_dir: P,
+    dst_dir: Q,
+    options: &MirrorOptions,
+) -> Result<MirrorStats, Error> {
+    let mut recompress_options = options.recompress;
+    recompress_options.preserve_metadata(true);
+    let mut mirror = Mirror {
+        options,
+        recompress_options,
+        stats: MirrorStats::default(),
+    };
+    mirror.mirror_dir(src_dir.as_ref(), dst_dir.as_ref())?;
+    Ok(mirror.stats)
+}
+
+/// State of a running [`mirror`] operation.
+struct Mirror<'a> {
+    options: &'a MirrorOptions,
+    recompress_options: RecompressOptions,
+    stats: MirrorStats,
+}
+
+impl Mirror<'_> {
+    fn mirror_dir(&mut self, src_dir: &Path, dst_dir: &Path) -> Result<(), Error> {
+        std::fs::create_dir_all(dst_dir).map_err(|err| Error::FileIo {
+            file: dst_dir.to_path_buf(),
+            operation: Operation::CreateDir,
+            source: err,
+        })?;
+
+        let mut names = HashSet::new();
+        for entry in read_dir(src_dir)? {
+            let entry = entry.map_err(|err| Error::FileIo {
+                file: src_dir.to_path_buf(),
+                operation: Operation::ReadDir,
+                source: err,
+            })?;
+            let src = entry.path();
+            let dst = dst_dir.join(entry.file_name());
+            let file_type = entry.file_type().map_err(|err| Error::FileIo {
+                file: src.clone(),
+                operation: Operation::Metadata,
+                source: err,
+            })?;
+
+            // Also record skipped entries, such that they are not deleted in `dst_dir`
+            names.insert(entry.file_name());
+            if file_type.is_dir() {
+                self.mirror_dir(&src, &dst)?;
+            } else if file_type.is_file() {
+                self.mirror_file(&src, &dst)?;
+            } else {
+                debug!("Skip mirroring {}, it is not a file", src.display());
+            }
+        }
+
+        if self.options.delete {
+            self.delete_extraneous(dst_dir, &names)?;
+        }
+        Ok(())
+    }
+
+    fn mirror_file(&mut self, src: &Path, dst: &Path) -> Result<(), Error> {
+        if self.is_unchanged(src, dst)? {
+            self.stats.unchanged += 1;
+            return Ok(());
+        }
+
+        debug!("Mirror file {} to {}", src.display(), dst.display());
+        recompress(src, dst, &self.recompress_options)?;
+        self.stats.copied += 1;
+        Ok(())
+    }
+
+    /// Test if `dst` is an up to date copy of `src`.
+    fn is_unchanged(&self, src: &Path, dst: &Path) -> Result<bool, Error> {
+        let Ok(dst_metadata) = std::fs::metadata(dst) else {
+            return Ok(false);
+        };
+        if !dst_metadata.is_file() {
+            return Ok(false);
+        }
+
+        match self.options.compare {
+            MirrorCompare::Metadata => {
+                let src_metadata = metadata(src)?;
+                if !same_modified(&src_metadata, &dst_metadata) {
+                    return Ok(false);
+                }
+                let dst_filetype = match self.recompress_options.filetype {
+                    Some(filetype) => filetype,
+                    None => guess_file_type(dst)?,
+                };
+                let src_filetype = file_type_from_compression(src, read_magic(src)?)?;
+                Ok(src_filetype != dst_filetype || src_metadata.len() == dst_metadata.len())
+            }
+            MirrorCompare::Content => same_content(src, dst),
+        }
+    }
+
+    fn delete_extraneous(
+        &mut self,
+        dst_dir: &Path,
+        names: &HashSet<OsString>,
+    ) -> Result<(), Error> {
+        for entry in read_dir(dst_dir)? {
+            let entry = entry.map_err(|err| Error::FileIo {
+                file: dst_dir.to_path_buf(),
+                operation: Operation::ReadDir,
+                source: err,
+            })?;
+            if names.contains(&entry.file_name()) {
+                continue;
+            }
+
+            let path = entry.path();
+            debug!("Remove extraneous {}", path.display());
+            if entry.file_type().is_ok_and(|ft| ft.is_dir()) {
+                std::fs::remove_dir_all(&path).map_err(|err| Error::FileIo {
+                    file: path.clone(),
+                    operation: Operation::Remove,
+                    source: err,
+                })?;
+            } else {
+                remove_file(&path)?;
+            }
+            self.stats.deleted += 1;
+        }
+        Ok(())
+    }
+}
+
+fn read_dir(dir: &Path) -> Result<std::fs::ReadDir, Error> {
+    std::fs::read_dir(dir).map_err(|err| Error::FileIo {
+        file: dir.to_path_buf(),
+        operation: Operation::ReadDir,
+        source: err,
+    })
+}
+
+fn metadata(file: &Path) -> Result<Metadata, Error> {
+    std::fs::metadata(file).map_err(|err| Error::FileIo {
+        file: file.to_path_buf(),
+        operation: Operation::Metadata,
+        source: err,
+    })
+}
+
+/// Test if both files have the same modification time.
+///
+/// Returns `false` if the platform does not support modification times.
+fn same_modified(a: &Metadata, b: &Metadata) -> bool {
+    match (a.modified(), b.modified()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Compare the decompressed content of both files.
+fn same_content(a: &Path, b: &Path) -> Result<bool, Error> {
+    let mut reader_a = BufReader::new(file_open_read(a)?);
+    let mut reader_b = BufReader::new(file_open_read(b)?);
+    loop {
+        let buf_a = reader_a.fill_buf().map_err(|err| Error::FileIo {
+            file: a.to_path_buf(),
+            operation: Operation::Read,
+            source: err,
+        })?;
+        let buf_b = reader_b.fill_buf().map_err(|err| Error::FileIo {
+            file: b.to_path_buf(),
+            operation: Operation::Read,
+            source: err,
+        })?;
+        if buf_a.is_empty() || buf_b.is_empty() {
+            return Ok(buf_a.is_empty() && buf_b.is_empty());
+        }
+
+        let len = buf_a.len().min(buf_b.len());
+        if buf_a[..len] != buf_b[..len] {
+            return Ok(false);
+        }
+        reader_a.consume(len);
+        reader_b.consume(len);
+    }
+}
@@ -0,0 +1,86 @@
+//! Conversion of [JSONL] files into [Parquet] files.
+//!
+//! [JSONL]: http://jsonlines.org/
+//! [Parquet]: https://parquet.apache.org/
+
+use super::{file_read, file_write};
+use crate::error::{Error, Operation};
+use arrow_json::ReaderBuilder;
+use arrow_schema::{ArrowError, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use std::{io::BufReader, path::Path};
+
+/// Number of JSONL lines converted into one Arrow record batch.
+const BATCH_SIZE: usize = 1024;
+
+/// Convert the [JSONL] file `src` into the [Parquet] file `dst`.
+///
+/// Every line of `src` is parsed as a row with the given `schema`.
+/// Fields missing from `schema` are ignored and missing values must be nullable.
+/// The file is converted in batches of rows, such that it never needs to be fully loaded into memory.
+///
+/// Like [`file_read`], `src` can be compressed.
+/// `dst` is truncated before writing.
+/// The return value is the number of rows written.
+///
+/// This requires the `parquet` feature.
+///
+/// # Example
+///
+/// ```no_run
+/// # use misc_utils::fs::jsonl_to_parquet;
+/// use arrow_schema::{DataType, Field, Schema};
+/// use std::sync::Arc;
+///
+/// let schema = Schema::new(vec![
+///     Field::new("name", DataType::Utf8, false),
+///     Field::new("count", DataType::UInt64, true),
+/// ]);
+/// let rows = jsonl_to_parquet("events.jsonl.xz", "events.parquet", Arc::new(schema))?;
+/// # Ok::<_, misc_utils::error::Error>(())
+/// ```
+///
+/// [JSONL]: http://jsonlines.org/
+/// [Parquet]: https://parquet.apache.org/
+pub fn jsonl_to_parquet<P, Q>(src: P, dst: Q, schema: SchemaRef) -> Result<u64, Error>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    let reader = BufReader::new(file_read(src).open()?);
+    let batches = ReaderBuilder::new(schema.clone())
+        .with_batch_size(BATCH_SIZE)
+        .build(reader)
+        .map_err(|err| arrow_error(src, err))?;
+
+    let parquet_error = |err| Error::ParquetError {
+        file: dst.to_path_buf(),
+        source: err,
+    };
+    let mut writer =
+        ArrowWriter::try_new(file_write(dst).truncate()?, schema, None).map_err(parquet_error)?;
+    let mut rows = 0;
+    for batch in batches {
+        let batch = batch.map_err(|err| arrow_error(src, err))?;
+        writer.write(&batch).map_err(parquet_error)?;
+        rows += batch.num_rows() as u64;
+    }
+    writer.into_inner().map_err(parquet_error)?.finish()?;
+    Ok(rows)
+}
+
+/// Convert the error of reading from `path` into the crate error.
+fn arrow_error(path: &Path, err: ArrowError) -> Error {
+    match err {
+        ArrowError::IoError(_, source) => Error::FileIo {
+            file: path.to_path_buf(),
+            operation: Operation::Read,
+            source,
+        },
+        err => Error::ArrowError {
+            file: path.to_path_buf(),
+            source: err,
+        },
+    }
+}
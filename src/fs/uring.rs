@@ -0,0 +1,397 @@
+//! File I/O based on [io_uring](https://kernel.dk/io_uring.pdf).
+//!
+//! The reader and writer in this module keep multiple sequential reads/writes in flight, such that
+//! the kernel can work on the next blocks while the current block is being processed.
+//! This reduces the number of syscalls compared to a [`BufReader`]/[`BufWriter`] of the same size.
+//!
+//! Creating the ring fails on kernels without io_uring support or when it is forbidden by a seccomp filter.
+//! In this case the callers fall back to the [`BufReader`]/[`BufWriter`] based I/O.
+//!
+//! # Safety
+//!
+//! The kernel reads from or writes into the buffers of all submitted operations.
+//! The buffers are owned by the [`Slot`]s and are only released after the operation completed.
+//! If waiting for the completions fails while dropping, the buffers are leaked instead.
+
+use io_uring::{opcode, types, IoUring};
+use log::debug;
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufRead, BufWriter, IoSlice, Read, Write},
+    mem,
+    os::unix::{fs::FileExt, io::AsRawFd},
+};
+
+/// Number of operations kept in flight.
+const QUEUE_DEPTH: usize = 4;
+/// Default size of each buffer.
+const BUFFER_SIZE: usize = 128 * 1024;
+
+/// Create a new ring, returning `None` if io_uring is not available.
+fn new_ring() -> Option<IoUring> {
+    match IoUring::new(QUEUE_DEPTH as u32) {
+        Ok(ring) => Some(ring),
+        Err(err) => {
+            debug!("io_uring is not available, falling back to regular I/O: {err}");
+            None
+        }
+    }
+}
+
+/// A buffer used by a submitted read or write operation.
+struct Slot {
+    /// `user_data` of the submitted operation
+    id: u64,
+    buffer: Vec<u8>,
+    offset: u64,
+    /// Result of the completed operation, `None` while it is in flight
+    result: Option<i32>,
+}
+
+/// Shared state between the reader and the writer.
+struct Ring {
+    ring: IoUring,
+    file: File,
+    /// Operations in the order of their offsets
+    slots: VecDeque<Slot>,
+    next_id: u64,
+}
+
+impl Ring {
+    /// Submit a read or write operation for the full length of `buffer`.
+    fn push(&mut self, mut buffer: Vec<u8>, offset: u64, write: bool) -> io::Result<()> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let fd = types::Fd(self.file.as_raw_fd());
+        let entry = if write {
+            opcode::Write::new(fd, buffer.as_ptr(), buffer.len() as u32)
+                .offset(offset)
+                .build()
+        } else {
+            opcode::Read::new(fd, buffer.as_mut_ptr(), buffer.len() as u32)
+                .offset(offset)
+                .build()
+        }
+        .user_data(id);
+
+        // SAFETY: The buffer is moved into a slot, which is kept alive until the operation completes.
+        // Moving the Vec does not move the heap allocation.
+        unsafe { self.ring.submission().push(&entry) }
+            .map_err(|_| io::Error::other("io_uring submission queue is full."))?;
+        self.slots.push_back(Slot {
+            id,
+            buffer,
+            offset,
+            result: None,
+        });
+        self.ring.submit()?;
+        Ok(())
+    }
+
+    /// Wait until at least one more operation completed and record all available results.
+    fn wait(&mut self) -> io::Result<()> {
+        loop {
+            match self.ring.submit_and_wait(1) {
+                Ok(_) => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        for cqe in self.ring.completion() {
+            if let Some(slot) = self.slots.iter_mut().find(|s| s.id == cqe.user_data()) {
+                slot.result = Some(cqe.result());
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait until the first operation completed and remove it.
+    fn pop_completed(&mut self) -> io::Result<Option<Slot>> {
+        while let Some(Slot { result: None, .. }) = self.slots.front() {
+            self.wait()?;
+        }
+        Ok(self.slots.pop_front())
+    }
+
+    /// Wait for all operations to complete, such that the buffers can be released.
+    fn drain(&mut self) -> io::Result<()> {
+        while self.slots.iter().any(|s| s.result.is_none()) {
+            self.wait()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        if self.drain().is_err() {
+            // The kernel might still access the buffers
+            mem::forget(mem::take(&mut self.slots));
+        }
+    }
+}
+
+/// Convert the result of a completion into the number of bytes.
+fn completion_result(result: i32) -> io::Result<usize> {
+    if result < 0 {
+        Err(io::Error::from_raw_os_error(-result))
+    } else {
+        Ok(result as usize)
+    }
+}
+
+/// Sequential reader, which reads ahead using io_uring.
+pub(crate) struct UringReader {
+    ring: Ring,
+    buffer_size: usize,
+    /// Offset for the next submitted read
+    next_offset: u64,
+    /// The end of the file was reached, no further reads are submitted
+    eof: bool,
+    /// Buffer currently consumed by the caller
+    current: Vec<u8>,
+    pos: usize,
+}
+
+impl UringReader {
+    /// Create a reader for the regular file `file`, starting at `offset`.
+    ///
+    /// Returns the file back, if io_uring is not available.
+    pub(crate) fn new(
+        file: File,
+        buffer_capacity: Option<usize>,
+        offset: u64,
+    ) -> Result<Self, File> {
+        let Some(ring) = new_ring() else {
+            return Err(file);
+        };
+        Ok(Self {
+            ring: Ring {
+                ring,
+                file,
+                slots: VecDeque::with_capacity(QUEUE_DEPTH),
+                next_id: 0,
+            },
+            buffer_size: buffer_capacity.unwrap_or(BUFFER_SIZE).max(1),
+            next_offset: offset,
+            eof: false,
+            current: Vec::new(),
+            pos: 0,
+        })
+    }
+
+    /// Keep [`QUEUE_DEPTH`] reads in flight.
+    fn submit_reads(&mut self) -> io::Result<()> {
+        while !self.eof && self.ring.slots.len() < QUEUE_DEPTH {
+            let buffer = vec![0; self.buffer_size];
+            self.ring.push(buffer, self.next_offset, false)?;
+            self.next_offset += self.buffer_size as u64;
+        }
+        Ok(())
+    }
+}
+
+impl Read for UringReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl BufRead for UringReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self.pos >= self.current.len() {
+            self.submit_reads()?;
+            let Some(mut slot) = self.ring.pop_completed()? else {
+                // end of file and no reads in flight
+                return Ok(&[]);
+            };
+            let result = slot.result.expect("Slot is completed");
+            let len = match completion_result(result) {
+                Ok(len) => len,
+                Err(err)
+                    if err.kind() == io::ErrorKind::Interrupted
+                        || err.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    // Retry the read synchronously
+                    self.ring.file.read_at(&mut slot.buffer, slot.offset)?
+                }
+                Err(err) => return Err(err),
+            };
+
+            if len < slot.buffer.len() {
+                // Short read, the following reads are at the wrong offset or beyond the end of the file
+                self.ring.drain()?;
+                self.ring.slots.clear();
+                self.next_offset = slot.offset + len as u64;
+                self.eof = len == 0;
+            }
+            slot.buffer.truncate(len);
+            self.current = slot.buffer;
+            self.pos = 0;
+        }
+        Ok(&self.current[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.current.len());
+    }
+}
+
+/// Sequential writer, which keeps multiple writes in flight using io_uring.
+pub(crate) struct UringWriter {
+    ring: Ring,
+    buffer_size: usize,
+    /// Offset for the next submitted write
+    next_offset: u64,
+    /// Buffer currently filled by the caller
+    current: Vec<u8>,
+}
+
+impl UringWriter {
+    /// Submit the current buffer and wait for a free slot if necessary.
+    fn submit_current(&mut self) -> io::Result<()> {
+        if self.current.is_empty() {
+            return Ok(());
+        }
+        if self.ring.slots.len() >= QUEUE_DEPTH {
+            self.complete_one()?;
+        }
+        let buffer = mem::replace(&mut self.current, Vec::with_capacity(self.buffer_size));
+        let len = buffer.len() as u64;
+        self.ring.push(buffer, self.next_offset, true)?;
+        self.next_offset += len;
+        Ok(())
+    }
+
+    /// Wait for the first write and finish it, if it was short.
+    fn complete_one(&mut self) -> io::Result<()> {
+        if let Some(slot) = self.ring.pop_completed()? {
+            let written = match completion_result(slot.result.expect("Slot is completed")) {
+                Ok(written) => written,
+                Err(err)
+                    if err.kind() == io::ErrorKind::Interrupted
+                        || err.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    0
+                }
+                Err(err) => return Err(err),
+            };
+            if written < slot.buffer.len() {
+                self.ring
+                    .file
+                    .write_all_at(&slot.buffer[written..], slot.offset + written as u64)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Write for UringWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.buffer_size - self.current.len());
+        self.current.extend_from_slice(&buf[..len]);
+        if self.current.len() >= self.buffer_size {
+            self.submit_current()?;
+        }
+        Ok(len)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let mut written = 0;
+        for buf in bufs {
+            let len = self.write(buf)?;
+            written += len;
+            if len < buf.len() {
+                break;
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.submit_current()?;
+        while !self.ring.slots.is_empty() {
+            self.complete_one()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for UringWriter {
+    fn drop(&mut self) {
+        // Errors cannot be reported, similar to `BufWriter`
+        let _ = self.flush();
+    }
+}
+
+/// Writer for the underlying file of a [`WriteBuilder`](super::WriteBuilder).
+pub(crate) enum FileWriter {
+    Std(BufWriter<File>),
+    Uring(Box<UringWriter>),
+}
+
+impl FileWriter {
+    /// Gets a reference to the underlying file.
+    pub(crate) fn get_ref(&self) -> &File {
+        match self {
+            FileWriter::Std(w) => w.get_ref(),
+            FileWriter::Uring(w) => &w.ring.file,
+        }
+    }
+
+    /// Create a writer, which uses io_uring if available.
+    ///
+    /// io_uring is only used for files opened in truncate mode.
+    /// In append mode the kernel ignores the offsets, such that concurrent writes could be reordered.
+    pub(crate) fn new(file: File, buffer_capacity: Option<usize>, append: bool) -> Self {
+        let ring = if append { None } else { new_ring() };
+        match ring {
+            Some(ring) => {
+                let buffer_size = buffer_capacity.unwrap_or(BUFFER_SIZE).max(1);
+                FileWriter::Uring(Box::new(UringWriter {
+                    ring: Ring {
+                        ring,
+                        file,
+                        slots: VecDeque::with_capacity(QUEUE_DEPTH),
+                        next_id: 0,
+                    },
+                    buffer_size,
+                    next_offset: 0,
+                    current: Vec::with_capacity(buffer_size),
+                }))
+            }
+            None => FileWriter::Std(match buffer_capacity {
+                Some(size) => BufWriter::with_capacity(size, file),
+                None => BufWriter::new(file),
+            }),
+        }
+    }
+}
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            FileWriter::Std(w) => w.write(buf),
+            FileWriter::Uring(w) => w.write(buf),
+        }
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        match self {
+            FileWriter::Std(w) => w.write_vectored(bufs),
+            FileWriter::Uring(w) => w.write_vectored(bufs),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            FileWriter::Std(w) => w.flush(),
+            FileWriter::Uring(w) => w.flush(),
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! This module contains runtime-agnostic async filesystem operations.
+//!
+//! Unlike [`crate::async_fs`], which requires Tokio, the functions in this module work with any executor, like async-std or smol.
+//! The blocking filesystem operations run on the thread pool of the [`blocking`] crate.
+//! Applications with their own thread pool can run the blocking operations there instead, by calling [`set_blocking_executor`].
+//! The functions mirror the ones in [`crate::async_fs`] and are implemented via [`crate::fs`].
+
+use crate::error::Error;
+#[cfg(feature = "jsonl")]
+use crate::error::MtJsonlError;
+#[cfg(feature = "jsonl")]
+use futures_core::Stream;
+#[cfg(feature = "jsonl")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "jsonl")]
+use std::sync::mpsc;
+use std::{
+    future::Future,
+    path::Path,
+    pin::Pin,
+    sync::{Arc, Mutex, RwLock},
+    task::{Context, Poll, Waker},
+};
+
+/// Executor for the blocking operations of this module.
+///
+/// The executor receives tasks which perform blocking I/O or CPU heavy work.
+/// Each task must eventually be run exactly once, preferably on a thread which may block.
+/// Dropping a task without running it leaves the corresponding future pending forever.
+///
+/// The trait is implemented for all closures with the signature `Fn(Box<dyn FnOnce() + Send>)`.
+pub trait BlockingExecutor: Send + Sync {
+    /// Run `task` on a thread which may block.
+    fn execute(&self, task: Box<dyn FnOnce() + Send>);
+}
+
+impl<F> BlockingExecutor for F
+where
+    F: Fn(Box<dyn FnOnce() + Send>) + Send + Sync,
+{
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        self(task);
+    }
+}
+
+/// Executor set by [`set_blocking_executor`].
+static BLOCKING_EXECUTOR: RwLock<Option<Arc<dyn BlockingExecutor>>> = RwLock::new(None);
+
+/// Run the blocking operations of this module on `executor`.
+///
+/// By default, the blocking operations run on the thread pool of the [`blocking`] crate.
+/// This replaces any previously set executor.
+/// Operations which already started are not moved to the new executor.
+///
+/// # Example
+///
+/// ```rust
+/// // Run each blocking operation on a new thread
+/// misc_utils::futures_fs::set_blocking_executor(|task: Box<dyn FnOnce() + Send>| {
+///     std::thread::spawn(task);
+/// });
+/// ```
+pub fn set_blocking_executor<E>(executor: E)
+where
+    E: BlockingExecutor + 'static,
+{
+    let mut guard = BLOCKING_EXECUTOR
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    *guard = Some(Arc::new(executor));
+}
+
+/// Spawn `task` on the executor set by [`set_blocking_executor`] or the default thread pool.
+fn spawn_blocking(task: Box<dyn FnOnce() + Send>) {
+    let executor = BLOCKING_EXECUTOR
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone();
+    match executor {
+        Some(executor) => executor.execute(task),
+        None => blocking::unblock(task).detach(),
+    }
+}
+
+/// Run `f` with [`spawn_blocking`] and wait for its result.
+async fn unblock<T, F>(f: F) -> T
+where
+    T: 'static + Send,
+    F: 'static + FnOnce() -> T + Send,
+{
+    let shared = Arc::new(Mutex::new(TaskState {
+        result: None,
+        waker: None,
+    }));
+    let task_shared = shared.clone();
+    spawn_blocking(Box::new(move || {
+        let result = f();
+        let mut state = task_shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.result = Some(result);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }));
+    BlockingTask { shared }.await
+}
+
+/// State shared between a blocking task and the [`BlockingTask`] waiting for it.
+struct TaskState<T> {
+    result: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving to the result of a blocking task.
+struct BlockingTask<T> {
+    shared: Arc<Mutex<TaskState<T>>>,
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = self
+            .shared
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Read the entire contents of a file into a bytes vector.
+///
+/// This function supports opening compressed files transparently.
+///
+/// The API mirrors the function in [`crate::async_fs::read`] but is implemented via [`crate::fs::read`].
+pub async fn read(path: impl AsRef<Path>) -> Result<Vec<u8>, Error> {
+    let path = path.as_ref().to_owned();
+    unblock(move || crate::fs::read(path)).await
+}
+
+/// Read the entire contents of a file into a string.
+///
+/// This function supports opening compressed files transparently.
+///
+/// The API mirrors the function in [`crate::async_fs::read_to_string`] but is implemented via [`crate::fs::read_to_string`].
+pub async fn read_to_string(path: impl AsRef<Path>) -> Result<String, Error> {
+    let path = path.as_ref().to_owned();
+    unblock(move || crate::fs::read_to_string(path)).await
+}
+
+/// Write a slice as the entire contents of a file.
+///
+/// The functions chooses the filetype based on the extension.
+/// If a recognized extension is used the file will be compressed otherwise the file will be written as plaintext.
+/// If compression is used, it will use the default (6) compression ratio.
+/// The method will truncate the file before writing, such that `contents` will be the only content of the file.
+///
+/// The API mirrors the function in [`crate::async_fs::write`] but is implemented via [`crate::fs::write`].
+pub async fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    let path = path.as_ref().to_owned();
+    let contents = contents.as_ref().to_owned();
+    unblock(move || crate::fs::write(path, contents)).await
+}
+
+/// Append the content to the file.
+///
+/// This function only works for plaintext and gzip files.
+///
+/// The function is similar to [`write()`] but will append the content to the end of the file instead of truncating.
+pub async fn append(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), Error> {
+    let path = path.as_ref().to_owned();
+    let contents = contents.as_ref().to_owned();
+    unblock(move || crate::fs::append(path, contents)).await
+}
+
+/// Parse a [JSONL] file as a [`Stream`] of values.
+///
+/// The file is read and parsed by [`crate::fs::parse_jsonl_multi_threaded`], which is driven from the blocking executor.
+/// Only a limited number of values is buffered, such that reading pauses if the stream is not polled.
+/// Dropping the stream stops reading the file.
+///
+/// This function is only available if the `jsonl` feature is enabled, too.
+///
+/// [JSONL]: http://jsonlines.org/
+#[cfg(feature = "jsonl")]
+pub fn parse_jsonl_stream<T>(
+    path: impl AsRef<Path>,
+) -> impl Stream<Item = Result<T, MtJsonlError>> + Send + Unpin
+where
+    T: 'static + DeserializeOwned + Send,
+{
+    let path = path.as_ref().to_owned();
+    iter_stream(move || crate::fs::parse_jsonl_multi_threaded(path, 1024))
+}
+
+/// Number of items buffered by [`iter_stream`].
+#[cfg(feature = "jsonl")]
+const ITER_STREAM_BUFSIZE: usize = 1024;
+
+/// Drive the iterator created by `make_iter` with [`spawn_blocking`] and return its items as a [`Stream`].
+#[cfg(feature = "jsonl")]
+fn iter_stream<I, F>(make_iter: F) -> impl Stream<Item = I::Item> + Send + Unpin
+where
+    I: Iterator,
+    I::Item: 'static + Send,
+    F: 'static + FnOnce() -> I + Send,
+{
+    let (sender, receiver) = mpsc::sync_channel(ITER_STREAM_BUFSIZE);
+    let waker = Arc::new(Mutex::new(None::<Waker>));
+    let task_waker = waker.clone();
+    spawn_blocking(Box::new(move || {
+        let wake = || {
+            if let Some(waker) = task_waker
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .take()
+            {
+                waker.wake();
+            }
+        };
+        for item in make_iter() {
+            if sender.send(item).is_err() {
+                // The stream was dropped
+                return;
+            }
+            wake();
+        }
+        // Wake the stream to observe the disconnected channel
+        drop(sender);
+        wake();
+    }));
+    IterStream { receiver, waker }
+}
+
+/// Stream returned by [`iter_stream`].
+#[cfg(feature = "jsonl")]
+struct IterStream<T> {
+    receiver: mpsc::Receiver<T>,
+    waker: Arc<Mutex<Option<Waker>>>,
+}
+
+#[cfg(feature = "jsonl")]
+impl<T> Stream for IterStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        match self.receiver.try_recv() {
+            Ok(item) => return Poll::Ready(Some(item)),
+            Err(mpsc::TryRecvError::Disconnected) => return Poll::Ready(None),
+            Err(mpsc::TryRecvError::Empty) => {}
+        }
+        *self
+            .waker
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(cx.waker().clone());
+        // Check again, since the item might have been sent before the waker was registered
+        match self.receiver.try_recv() {
+            Ok(item) => Poll::Ready(Some(item)),
+            Err(mpsc::TryRecvError::Disconnected) => Poll::Ready(None),
+            Err(mpsc::TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+}
@@ -1,7 +1,15 @@
 use num_traits::Bounded;
+#[cfg(feature = "rayon")]
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
 use std::{
-    fmt::{Display, Formatter, Result as FmtResult},
+    fmt::{self, Debug, Display, Formatter, Result as FmtResult},
+    iter::Sum,
+    ops::{Add, AddAssign},
     str::FromStr,
+    sync::atomic::{
+        AtomicBool, AtomicI32, AtomicI64, AtomicIsize, AtomicU32, AtomicU64, AtomicUsize, Ordering,
+    },
+    time::Instant,
 };
 
 /// Helper type to ensure to calculate a minimal value
@@ -55,6 +63,18 @@ where
             (Some(v1), Some(v2)) => self.value = Some(v1.min(v2)),
         }
     }
+
+    /// Return the minimal value found so far and reset `self`
+    ///
+    /// Afterwards, `self` is uninitialized, as if created by [`Min::new`].
+    pub fn take(&mut self) -> Option<T> {
+        self.value.take()
+    }
+
+    /// Reset `self` to be uninitialized, as if created by [`Min::new`]
+    pub fn reset(&mut self) {
+        self.value = None;
+    }
 }
 
 impl<T> Default for Min<T> {
@@ -86,6 +106,93 @@ where
     }
 }
 
+impl<T> Extend<T> for Min<T>
+where
+    T: Copy + Ord,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.update(value);
+        }
+    }
+}
+
+impl<T> Extend<Min<T>> for Min<T>
+where
+    T: Copy + Ord,
+{
+    fn extend<I: IntoIterator<Item = Min<T>>>(&mut self, iter: I) {
+        for value in iter {
+            self.update(value);
+        }
+    }
+}
+
+/// Merge two instances, keeping the minimum of both
+impl<T> Add for Min<T>
+where
+    T: Copy + Ord,
+{
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self.update(rhs);
+        self
+    }
+}
+
+/// Merge `rhs` into `self`, keeping the minimum of both
+impl<T> AddAssign for Min<T>
+where
+    T: Copy + Ord,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        self.update(rhs);
+    }
+}
+
+/// Merge all instances, keeping the minimum of all of them
+impl<T> Sum for Min<T>
+where
+    T: Copy + Ord,
+{
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl<'a, T> Sum<&'a Min<T>> for Min<T>
+where
+    T: Copy + Ord,
+{
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> FromParallelIterator<T> for Min<T>
+where
+    T: Ord + Send,
+{
+    fn from_par_iter<I: IntoParallelIterator<Item = T>>(par_iter: I) -> Self {
+        Self {
+            value: par_iter.into_par_iter().min(),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> ParallelExtend<T> for Min<T>
+where
+    T: Copy + Ord + Send,
+{
+    fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, par_iter: I) {
+        let other: Self = par_iter.into_par_iter().collect();
+        self.update(other);
+    }
+}
+
 impl<T> Display for Min<T>
 where
     T: Display,
@@ -161,6 +268,18 @@ where
             (Some(v1), Some(v2)) => self.value = Some(v1.max(v2)),
         }
     }
+
+    /// Return the maximal value found so far and reset `self`
+    ///
+    /// Afterwards, `self` is uninitialized, as if created by [`Max::new`].
+    pub fn take(&mut self) -> Option<T> {
+        self.value.take()
+    }
+
+    /// Reset `self` to be uninitialized, as if created by [`Max::new`]
+    pub fn reset(&mut self) {
+        self.value = None;
+    }
 }
 
 impl<T> Default for Max<T> {
@@ -192,6 +311,93 @@ where
     }
 }
 
+impl<T> Extend<T> for Max<T>
+where
+    T: Copy + Ord,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.update(value);
+        }
+    }
+}
+
+impl<T> Extend<Max<T>> for Max<T>
+where
+    T: Copy + Ord,
+{
+    fn extend<I: IntoIterator<Item = Max<T>>>(&mut self, iter: I) {
+        for value in iter {
+            self.update(value);
+        }
+    }
+}
+
+/// Merge two instances, keeping the maximum of both
+impl<T> Add for Max<T>
+where
+    T: Copy + Ord,
+{
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self.update(rhs);
+        self
+    }
+}
+
+/// Merge `rhs` into `self`, keeping the maximum of both
+impl<T> AddAssign for Max<T>
+where
+    T: Copy + Ord,
+{
+    fn add_assign(&mut self, rhs: Self) {
+        self.update(rhs);
+    }
+}
+
+/// Merge all instances, keeping the maximum of all of them
+impl<T> Sum for Max<T>
+where
+    T: Copy + Ord,
+{
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl<'a, T> Sum<&'a Max<T>> for Max<T>
+where
+    T: Copy + Ord,
+{
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> FromParallelIterator<T> for Max<T>
+where
+    T: Ord + Send,
+{
+    fn from_par_iter<I: IntoParallelIterator<Item = T>>(par_iter: I) -> Self {
+        Self {
+            value: par_iter.into_par_iter().max(),
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> ParallelExtend<T> for Max<T>
+where
+    T: Copy + Ord + Send,
+{
+    fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, par_iter: I) {
+        let other: Self = par_iter.into_par_iter().collect();
+        self.update(other);
+    }
+}
+
 impl<T> Display for Max<T>
 where
     T: Display,
@@ -215,3 +421,577 @@ where
         Ok(Self::with_initial(T::from_str(s)?))
     }
 }
+
+/// Helper type to track the minimal key together with an associated value
+///
+/// The value can be any payload belonging to the key, like the record or the index which produced it.
+/// If multiple keys are equally minimal, the value of the first one is kept.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MinByKey<K, V> {
+    value: Option<(K, V)>,
+}
+
+impl<K, V> MinByKey<K, V>
+where
+    K: Ord,
+{
+    /// Create a new instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new instance with an initial key and value to compare to
+    pub fn with_initial(key: K, value: V) -> Self {
+        Self {
+            value: Some((key, value)),
+        }
+    }
+
+    /// Return the minimal key and its value found so far
+    ///
+    /// Returns `None` if neither an initial value exists nor `update` was called.
+    pub fn get_min(&self) -> Option<(&K, &V)> {
+        self.value.as_ref().map(|(k, v)| (k, v))
+    }
+
+    /// Update the key and value if `key` is lower than the current key
+    ///
+    /// The value is only replaced if `key` is strictly less than the current key.
+    pub fn update(&mut self, key: K, value: V) {
+        match &self.value {
+            Some((current, _)) if *current <= key => {}
+            _ => self.value = Some((key, value)),
+        }
+    }
+
+    /// Update the key and value with `value` and its key calculated by `f`
+    ///
+    /// The value is only replaced if the key is strictly less than the current key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::MinByKey;
+    /// let mut m = MinByKey::new();
+    /// m.update_by_key("apple", |s| s.len());
+    /// m.update_by_key("fig", |s| s.len());
+    /// assert_eq!(m.into_inner(), Some((3, "fig")));
+    /// ```
+    pub fn update_by_key<F>(&mut self, value: V, f: F)
+    where
+        F: FnOnce(&V) -> K,
+    {
+        let key = f(&value);
+        self.update(key, value);
+    }
+
+    /// Return the minimal key and its value, consuming `self`
+    pub fn into_inner(self) -> Option<(K, V)> {
+        self.value
+    }
+}
+
+impl<K, V> Default for MinByKey<K, V> {
+    fn default() -> Self {
+        Self { value: None }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for MinByKey<K, V>
+where
+    K: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut m = Self::default();
+        for (key, value) in iter {
+            m.update(key, value);
+        }
+        m
+    }
+}
+
+/// Helper type to track the maximal key together with an associated value
+///
+/// The value can be any payload belonging to the key, like the record or the index which produced it.
+/// If multiple keys are equally maximal, the value of the first one is kept.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct MaxByKey<K, V> {
+    value: Option<(K, V)>,
+}
+
+impl<K, V> MaxByKey<K, V>
+where
+    K: Ord,
+{
+    /// Create a new instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new instance with an initial key and value to compare to
+    pub fn with_initial(key: K, value: V) -> Self {
+        Self {
+            value: Some((key, value)),
+        }
+    }
+
+    /// Return the maximal key and its value found so far
+    ///
+    /// Returns `None` if neither an initial value exists nor `update` was called.
+    pub fn get_max(&self) -> Option<(&K, &V)> {
+        self.value.as_ref().map(|(k, v)| (k, v))
+    }
+
+    /// Update the key and value if `key` is greater than the current key
+    ///
+    /// The value is only replaced if `key` is strictly greater than the current key.
+    pub fn update(&mut self, key: K, value: V) {
+        match &self.value {
+            Some((current, _)) if *current >= key => {}
+            _ => self.value = Some((key, value)),
+        }
+    }
+
+    /// Update the key and value with `value` and its key calculated by `f`
+    ///
+    /// The value is only replaced if the key is strictly greater than the current key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::MaxByKey;
+    /// let mut m = MaxByKey::new();
+    /// m.update_by_key("apple", |s| s.len());
+    /// m.update_by_key("fig", |s| s.len());
+    /// assert_eq!(m.into_inner(), Some((5, "apple")));
+    /// ```
+    pub fn update_by_key<F>(&mut self, value: V, f: F)
+    where
+        F: FnOnce(&V) -> K,
+    {
+        let key = f(&value);
+        self.update(key, value);
+    }
+
+    /// Return the maximal key and its value, consuming `self`
+    pub fn into_inner(self) -> Option<(K, V)> {
+        self.value
+    }
+}
+
+impl<K, V> Default for MaxByKey<K, V> {
+    fn default() -> Self {
+        Self { value: None }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for MaxByKey<K, V>
+where
+    K: Ord,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut m = Self::default();
+        for (key, value) in iter {
+            m.update(key, value);
+        }
+        m
+    }
+}
+
+/// Helper type to track an extreme value together with the time it was observed
+///
+/// The timestamp is set whenever the extreme value of the wrapped [`Min`] or [`Max`] changes.
+/// If the same extreme value is observed again, the timestamp of the first observation is kept.
+/// By default, the timestamps are [`Instant`]s, but any type, like a wall clock time, can be used with [`Timestamped::update_at`].
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::{Max, Timestamped};
+/// let mut latency = Timestamped::<Max<u32>, &str>::new();
+/// latency.update_at(30, "10:00");
+/// latency.update_at(80, "10:05");
+/// latency.update_at(50, "10:10");
+/// assert_eq!(latency.get_max(), Some(80));
+/// assert_eq!(latency.observed_at(), Some(&"10:05"));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Timestamped<M, Ts = Instant> {
+    inner: M,
+    observed_at: Option<Ts>,
+}
+
+impl<M, Ts> Timestamped<M, Ts>
+where
+    M: Default,
+{
+    /// Create a new instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the wrapped tracker
+    pub fn inner(&self) -> &M {
+        &self.inner
+    }
+
+    /// Return the time at which the current extreme value was observed
+    ///
+    /// Returns `None` if no value was observed yet.
+    pub fn observed_at(&self) -> Option<&Ts> {
+        self.observed_at.as_ref()
+    }
+
+    /// Return the wrapped tracker and the time at which its extreme value was observed
+    pub fn into_inner(self) -> (M, Option<Ts>) {
+        (self.inner, self.observed_at)
+    }
+}
+
+impl<M, Ts> Default for Timestamped<M, Ts>
+where
+    M: Default,
+{
+    fn default() -> Self {
+        Self {
+            inner: M::default(),
+            observed_at: None,
+        }
+    }
+}
+
+impl<T, Ts> Timestamped<Min<T>, Ts>
+where
+    T: Copy + Ord,
+{
+    /// Return the minimal value found so far
+    ///
+    /// Returns `None` if `update` was not called yet.
+    pub fn get_min(&self) -> Option<T> {
+        self.inner.get_min()
+    }
+
+    /// Update the value by replacing it with a lower value, which was observed at time `at`
+    pub fn update_at(&mut self, value: T, at: Ts) {
+        if self.inner.get_min().is_none_or(|current| value < current) {
+            self.inner.update(value);
+            self.observed_at = Some(at);
+        }
+    }
+}
+
+impl<T> Timestamped<Min<T>, Instant>
+where
+    T: Copy + Ord,
+{
+    /// Update the value by replacing it with a lower value, which was observed now
+    pub fn update(&mut self, value: T) {
+        self.update_at(value, Instant::now());
+    }
+}
+
+impl<T, Ts> Timestamped<Max<T>, Ts>
+where
+    T: Copy + Ord,
+{
+    /// Return the maximal value found so far
+    ///
+    /// Returns `None` if `update` was not called yet.
+    pub fn get_max(&self) -> Option<T> {
+        self.inner.get_max()
+    }
+
+    /// Update the value by replacing it with a higher value, which was observed at time `at`
+    pub fn update_at(&mut self, value: T, at: Ts) {
+        if self.inner.get_max().is_none_or(|current| value > current) {
+            self.inner.update(value);
+            self.observed_at = Some(at);
+        }
+    }
+}
+
+impl<T> Timestamped<Max<T>, Instant>
+where
+    T: Copy + Ord,
+{
+    /// Update the value by replacing it with a higher value, which was observed now
+    pub fn update(&mut self, value: T) {
+        self.update_at(value, Instant::now());
+    }
+}
+
+/// Extension trait to collect the extreme items of an [`Iterator`] by a key
+pub trait MinMaxIteratorExt: Iterator + Sized {
+    /// Collect the item with the minimal key calculated by `f`
+    ///
+    /// If multiple items have an equally minimal key, the first one is kept.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::MinMaxIteratorExt;
+    /// let words = ["pear", "fig", "apple", "kiwi"];
+    /// let shortest = words.into_iter().collect_min_by_key(|s| s.len());
+    /// assert_eq!(shortest.into_inner(), Some((3, "fig")));
+    /// ```
+    fn collect_min_by_key<K, F>(self, f: F) -> MinByKey<K, Self::Item>
+    where
+        K: Ord,
+        F: FnMut(&Self::Item) -> K;
+
+    /// Collect the item with the maximal key calculated by `f`
+    ///
+    /// If multiple items have an equally maximal key, the first one is kept.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::MinMaxIteratorExt;
+    /// let words = ["pear", "fig", "apple", "kiwi"];
+    /// let longest = words.into_iter().collect_max_by_key(|s| s.len());
+    /// assert_eq!(longest.into_inner(), Some((5, "apple")));
+    /// ```
+    fn collect_max_by_key<K, F>(self, f: F) -> MaxByKey<K, Self::Item>
+    where
+        K: Ord,
+        F: FnMut(&Self::Item) -> K;
+}
+
+impl<I> MinMaxIteratorExt for I
+where
+    I: Iterator,
+{
+    fn collect_min_by_key<K, F>(self, mut f: F) -> MinByKey<K, Self::Item>
+    where
+        K: Ord,
+        F: FnMut(&Self::Item) -> K,
+    {
+        let mut m = MinByKey::new();
+        for item in self {
+            m.update_by_key(item, &mut f);
+        }
+        m
+    }
+
+    fn collect_max_by_key<K, F>(self, mut f: F) -> MaxByKey<K, Self::Item>
+    where
+        K: Ord,
+        F: FnMut(&Self::Item) -> K,
+    {
+        let mut m = MaxByKey::new();
+        for item in self {
+            m.update_by_key(item, &mut f);
+        }
+        m
+    }
+}
+
+mod sealed {
+    /// Integer types with a corresponding atomic type
+    pub trait AtomicInteger {
+        /// Atomic version of the integer type
+        type Atomic;
+    }
+}
+
+/// Helper type to calculate a minimal value, which can be updated concurrently
+///
+/// Unlike [`Min`], updating only requires a shared reference, such that multiple threads can update the same instance without locking.
+/// The type is available for the integer types with a corresponding atomic type, like [`u64`] and [`i64`].
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::AtomicMin;
+/// static MIN_LATENCY: AtomicMin<u64> = AtomicMin::<u64>::new();
+///
+/// std::thread::scope(|s| {
+///     for latency in [30, 10, 20] {
+///         s.spawn(move || MIN_LATENCY.update(latency));
+///     }
+/// });
+/// assert_eq!(MIN_LATENCY.get_min(), Some(10));
+/// ```
+pub struct AtomicMin<T: sealed::AtomicInteger> {
+    /// Minimal value, which is the maximal value of `T` if no value exists
+    value: T::Atomic,
+    initialized: AtomicBool,
+}
+
+/// Helper type to calculate a maximal value, which can be updated concurrently
+///
+/// Unlike [`Max`], updating only requires a shared reference, such that multiple threads can update the same instance without locking.
+/// The type is available for the integer types with a corresponding atomic type, like [`u64`] and [`i64`].
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::AtomicMax;
+/// let max = AtomicMax::<i64>::new();
+///
+/// std::thread::scope(|s| {
+///     for value in [-30, -10, -20] {
+///         let max = &max;
+///         s.spawn(move || max.update(value));
+///     }
+/// });
+/// assert_eq!(max.get_max(), Some(-10));
+/// ```
+pub struct AtomicMax<T: sealed::AtomicInteger> {
+    /// Maximal value, which is the minimal value of `T` if no value exists
+    value: T::Atomic,
+    initialized: AtomicBool,
+}
+
+macro_rules! impl_atomic_minmax {
+    ($($t:ty => $atomic:ty),* $(,)?) => {$(
+        impl sealed::AtomicInteger for $t {
+            type Atomic = $atomic;
+        }
+
+        impl AtomicMin<$t> {
+            /// Create a new instance
+            pub const fn new() -> Self {
+                Self {
+                    value: <$atomic>::new(<$t>::MAX),
+                    initialized: AtomicBool::new(false),
+                }
+            }
+
+            /// Create a new instance with an initial value to compare to
+            pub const fn with_initial(initial: $t) -> Self {
+                Self {
+                    value: <$atomic>::new(initial),
+                    initialized: AtomicBool::new(true),
+                }
+            }
+
+            /// Return the minimal value found so far
+            ///
+            /// Returns `None` if neither an initial value exists nor `update` was called.
+            /// Returns `Some(T)` if at least one value exists.
+            pub fn get_min(&self) -> Option<$t> {
+                self.initialized
+                    .load(Ordering::Acquire)
+                    .then(|| self.value.load(Ordering::Relaxed))
+            }
+
+            /// Return the minimal value found so far
+            ///
+            /// This method falls back to the maximal value for type `T`, if no other value exists.
+            pub fn get_min_extreme(&self) -> $t {
+                self.value.load(Ordering::Relaxed)
+            }
+
+            /// Update the value by replacing it with a lower value
+            ///
+            /// If `value` is less than `self`, then self is replaced with `value`.
+            pub fn update(&self, value: $t) {
+                self.value.fetch_min(value, Ordering::Relaxed);
+                self.initialized.store(true, Ordering::Release);
+            }
+
+            /// Return the current state as a [`Min`]
+            pub fn load(&self) -> Min<$t> {
+                self.get_min().map(Min::with_initial).unwrap_or_default()
+            }
+        }
+
+        impl Default for AtomicMin<$t> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl From<$t> for AtomicMin<$t> {
+            fn from(value: $t) -> Self {
+                Self::with_initial(value)
+            }
+        }
+
+        impl Debug for AtomicMin<$t> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.debug_struct("AtomicMin")
+                    .field("value", &self.get_min())
+                    .finish()
+            }
+        }
+
+        impl AtomicMax<$t> {
+            /// Create a new instance
+            pub const fn new() -> Self {
+                Self {
+                    value: <$atomic>::new(<$t>::MIN),
+                    initialized: AtomicBool::new(false),
+                }
+            }
+
+            /// Create a new instance with an initial value to compare to
+            pub const fn with_initial(initial: $t) -> Self {
+                Self {
+                    value: <$atomic>::new(initial),
+                    initialized: AtomicBool::new(true),
+                }
+            }
+
+            /// Return the maximal value found so far
+            ///
+            /// Returns `None` if neither an initial value exists nor `update` was called.
+            /// Returns `Some(T)` if at least one value exists.
+            pub fn get_max(&self) -> Option<$t> {
+                self.initialized
+                    .load(Ordering::Acquire)
+                    .then(|| self.value.load(Ordering::Relaxed))
+            }
+
+            /// Return the maximal value found so far
+            ///
+            /// This method falls back to the minimal value for type `T`, if no other value exists.
+            pub fn get_max_extreme(&self) -> $t {
+                self.value.load(Ordering::Relaxed)
+            }
+
+            /// Update the value by replacing it with a higher value
+            ///
+            /// If `value` is greater than `self`, then self is replaced with `value`.
+            pub fn update(&self, value: $t) {
+                self.value.fetch_max(value, Ordering::Relaxed);
+                self.initialized.store(true, Ordering::Release);
+            }
+
+            /// Return the current state as a [`Max`]
+            pub fn load(&self) -> Max<$t> {
+                self.get_max().map(Max::with_initial).unwrap_or_default()
+            }
+        }
+
+        impl Default for AtomicMax<$t> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl From<$t> for AtomicMax<$t> {
+            fn from(value: $t) -> Self {
+                Self::with_initial(value)
+            }
+        }
+
+        impl Debug for AtomicMax<$t> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+                f.debug_struct("AtomicMax")
+                    .field("value", &self.get_max())
+                    .finish()
+            }
+        }
+    )*};
+}
+
+impl_atomic_minmax!(
+    i32 => AtomicI32,
+    i64 => AtomicI64,
+    isize => AtomicIsize,
+    u32 => AtomicU32,
+    u64 => AtomicU64,
+    usize => AtomicUsize,
+);
@@ -1,8 +1,22 @@
 //! This module contains functions for file system path manipulation.
+//!
+//! With the `camino` feature, the [`Utf8PathExt`] and [`Utf8PathBufExt`] traits provide the same functionality for UTF-8 paths.
 
+#[cfg(feature = "camino")]
+mod utf8;
+
+#[cfg(feature = "camino")]
+pub use self::utf8::{
+    Utf8AncestorsWithin, Utf8PathBufExt, Utf8PathExt, Utf8PathExtensions, Utf8PathPrefixes,
+};
+use crate::byteascii::BYTESPRINTED;
+use crate::error::{Error, Operation};
 use std::{
     ffi::{OsStr, OsString},
-    path::{Path, PathBuf},
+    fmt::{self, Write as _},
+    fs::ReadDir,
+    iter::{FusedIterator, Take},
+    path::{Ancestors, Component, Path, PathBuf},
 };
 
 /// This traits extends the available methods on [`Path`].
@@ -35,43 +49,1026 @@ pub trait PathExt {
     /// );
     /// ```
     fn extensions(&'_ self) -> PathExtensions<'_>;
+
+    /// Iterate over all prefixes of the path, starting with the shortest one.
+    ///
+    /// The prefixes are the same paths as returned by [`Path::ancestors`], but in reverse order, and the final path is the path itself.
+    /// This is useful for creating directories one by one or for checking permissions along a path.
+    /// The empty path, which is the last ancestor of relative paths, is not returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::path::Path;
+    /// #
+    /// let p = Path::new("/home/user/file.txt");
+    /// assert_eq!(
+    ///     p.prefixes().collect::<Vec<_>>(),
+    ///     vec![
+    ///         Path::new("/"),
+    ///         Path::new("/home"),
+    ///         Path::new("/home/user"),
+    ///         Path::new("/home/user/file.txt"),
+    ///     ]
+    /// );
+    /// ```
+    fn prefixes(&self) -> PathPrefixes<'_>;
+
+    /// Convert the path into an extended-length path on Windows.
+    ///
+    /// Windows limits most paths to `MAX_PATH` (260) characters, unless they use the `\\?\` prefix.
+    /// This method turns the path into an absolute path, normalizes it, and applies the prefix.
+    /// UNC paths like `\\server\share` are converted into `\\?\UNC\server\share`.
+    /// Paths which already are verbatim or device paths are returned unchanged.
+    ///
+    /// On all other platforms, the path is returned unchanged.
+    ///
+    /// The functions in [`crate::fs`] apply this conversion automatically for overlong paths.
+    fn to_extended_length(&self) -> PathBuf;
+
+    /// Compute the relative path which leads from `base` to this path.
+    ///
+    /// This is the inverse of [`Path::join`], i.e., `base.join(path.relative_to(base)?)` refers to `path`.
+    /// Components of `base` which are not shared with this path are replaced by `..`.
+    /// The computation is purely lexical and does not access the filesystem, so symlinks are not resolved.
+    ///
+    /// Returns [`None`] if no relative path exists.
+    /// This is the case if only one of the paths is absolute, if they have different prefixes on Windows, or if `base` contains `..` components after the shared part.
+    /// If both paths are equal, the relative path is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::path::Path;
+    /// #
+    /// let p = Path::new("/home/user/projects/misc_utils/src/lib.rs");
+    /// assert_eq!(
+    ///     p.relative_to(Path::new("/home/user/docs")),
+    ///     Some("../projects/misc_utils/src/lib.rs".into())
+    /// );
+    /// assert_eq!(p.relative_to(Path::new("relative")), None);
+    /// ```
+    fn relative_to(&self, base: &Path) -> Option<PathBuf>;
+
+    /// Remove a trailing compression extension, returning the name of the uncompressed file.
+    ///
+    /// The extensions `gz`, `gzip`, `xz`, `bz2`, `bzip`, and `zst` are removed.
+    /// The compound extensions `tgz`, `txz`, `tbz2`, and `tzst` are replaced by `tar`.
+    /// Only the last extension is considered and the path is returned unchanged if it is not a compression extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::path::Path;
+    /// #
+    /// assert_eq!(Path::new("data/file.jsonl.xz").without_compression_extension(), Path::new("data/file.jsonl"));
+    /// assert_eq!(Path::new("backup.tgz").without_compression_extension(), Path::new("backup.tar"));
+    /// assert_eq!(Path::new("file.txt").without_compression_extension(), Path::new("file.txt"));
+    /// ```
+    fn without_compression_extension(&self) -> PathBuf;
+
+    /// Extract the portion of the file name before the first `.`.
+    ///
+    /// This complements [`Path::file_stem`], which splits at the last `.`.
+    /// A leading `.` of hidden files does not start an extension.
+    /// Returns [`None`] if there is no file name.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::ffi::OsStr;
+    /// # use std::path::Path;
+    /// #
+    /// assert_eq!(Path::new("data/archive.tar.xz").file_prefix(), Some(OsStr::new("archive")));
+    /// assert_eq!(Path::new(".config.toml").file_prefix(), Some(OsStr::new(".config")));
+    /// ```
+    fn file_prefix(&self) -> Option<&OsStr>;
+
+    /// Extract the portion of the file name after the first `.`.
+    ///
+    /// This contains all extensions as a single value, e.g., `tar.xz`, while [`PathExt::extensions`] iterates over them one by one.
+    /// A leading `.` of hidden files does not start an extension.
+    /// Returns [`None`] if there is no file name or no extension.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::ffi::OsStr;
+    /// # use std::path::Path;
+    /// #
+    /// assert_eq!(Path::new("data/archive.tar.xz").full_extension(), Some(OsStr::new("tar.xz")));
+    /// assert_eq!(Path::new(".bashrc").full_extension(), None);
+    /// ```
+    fn full_extension(&self) -> Option<&OsStr>;
+
+    /// Normalize the path into the Unicode Normalization Form C (canonical composition).
+    ///
+    /// Linux and Windows commonly use NFC for file names, while macOS file systems use NFD.
+    /// Normalizing both paths allows comparing the same logical file names created on different platforms.
+    /// If the path is not valid UTF-8, only the valid components are normalized and the path is otherwise normalized like [`Path::components`].
+    ///
+    /// This requires the `unicode-normalization` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::path::Path;
+    /// #
+    /// let nfd = Path::new("cafe\u{301}/file.txt");
+    /// assert_eq!(nfd.nfc(), Path::new("caf\u{e9}/file.txt"));
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    fn nfc(&self) -> PathBuf;
+
+    /// Normalize the path into the Unicode Normalization Form D (canonical decomposition).
+    ///
+    /// See [`PathExt::nfc`] for details.
+    ///
+    /// This requires the `unicode-normalization` feature.
+    #[cfg(feature = "unicode-normalization")]
+    fn nfd(&self) -> PathBuf;
+
+    /// Check if the path refers to a hidden file or directory.
+    ///
+    /// On Unix, files are hidden if their name starts with a `.`.
+    /// This check only uses the path and does not access the filesystem.
+    ///
+    /// On Windows, files are hidden if they have the `FILE_ATTRIBUTE_HIDDEN` attribute.
+    /// This requires accessing the file metadata, without following symlinks.
+    /// Files whose metadata cannot be read are considered not hidden.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::path::Path;
+    /// #
+    /// # #[cfg(unix)]
+    /// assert!(Path::new("/home/user/.bashrc").is_hidden());
+    /// assert!(!Path::new("/home/user/file.txt").is_hidden());
+    /// ```
+    fn is_hidden(&self) -> bool;
+
+    /// Iterate over the path and its ancestors, but stop at `root`.
+    ///
+    /// This works like [`Path::ancestors`], but never yields a path outside of `root`.
+    /// The last item is `root` itself.
+    /// If the path is not inside of `root`, or if it leaves `root` via `..` components, the iterator is empty.
+    /// The check is purely lexical and does not access the filesystem, so symlinks are not resolved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::path::Path;
+    /// #
+    /// let p = Path::new("/srv/data/2024/01/file.json");
+    /// assert_eq!(
+    ///     p.ancestors_within(Path::new("/srv/data")).collect::<Vec<_>>(),
+    ///     vec![
+    ///         Path::new("/srv/data/2024/01/file.json"),
+    ///         Path::new("/srv/data/2024/01"),
+    ///         Path::new("/srv/data/2024"),
+    ///         Path::new("/srv/data"),
+    ///     ]
+    /// );
+    /// assert_eq!(p.ancestors_within(Path::new("/home")).count(), 0);
+    /// ```
+    fn ancestors_within(&self, root: &Path) -> AncestorsWithin<'_>;
+
+    /// Iterate over all files and directories below this directory.
+    ///
+    /// The directory tree is traversed depth-first and each directory is yielded before its content.
+    /// The entries of a directory are yielded in the order of [`std::fs::read_dir`], which depends on the platform.
+    /// Symlinks are yielded, but not followed.
+    /// The directory itself is not part of the iterator.
+    ///
+    /// Errors while reading a directory are yielded as items, but do not stop the traversal.
+    fn descendants(&self) -> Descendants;
+
+    /// Convert an absolute path into a `file://` URL.
+    ///
+    /// All bytes except for ASCII letters, digits, `-`, `.`, `_`, `~`, and the separator `/` are percent-encoded.
+    /// On Windows, drive letters are converted into `file:///C:/` and UNC paths `\\server\share` into `file://server/share`.
+    ///
+    /// Returns [`None`] for relative paths and on Windows for paths which are not valid Unicode or use an unsupported prefix.
+    /// [`from_file_url`] converts the URL back into a path.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::path::Path;
+    /// #
+    /// # #[cfg(unix)]
+    /// assert_eq!(
+    ///     Path::new("/home/user/my data/file#1.json").to_file_url(),
+    ///     Some("file:///home/user/my%20data/file%231.json".to_string())
+    /// );
+    /// # #[cfg(windows)]
+    /// assert_eq!(
+    ///     Path::new(r"C:\Users\me\my data").to_file_url(),
+    ///     Some("file:///C:/Users/me/my%20data".to_string())
+    /// );
+    /// assert_eq!(Path::new("relative/path").to_file_url(), None);
+    /// ```
+    fn to_file_url(&self) -> Option<String>;
+
+    /// Display the path, escaping bytes which are not valid UTF-8.
+    ///
+    /// Unlike [`Path::display`], which replaces invalid bytes with U+FFFD, this prints each invalid byte in the `\xHH` notation of [`byteascii`](crate::byteascii).
+    /// ASCII control characters are escaped the same way, such that the output always fits on a single line.
+    /// Outside of Windows, a literal backslash is printed as `\\`, such that it cannot be confused with an escaped byte.
+    /// On Windows, unpaired surrogates are printed as the three escaped bytes of their WTF-8 encoding.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathExt;
+    /// # use std::path::Path;
+    /// #
+    /// assert_eq!(
+    ///     Path::new("dir/new\nline.txt").display_escaped().to_string(),
+    ///     r"dir/new\nline.txt"
+    /// );
+    ///
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::os::unix::ffi::OsStrExt;
+    /// let path = Path::new(std::ffi::OsStr::from_bytes(b"caf\xc3\xa9-\xff.txt"));
+    /// assert_eq!(path.display_escaped().to_string(), r"café-\xff.txt");
+    /// # }
+    /// ```
+    fn display_escaped(&self) -> DisplayEscaped<'_>;
 }
 
-impl PathExt for Path {
-    fn extensions(&'_ self) -> PathExtensions<'_> {
-        PathExtensions(self)
+impl PathExt for Path {
+    fn extensions(&'_ self) -> PathExtensions<'_> {
+        PathExtensions(self.full_extension())
+    }
+
+    fn prefixes(&self) -> PathPrefixes<'_> {
+        let len = self
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .count();
+        PathPrefixes {
+            path: self,
+            len,
+            front: 0,
+            back: len,
+        }
+    }
+
+    #[cfg(windows)]
+    fn to_extended_length(&self) -> PathBuf {
+        use std::path::{Component, Prefix};
+
+        let absolute = match std::path::absolute(self) {
+            Ok(absolute) => absolute,
+            Err(_) => return self.to_path_buf(),
+        };
+        let mut components = absolute.components();
+        let mut extended = match components.next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::Disk(_) => {
+                    let mut extended = OsString::from(r"\\?\");
+                    extended.push(absolute.as_os_str());
+                    return PathBuf::from(extended);
+                }
+                Prefix::UNC(server, share) => {
+                    let mut extended = OsString::from(r"\\?\UNC\");
+                    extended.push(server);
+                    extended.push(r"\");
+                    extended.push(share);
+                    PathBuf::from(extended)
+                }
+                // Verbatim and device paths cannot be extended
+                _ => return absolute,
+            },
+            _ => return absolute,
+        };
+        // Verbatim paths are not normalized, so `/` must be converted into `\`
+        for component in components {
+            match component {
+                Component::RootDir => {}
+                c => extended.push(c),
+            }
+        }
+        extended
+    }
+
+    #[cfg(not(windows))]
+    fn to_extended_length(&self) -> PathBuf {
+        self.to_path_buf()
+    }
+
+    fn relative_to(&self, base: &Path) -> Option<PathBuf> {
+        if self.is_absolute() != base.is_absolute() {
+            return None;
+        }
+
+        // `.` does not change the location, but `components` only removes it in the middle of paths
+        let mut path = self
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .peekable();
+        let mut base = base
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .peekable();
+        while let (Some(a), Some(b)) = (path.peek(), base.peek()) {
+            if a != b {
+                break;
+            }
+            path.next();
+            base.next();
+        }
+
+        let mut relative = PathBuf::new();
+        for component in base {
+            match component {
+                Component::Normal(_) => relative.push(Component::ParentDir),
+                // The directory name behind `..` is unknown, prefixes and roots cannot be left
+                Component::ParentDir | Component::Prefix(_) | Component::RootDir => return None,
+                Component::CurDir => {}
+            }
+        }
+        relative.extend(path);
+        Some(relative)
+    }
+
+    fn without_compression_extension(&self) -> PathBuf {
+        let mut path = self.to_path_buf();
+        match self.extension().and_then(OsStr::to_str) {
+            Some("gz" | "gzip" | "xz" | "bz2" | "bzip" | "zst") => {
+                path.set_extension("");
+            }
+            Some("tgz" | "txz" | "tbz2" | "tzst") => {
+                path.set_extension("tar");
+            }
+            _ => {}
+        }
+        path
+    }
+
+    fn file_prefix(&self) -> Option<&OsStr> {
+        let file_name = self.file_name()?;
+        Some(split_file_name(file_name).0)
+    }
+
+    fn full_extension(&self) -> Option<&OsStr> {
+        split_file_name(self.file_name()?).1
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    fn nfc(&self) -> PathBuf {
+        use unicode_normalization::UnicodeNormalization;
+        normalize(self, |s| s.nfc().collect())
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    fn nfd(&self) -> PathBuf {
+        use unicode_normalization::UnicodeNormalization;
+        normalize(self, |s| s.nfd().collect())
+    }
+
+    fn ancestors_within(&self, root: &Path) -> AncestorsWithin<'_> {
+        let len = match self.strip_prefix(root) {
+            Ok(rest) if rest.components().all(|c| matches!(c, Component::Normal(_))) => {
+                rest.components().count() + 1
+            }
+            _ => 0,
+        };
+        AncestorsWithin(self.ancestors().take(len))
+    }
+
+    fn descendants(&self) -> Descendants {
+        Descendants {
+            root: Some(self.to_path_buf()),
+            stack: Vec::new(),
+            error: None,
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn to_file_url(&self) -> Option<String> {
+        if !self.is_absolute() {
+            return None;
+        }
+        let mut url = String::from("file://");
+        percent_encode(self.as_os_str().as_encoded_bytes(), &mut url);
+        Some(url)
+    }
+
+    #[cfg(windows)]
+    fn to_file_url(&self) -> Option<String> {
+        use std::path::Prefix;
+
+        if !self.is_absolute() {
+            return None;
+        }
+        let mut url = String::from("file://");
+        let mut components = self.components();
+        match components.next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::Disk(drive) | Prefix::VerbatimDisk(drive) => {
+                    url.push('/');
+                    url.push(char::from(drive));
+                    url.push(':');
+                }
+                Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+                    percent_encode(server.to_str()?.as_bytes(), &mut url);
+                    url.push('/');
+                    percent_encode(share.to_str()?.as_bytes(), &mut url);
+                }
+                _ => return None,
+            },
+            _ => return None,
+        }
+        let mut has_path = false;
+        for component in components {
+            match component {
+                Component::Normal(name) => {
+                    url.push('/');
+                    percent_encode(name.to_str()?.as_bytes(), &mut url);
+                    has_path = true;
+                }
+                Component::ParentDir => {
+                    url.push_str("/..");
+                    has_path = true;
+                }
+                _ => {}
+            }
+        }
+        if !has_path {
+            url.push('/');
+        }
+        Some(url)
+    }
+
+    fn display_escaped(&self) -> DisplayEscaped<'_> {
+        DisplayEscaped(self)
+    }
+
+    #[cfg(not(windows))]
+    fn is_hidden(&self) -> bool {
+        self.file_name()
+            .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+    }
+
+    #[cfg(windows)]
+    fn is_hidden(&self) -> bool {
+        use std::os::windows::fs::MetadataExt;
+        use windows_sys::Win32::Storage::FileSystem::FILE_ATTRIBUTE_HIDDEN;
+
+        std::fs::symlink_metadata(self)
+            .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+    }
+}
+
+/// Iterator over all prefixes of a path, starting with the shortest one.
+///
+/// This iterator is returned by [`PathExt::prefixes`].
+#[derive(Clone, Debug)]
+pub struct PathPrefixes<'a> {
+    path: &'a Path,
+    /// Number of prefixes of the path
+    len: usize,
+    /// Index of the next prefix to return from the front
+    front: usize,
+    /// Index after the next prefix to return from the back
+    back: usize,
+}
+
+impl<'a> PathPrefixes<'a> {
+    /// Return the prefix with `idx` components, counting from the shortest prefix at index 0.
+    fn prefix(&self, idx: usize) -> Option<&'a Path> {
+        self.path.ancestors().nth(self.len - 1 - idx)
+    }
+}
+
+impl<'a> Iterator for PathPrefixes<'a> {
+    type Item = &'a Path;
+
+    fn next(&mut self) -> Option<&'a Path> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.prefix(self.front - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl<'a> DoubleEndedIterator for PathPrefixes<'a> {
+    fn next_back(&mut self) -> Option<&'a Path> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.prefix(self.back)
+    }
+}
+
+impl ExactSizeIterator for PathPrefixes<'_> {}
+
+impl FusedIterator for PathPrefixes<'_> {}
+
+/// Apply `normalize` to all components of `path` which are valid UTF-8.
+#[cfg(feature = "unicode-normalization")]
+fn normalize(path: &Path, normalize: fn(&str) -> String) -> PathBuf {
+    if let Some(path) = path.to_str() {
+        return PathBuf::from(normalize(path));
+    }
+    path.components()
+        .map(|component| match component {
+            Component::Normal(name) => match name.to_str() {
+                Some(name) => OsString::from(normalize(name)),
+                None => name.to_os_string(),
+            },
+            component => component.as_os_str().to_os_string(),
+        })
+        .collect()
+}
+
+/// Split a file name at the first `.`, ignoring a leading `.`.
+fn split_file_name(file_name: &OsStr) -> (&OsStr, Option<&OsStr>) {
+    let bytes = file_name.as_encoded_bytes();
+    let start = usize::from(bytes.starts_with(b"."));
+    match bytes[start..].iter().position(|&b| b == b'.') {
+        Some(pos) => {
+            let (prefix, extension) = split_at_dot(file_name, start + pos);
+            (prefix, Some(extension))
+        }
+        None => (file_name, None),
+    }
+}
+
+/// Split `s` into the parts before and after the `.` at byte position `pos`.
+fn split_at_dot(s: &OsStr, pos: usize) -> (&OsStr, &OsStr) {
+    let bytes = s.as_encoded_bytes();
+    assert_eq!(bytes[pos], b'.');
+    // SAFETY: Both parts are split next to the ASCII character `.` of a valid `OsStr`
+    unsafe {
+        (
+            OsStr::from_encoded_bytes_unchecked(&bytes[..pos]),
+            OsStr::from_encoded_bytes_unchecked(&bytes[pos + 1..]),
+        )
+    }
+}
+
+/// This traits extends the available methods on [`PathBuf`].
+pub trait PathBufExt {
+    /// Appends `extension` to [`self.file_name`](Path::file_name).
+    ///
+    /// Returns false and does nothing if [`self.file_name`](Path::file_name) is [`None`], returns `true` and appends the extension otherwise.
+    ///
+    /// The API and documentation should fully mirror [`PathBuf::set_extension`].
+    fn add_extension<S: AsRef<OsStr>>(&mut self, extension: S) -> bool;
+
+    /// Replaces all extensions of [`self.file_name`](Path::file_name) with `extensions`.
+    ///
+    /// The file name is cut after [`PathExt::file_prefix`] and the extensions are appended in order.
+    /// Empty extensions are skipped, such that an empty iterator removes all extensions.
+    ///
+    /// Returns false and does nothing if [`self.file_name`](Path::file_name) is [`None`], returns `true` and replaces the extensions otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use misc_utils::path::PathBufExt;
+    /// # use std::path::{Path, PathBuf};
+    /// #
+    /// let mut p = PathBuf::from("/data/table.csv.gz");
+    /// assert!(p.set_extensions(["jsonl", "zst"]));
+    /// assert_eq!(p, Path::new("/data/table.jsonl.zst"));
+    /// ```
+    fn set_extensions<I>(&mut self, extensions: I) -> bool
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>;
+}
+
+impl PathBufExt for PathBuf {
+    fn add_extension<S: AsRef<OsStr>>(&mut self, extension: S) -> bool {
+        if self.file_name().is_none() {
+            return false;
+        }
+
+        let mut stem = match self.file_name() {
+            Some(stem) => stem.to_os_string(),
+            None => OsString::new(),
+        };
+
+        if !extension.as_ref().is_empty() {
+            stem.push(".");
+            stem.push(extension.as_ref());
+        }
+        self.set_file_name(&stem);
+
+        true
+    }
+
+    fn set_extensions<I>(&mut self, extensions: I) -> bool
+    where
+        I: IntoIterator,
+        I::Item: AsRef<OsStr>,
+    {
+        let Some(prefix) = self.file_prefix() else {
+            return false;
+        };
+
+        let mut file_name = prefix.to_os_string();
+        for extension in extensions {
+            if !extension.as_ref().is_empty() {
+                file_name.push(".");
+                file_name.push(extension.as_ref());
+            }
+        }
+        self.set_file_name(&file_name);
+
+        true
+    }
+}
+
+/// Expand a leading `~` to the home directory of the current user.
+///
+/// On Unix, a leading `~user` is expanded to the home directory of `user`, too.
+/// Only the first component is expanded, such that `a/~/b` stays unchanged.
+/// The path is returned unchanged if the home directory cannot be determined.
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::path::expand_tilde;
+/// # use std::path::Path;
+/// #
+/// let home = std::env::home_dir().unwrap();
+/// assert_eq!(expand_tilde("~/data/file.json.gz"), home.join("data/file.json.gz"));
+/// assert_eq!(expand_tilde("data/~"), Path::new("data/~"));
+/// ```
+pub fn expand_tilde<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    let mut components = path.components();
+    let home = match components.next() {
+        Some(Component::Normal(first)) if first == "~" => std::env::home_dir(),
+        #[cfg(unix)]
+        Some(Component::Normal(first)) => {
+            use std::os::unix::ffi::OsStrExt;
+            match first.as_bytes().strip_prefix(b"~") {
+                Some(user) => user_home_dir(user),
+                None => None,
+            }
+        }
+        _ => None,
+    };
+    match home {
+        Some(mut home) => {
+            home.extend(components);
+            home
+        }
+        None => path.to_path_buf(),
+    }
+}
+
+/// Look up the home directory of `user` in the password database.
+#[cfg(unix)]
+fn user_home_dir(user: &[u8]) -> Option<PathBuf> {
+    use std::{
+        ffi::{CStr, CString},
+        os::unix::ffi::OsStrExt,
+    };
+
+    let user = CString::new(user).ok()?;
+    let mut buffer = vec![0; 1024];
+    loop {
+        // SAFETY: `passwd` only contains integers and pointers, for which all zero is valid
+        let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        // SAFETY: All pointers are valid and the length matches the buffer
+        let res = unsafe {
+            libc::getpwnam_r(
+                user.as_ptr(),
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+        if res == libc::ERANGE {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if res != 0 || result.is_null() || passwd.pw_dir.is_null() {
+            return None;
+        }
+        // SAFETY: `pw_dir` points to a NUL-terminated string inside `buffer`
+        let dir = unsafe { CStr::from_ptr(passwd.pw_dir) };
+        return Some(PathBuf::from(OsStr::from_bytes(dir.to_bytes())));
+    }
+}
+
+/// Substitute environment variables in a path.
+///
+/// Variables are written as `$VAR` or `${VAR}` and on Windows also as `%VAR%`.
+/// Variable names consist of ASCII letters, digits, and `_`, and for `$VAR` must not start with a digit.
+/// Variables which are not set are left unchanged.
+///
+/// The variables are looked up in the environment of the process, see [`expand_env_with`] to use a custom lookup.
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::path::expand_env;
+/// # use std::path::Path;
+/// #
+/// if let Some(home) = std::env::var_os("HOME") {
+///     assert_eq!(expand_env("$HOME/data"), Path::new(&home).join("data"));
+/// }
+/// ```
+pub fn expand_env<P: AsRef<Path>>(path: P) -> PathBuf {
+    expand_env_with(path, |name| std::env::var_os(name))
+}
+
+/// Substitute variables in a path, using `lookup` to determine their values.
+///
+/// See [`expand_env`] for the syntax of variables.
+/// `lookup` receives the name of the variable and returns its value, or [`None`] to leave the variable unchanged.
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::path::expand_env_with;
+/// # use std::path::Path;
+/// #
+/// let path = expand_env_with("${XDG_DATA_HOME}/app/$UNKNOWN", |name| match name {
+///     "XDG_DATA_HOME" => Some("/home/user/.local/share".into()),
+///     _ => None,
+/// });
+/// assert_eq!(path, Path::new("/home/user/.local/share/app/$UNKNOWN"));
+/// ```
+pub fn expand_env_with<P, F>(path: P, mut lookup: F) -> PathBuf
+where
+    P: AsRef<Path>,
+    F: FnMut(&str) -> Option<OsString>,
+{
+    let path = path.as_ref().as_os_str();
+    let bytes = path.as_encoded_bytes();
+    let mut expanded = OsString::with_capacity(path.len());
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let Some((name, end)) = parse_variable(bytes, i) else {
+            i += 1;
+            continue;
+        };
+        if let Some(value) = lookup(name) {
+            // SAFETY: Variables start and end with ASCII characters, so the literal is split next to them
+            expanded.push(unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[literal_start..i]) });
+            expanded.push(value);
+            literal_start = end;
+        }
+        i = end;
+    }
+    // SAFETY: The literal starts after an ASCII character or at the start
+    expanded.push(unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[literal_start..]) });
+    PathBuf::from(expanded)
+}
+
+/// Parse a variable starting at `bytes[start]`.
+///
+/// Returns the name of the variable and the index after its end.
+fn parse_variable(bytes: &[u8], start: usize) -> Option<(&str, usize)> {
+    let is_name = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+    let name_len = |from: usize| bytes[from..].iter().take_while(|b| is_name(b)).count();
+    // Names only contain ASCII characters
+    let name = |from: usize, len: usize| std::str::from_utf8(&bytes[from..from + len]).ok();
+
+    match bytes.get(start..start + 2)? {
+        [b'$', b'{'] => {
+            let len = name_len(start + 2);
+            let end = start + 2 + len;
+            if len > 0 && bytes.get(end) == Some(&b'}') {
+                return Some((name(start + 2, len)?, end + 1));
+            }
+            None
+        }
+        [b'$', first] if !first.is_ascii_digit() => {
+            let len = name_len(start + 1);
+            if len > 0 {
+                return Some((name(start + 1, len)?, start + 1 + len));
+            }
+            None
+        }
+        #[cfg(windows)]
+        [b'%', _] => {
+            let len = name_len(start + 1);
+            let end = start + 1 + len;
+            if len > 0 && bytes.get(end) == Some(&b'%') {
+                return Some((name(start + 1, len)?, end + 1));
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+/// Convert a `file://` URL into a path.
+///
+/// Percent-encoded bytes are decoded and a query or fragment part is ignored.
+/// The host must be empty or `localhost`.
+/// On Windows, drive letters like `file:///C:/` are supported and other hosts are converted into UNC paths `\\server\share`.
+///
+/// Returns [`None`] if `url` is not a valid `file://` URL for the platform.
+/// This is the inverse of [`PathExt::to_file_url`].
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::path::from_file_url;
+/// # use std::path::Path;
+/// #
+/// # #[cfg(unix)]
+/// assert_eq!(
+///     from_file_url("file:///home/user/my%20data/file%231.json"),
+///     Some(Path::new("/home/user/my data/file#1.json").to_path_buf())
+/// );
+/// # #[cfg(windows)]
+/// assert_eq!(
+///     from_file_url("file:///C:/Users/me/my%20data"),
+///     Some(Path::new(r"C:\Users\me\my data").to_path_buf())
+/// );
+/// assert_eq!(from_file_url("https://example.com/file"), None);
+/// ```
+pub fn from_file_url(url: &str) -> Option<PathBuf> {
+    const SCHEME: &str = "file://";
+    if !url.get(..SCHEME.len())?.eq_ignore_ascii_case(SCHEME) {
+        return None;
+    }
+    let rest = &url[SCHEME.len()..];
+    let rest = rest.split(['?', '#']).next().unwrap_or_default();
+    let (host, path) = rest.split_at(rest.find('/')?);
+    let path = percent_decode(path)?;
+    let local = host.is_empty() || host.eq_ignore_ascii_case("localhost");
+
+    #[cfg(not(windows))]
+    {
+        use std::os::unix::ffi::OsStringExt;
+
+        if !local {
+            return None;
+        }
+        Some(PathBuf::from(OsString::from_vec(path)))
+    }
+
+    #[cfg(windows)]
+    {
+        let path = String::from_utf8(path).ok()?.replace('/', "\\");
+        if local {
+            // Strip the separator in front of the drive letter
+            let path = &path[1..];
+            let bytes = path.as_bytes();
+            if bytes.len() < 2 || !bytes[0].is_ascii_alphabetic() || bytes[1] != b':' {
+                return None;
+            }
+            if bytes.len() == 2 {
+                return Some(PathBuf::from(format!("{path}\\")));
+            }
+            Some(PathBuf::from(path))
+        } else {
+            let host = String::from_utf8(percent_decode(host)?).ok()?;
+            Some(PathBuf::from(format!("\\\\{host}{path}")))
+        }
     }
 }
 
-/// This traits extends the available methods on [`PathBuf`].
-pub trait PathBufExt {
-    /// Appends `extension` to [`self.file_name`](Path::file_name).
-    ///
-    /// Returns false and does nothing if [`self.file_name`](Path::file_name) is [`None`], returns `true` and appends the extension otherwise.
-    ///
-    /// The API and documentation should fully mirror [`PathBuf::set_extension`].
-    fn add_extension<S: AsRef<OsStr>>(&mut self, extension: S) -> bool;
+/// Convert a Windows path into the corresponding path inside of WSL.
+///
+/// Paths on a drive like `C:\Users\me\data` are converted into `/mnt/c/Users/me/data`.
+/// Both `\` and `/` are accepted as separators and verbatim paths (`\\?\C:\`) are supported, too.
+/// The conversion only works on the path string, so it gives the same result on every platform.
+///
+/// Returns [`None`] if `path` is not valid Unicode or not an absolute path on a drive, e.g., a relative or a UNC path.
+/// [`from_wsl`] performs the inverse conversion.
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::path::to_wsl;
+/// # use std::path::Path;
+/// #
+/// assert_eq!(
+///     to_wsl(Path::new(r"C:\Users\me\data")),
+///     Some("/mnt/c/Users/me/data".to_string())
+/// );
+/// assert_eq!(to_wsl(Path::new(r"\\server\share")), None);
+/// ```
+pub fn to_wsl(path: &Path) -> Option<String> {
+    let path = path.to_str()?;
+    let path = path.strip_prefix(r"\\?\").unwrap_or(path);
+    let mut chars = path.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    if chars.next() != Some(':') {
+        return None;
+    }
+    let rest = chars.as_str();
+    // Drive-relative paths like `C:data` depend on the current directory of the drive
+    if !rest.is_empty() && !rest.starts_with(['\\', '/']) {
+        return None;
+    }
+
+    let mut wsl = format!("/mnt/{}", drive.to_ascii_lowercase());
+    for part in rest.split(['\\', '/']).filter(|part| !part.is_empty()) {
+        wsl.push('/');
+        wsl.push_str(part);
+    }
+    Some(wsl)
 }
 
-impl PathBufExt for PathBuf {
-    fn add_extension<S: AsRef<OsStr>>(&mut self, extension: S) -> bool {
-        if self.file_name().is_none() {
-            return false;
-        }
+/// Convert a path inside of WSL into the corresponding Windows path.
+///
+/// Paths below a drive mount like `/mnt/c/Users/me/data` are converted into `C:\Users\me\data`.
+/// The conversion only works on the path string, so it gives the same result on every platform.
+///
+/// Returns [`None`] if `path` is not below a drive mount in `/mnt`.
+/// [`to_wsl`] performs the inverse conversion.
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::path::from_wsl;
+/// # use std::path::PathBuf;
+/// #
+/// assert_eq!(
+///     from_wsl("/mnt/c/Users/me/data"),
+///     Some(PathBuf::from(r"C:\Users\me\data"))
+/// );
+/// assert_eq!(from_wsl("/home/me"), None);
+/// ```
+pub fn from_wsl(path: &str) -> Option<PathBuf> {
+    let rest = path.strip_prefix("/mnt/")?;
+    let mut chars = rest.chars();
+    let drive = chars.next().filter(char::is_ascii_alphabetic)?;
+    let rest = chars.as_str();
+    if !rest.is_empty() && !rest.starts_with('/') {
+        return None;
+    }
 
-        let mut stem = match self.file_name() {
-            Some(stem) => stem.to_os_string(),
-            None => OsString::new(),
-        };
+    let mut windows = format!("{}:", drive.to_ascii_uppercase());
+    let mut parts = rest.split('/').filter(|part| !part.is_empty()).peekable();
+    if parts.peek().is_none() {
+        windows.push('\\');
+    }
+    for part in parts {
+        windows.push('\\');
+        windows.push_str(part);
+    }
+    Some(PathBuf::from(windows))
+}
 
-        if !extension.as_ref().is_empty() {
-            stem.push(".");
-            stem.push(extension.as_ref());
+/// Append `bytes` to `out`, percent-encoding all bytes except unreserved characters and `/`.
+fn percent_encode(bytes: &[u8], out: &mut String) {
+    for &b in bytes {
+        if b.is_ascii_alphanumeric() || b"-._~/".contains(&b) {
+            out.push(char::from(b));
+        } else {
+            out.push_str(&format!("%{b:02X}"));
         }
-        self.set_file_name(&stem);
+    }
+}
 
-        true
+/// Decode all percent-encoded bytes of `s`.
+///
+/// Returns [`None`] if `s` contains an invalid percent-encoding.
+fn percent_decode(s: &str) -> Option<Vec<u8>> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            // `from_str_radix` alone would accept a sign, like in `%+5`
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?)
+                .ok()
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
     }
+    Some(decoded)
 }
 
 /// Iterator over all file extensions of a [`Path`].
@@ -83,6 +1080,9 @@ impl PathBufExt for PathBuf {
 /// Accessing multiple extension can be useful, if extensions are chained to provide hints how the
 /// file is structured, e.g., `archive.tar.xz`.
 ///
+/// The iterator is double-ended, such that the first extension can be accessed with [`DoubleEndedIterator::next_back`] or [`Iterator::last`].
+/// The extensions are the parts of [`PathExt::full_extension`] separated by `.`.
+///
 /// # Example
 ///
 /// ```rust
@@ -102,27 +1102,173 @@ impl PathBufExt for PathBuf {
 /// );
 /// ```
 #[derive(Copy, Clone, Debug)]
-pub struct PathExtensions<'a>(&'a Path);
+pub struct PathExtensions<'a>(Option<&'a OsStr>);
 
 impl<'a> Iterator for PathExtensions<'a> {
     type Item = &'a OsStr;
 
     fn next(&mut self) -> Option<&'a OsStr> {
-        let (new_filestem, new_extension) = (self.0.file_stem(), self.0.extension());
-        if new_extension.is_none() {
-            self.0 = Path::new("");
-            None
-        } else {
-            if let Some(new_filestem) = new_filestem {
-                self.0 = Path::new(new_filestem);
-            } else {
-                self.0 = Path::new("");
+        let remaining = self.0?;
+        match remaining
+            .as_encoded_bytes()
+            .iter()
+            .rposition(|&b| b == b'.')
+        {
+            Some(pos) => {
+                let (rest, extension) = split_at_dot(remaining, pos);
+                self.0 = Some(rest);
+                Some(extension)
+            }
+            None => self.0.take(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+
+    fn last(mut self) -> Option<&'a OsStr> {
+        self.next_back()
+    }
+}
+
+impl<'a> DoubleEndedIterator for PathExtensions<'a> {
+    fn next_back(&mut self) -> Option<&'a OsStr> {
+        let remaining = self.0?;
+        match remaining.as_encoded_bytes().iter().position(|&b| b == b'.') {
+            Some(pos) => {
+                let (extension, rest) = split_at_dot(remaining, pos);
+                self.0 = Some(rest);
+                Some(extension)
+            }
+            None => self.0.take(),
+        }
+    }
+}
+
+impl ExactSizeIterator for PathExtensions<'_> {
+    fn len(&self) -> usize {
+        self.0.map_or(0, |remaining| {
+            memchr::memchr_iter(b'.', remaining.as_encoded_bytes()).count() + 1
+        })
+    }
+}
+
+impl FusedIterator for PathExtensions<'_> {}
+
+/// Helper struct to display a path with escaped invalid UTF-8 bytes.
+///
+/// This struct is created by [`PathExt::display_escaped`].
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayEscaped<'a>(&'a Path);
+
+impl fmt::Display for DisplayEscaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for chunk in self.0.as_os_str().as_encoded_bytes().utf8_chunks() {
+            for c in chunk.valid().chars() {
+                // Backslashes are separators on Windows, elsewhere they are escaped to keep the output unambiguous
+                if c.is_ascii_control() || (c == '\\' && !cfg!(windows)) {
+                    f.write_str(BYTESPRINTED[c as usize])?;
+                } else {
+                    f.write_char(c)?;
+                }
+            }
+            for &b in chunk.invalid() {
+                f.write_str(BYTESPRINTED[b as usize])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Iterator over a path and its ancestors, which stops at a root directory.
+///
+/// This iterator is created by [`PathExt::ancestors_within`].
+#[derive(Clone, Debug)]
+pub struct AncestorsWithin<'a>(Take<Ancestors<'a>>);
+
+impl<'a> Iterator for AncestorsWithin<'a> {
+    type Item = &'a Path;
+
+    fn next(&mut self) -> Option<&'a Path> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl FusedIterator for AncestorsWithin<'_> {}
+
+/// Iterator over all files and directories below a directory.
+///
+/// This iterator is created by [`PathExt::descendants`].
+#[derive(Debug)]
+pub struct Descendants {
+    /// Directory which is read on the first call to `next`.
+    root: Option<PathBuf>,
+    /// Directories which are currently read.
+    stack: Vec<(PathBuf, ReadDir)>,
+    /// Error to yield after the directory which caused it.
+    error: Option<Error>,
+}
+
+impl Iterator for Descendants {
+    type Item = Result<PathBuf, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        if let Some(root) = self.root.take() {
+            match read_dir(&root) {
+                Ok(entries) => self.stack.push((root, entries)),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        loop {
+            let (dir, entries) = self.stack.last_mut()?;
+            let entry = match entries.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => {
+                    return Some(Err(Error::FileIo {
+                        file: dir.clone(),
+                        operation: Operation::ReadDir,
+                        source: err,
+                    }))
+                }
+                None => {
+                    self.stack.pop();
+                    continue;
+                }
             };
-            new_extension
+            let path = entry.path();
+            // `DirEntry::file_type` does not follow symlinks
+            if entry.file_type().is_ok_and(|ft| ft.is_dir()) {
+                match read_dir(&path) {
+                    Ok(entries) => self.stack.push((path.clone(), entries)),
+                    Err(err) => self.error = Some(err),
+                }
+            }
+            return Some(Ok(path));
         }
     }
 }
 
+impl FusedIterator for Descendants {}
+
+/// Open the directory `path` for reading.
+fn read_dir(path: &Path) -> Result<ReadDir, Error> {
+    std::fs::read_dir(path).map_err(|err| Error::FileIo {
+        file: path.to_path_buf(),
+        operation: Operation::ReadDir,
+        source: err,
+    })
+}
+
 #[test]
 fn test_path_extensions() {
     let p = &Path::new("/home/user/projects/misc_utils/Cargo.toml");
@@ -163,6 +1309,32 @@ fn test_pathbuf_extensions() {
     assert_eq!(p.extensions().collect::<Vec<_>>(), vec![OsStr::new("file")]);
 }
 
+#[cfg(not(windows))]
+#[test]
+fn test_to_extended_length() {
+    let p = Path::new("/home/user/projects/misc_utils/Cargo.toml");
+    assert_eq!(p.to_extended_length(), p);
+    let p = Path::new("relative/path");
+    assert_eq!(p.to_extended_length(), p);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_to_extended_length() {
+    let p = Path::new(r"C:\Users\user\..\data\file.txt");
+    assert_eq!(
+        p.to_extended_length(),
+        Path::new(r"\\?\C:\Users\data\file.txt")
+    );
+    let p = Path::new(r"\\server\share\dir\file.txt");
+    assert_eq!(
+        p.to_extended_length(),
+        Path::new(r"\\?\UNC\server\share\dir\file.txt")
+    );
+    let p = Path::new(r"\\?\C:\already\extended");
+    assert_eq!(p.to_extended_length(), p);
+}
+
 #[test]
 fn test_add_extension() {
     let mut pb = PathBuf::from("some.file");
@@ -177,3 +1349,407 @@ fn test_add_extension() {
     let mut pb = PathBuf::from("/");
     assert!(!PathBufExt::add_extension(&mut pb, "ext"));
 }
+
+#[test]
+fn test_relative_to() {
+    let relative = |path: &str, base: &str| Path::new(path).relative_to(Path::new(base));
+
+    assert_eq!(relative("/a/b/c", "/a/b"), Some("c".into()));
+    assert_eq!(relative("/a/b", "/a/b/c"), Some("..".into()));
+    assert_eq!(relative("/a/b/c", "/a/x/y"), Some("../../b/c".into()));
+    assert_eq!(relative("/a/b", "/a/b"), Some("".into()));
+    assert_eq!(relative("/a/b", "/"), Some("a/b".into()));
+    assert_eq!(relative("a/b", "./a/c/"), Some("../b".into()));
+    assert_eq!(relative("../a", "b"), Some("../../a".into()));
+    assert_eq!(relative("../a", "../b"), Some("../a".into()));
+    // The directory behind `..` is unknown
+    assert_eq!(relative("a", "../b"), None);
+    assert_eq!(relative("/a", "b"), None);
+    assert_eq!(relative("a", "/b"), None);
+
+    // Joining the relative path to the base is the inverse
+    let (path, base) = (
+        Path::new("/home/user/data/file.txt"),
+        Path::new("/home/other"),
+    );
+    assert_eq!(
+        base.join(path.relative_to(base).unwrap()),
+        Path::new("/home/other/../user/data/file.txt")
+    );
+}
+
+#[test]
+fn test_prefixes() {
+    let p = Path::new("/home/user/file.txt");
+    assert_eq!(
+        p.prefixes().collect::<Vec<_>>(),
+        vec![
+            Path::new("/"),
+            Path::new("/home"),
+            Path::new("/home/user"),
+            Path::new("/home/user/file.txt"),
+        ]
+    );
+    assert_eq!(p.prefixes().len(), 4);
+    assert_eq!(p.prefixes().next_back(), Some(p));
+    assert_eq!(
+        p.prefixes().rev().collect::<Vec<_>>(),
+        p.ancestors().collect::<Vec<_>>()
+    );
+
+    let mut prefixes = Path::new("a/b/c").prefixes();
+    assert_eq!(prefixes.next(), Some(Path::new("a")));
+    assert_eq!(prefixes.next_back(), Some(Path::new("a/b/c")));
+    assert_eq!(prefixes.len(), 1);
+    assert_eq!(prefixes.next(), Some(Path::new("a/b")));
+    assert_eq!(prefixes.next_back(), None);
+    assert_eq!(prefixes.next(), None);
+
+    assert_eq!(Path::new("").prefixes().count(), 0);
+    assert_eq!(
+        Path::new("/").prefixes().collect::<Vec<_>>(),
+        vec![Path::new("/")]
+    );
+}
+
+#[test]
+fn test_without_compression_extension() {
+    let strip = |path: &str| Path::new(path).without_compression_extension();
+
+    assert_eq!(strip("/data/file.jsonl.gz"), Path::new("/data/file.jsonl"));
+    assert_eq!(strip("file.json.xz"), Path::new("file.json"));
+    assert_eq!(strip("file.bz2"), Path::new("file"));
+    assert_eq!(strip("archive.tar.zst"), Path::new("archive.tar"));
+    assert_eq!(strip("archive.tgz"), Path::new("archive.tar"));
+    assert_eq!(strip("archive.txz"), Path::new("archive.tar"));
+    // Only the last extension is removed
+    assert_eq!(strip("file.gz.gz"), Path::new("file.gz"));
+    assert_eq!(strip("file.gz.txt"), Path::new("file.gz.txt"));
+    assert_eq!(strip(".gz"), Path::new(".gz"));
+    assert_eq!(strip("/"), Path::new("/"));
+}
+
+#[test]
+fn test_file_prefix_full_extension() {
+    fn split(path: &str) -> (Option<&OsStr>, Option<&OsStr>) {
+        let path = Path::new(path);
+        (path.file_prefix(), path.full_extension())
+    }
+
+    assert_eq!(
+        split("/data/archive.tar.xz"),
+        (Some(OsStr::new("archive")), Some(OsStr::new("tar.xz")))
+    );
+    assert_eq!(
+        split("Cargo.toml"),
+        (Some(OsStr::new("Cargo")), Some(OsStr::new("toml")))
+    );
+    assert_eq!(split("README"), (Some(OsStr::new("README")), None));
+    assert_eq!(split(".hidden"), (Some(OsStr::new(".hidden")), None));
+    assert_eq!(
+        split(".config.toml.bak"),
+        (Some(OsStr::new(".config")), Some(OsStr::new("toml.bak")))
+    );
+    assert_eq!(
+        split("trailing."),
+        (Some(OsStr::new("trailing")), Some(OsStr::new("")))
+    );
+    assert_eq!(split("/"), (None, None));
+    assert_eq!(split("dir/.."), (None, None));
+}
+
+#[test]
+fn test_set_extensions() {
+    let mut pb = PathBuf::from("/data/table.csv.gz");
+    assert!(pb.set_extensions(["jsonl", "zst"]));
+    assert_eq!(pb, Path::new("/data/table.jsonl.zst"));
+    assert!(pb.set_extensions(["", "txt"]));
+    assert_eq!(pb, Path::new("/data/table.txt"));
+    assert!(pb.set_extensions(Vec::<&str>::new()));
+    assert_eq!(pb, Path::new("/data/table"));
+
+    let mut pb = PathBuf::from(".config.toml");
+    assert!(pb.set_extensions(["yaml"]));
+    assert_eq!(pb, Path::new(".config.yaml"));
+
+    let mut pb = PathBuf::from("/");
+    assert!(!pb.set_extensions(["ext"]));
+}
+
+#[test]
+fn test_expand_tilde() {
+    let home = std::env::home_dir().unwrap();
+    assert_eq!(expand_tilde("~"), home);
+    assert_eq!(
+        expand_tilde("~/data/file.json"),
+        home.join("data/file.json")
+    );
+    assert_eq!(expand_tilde("data/~/file"), Path::new("data/~/file"));
+    assert_eq!(expand_tilde("/~"), Path::new("/~"));
+    assert_eq!(
+        expand_tilde("~~unknown-user~~/file"),
+        Path::new("~~unknown-user~~/file")
+    );
+    #[cfg(target_os = "linux")]
+    assert_eq!(expand_tilde("~root/file"), Path::new("/root/file"));
+}
+
+#[test]
+fn test_expand_env() {
+    let lookup = |name: &str| match name {
+        "HOME" => Some(OsString::from("/home/user")),
+        "EMPTY" => Some(OsString::new()),
+        "A_1" => Some(OsString::from("x")),
+        _ => None,
+    };
+    let expand = |path: &str| expand_env_with(path, lookup);
+
+    assert_eq!(expand("$HOME/data"), Path::new("/home/user/data"));
+    assert_eq!(expand("${HOME}/data"), Path::new("/home/user/data"));
+    assert_eq!(expand("/a/$A_1$A_1/${A_1}b"), Path::new("/a/xx/xb"));
+    assert_eq!(expand("$EMPTY/file"), Path::new("/file"));
+    assert_eq!(
+        expand("$UNKNOWN/${UNKNOWN}"),
+        Path::new("$UNKNOWN/${UNKNOWN}")
+    );
+    assert_eq!(expand("$/${}/$1/${HOME"), Path::new("$/${}/$1/${HOME"));
+    assert_eq!(expand("trailing$"), Path::new("trailing$"));
+    #[cfg(windows)]
+    assert_eq!(
+        expand(r"%HOME%\data\%UNKNOWN%"),
+        Path::new(r"/home/user\data\%UNKNOWN%")
+    );
+    #[cfg(not(windows))]
+    assert_eq!(expand("%HOME%"), Path::new("%HOME%"));
+
+    assert_eq!(
+        expand_env("$MISC_UTILS_UNSET_VARIABLE/file"),
+        Path::new("$MISC_UTILS_UNSET_VARIABLE/file")
+    );
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_unicode_normalization() {
+    let nfc = Path::new("/data/caf\u{e9}/\u{c5}ngstr\u{f6}m.txt");
+    let nfd = Path::new("/data/cafe\u{301}/A\u{30a}ngstro\u{308}m.txt");
+    assert_ne!(nfc, nfd);
+    assert_eq!(nfd.nfc(), nfc);
+    assert_eq!(nfc.nfd(), nfd);
+    assert_eq!(nfc.nfc(), nfc);
+    assert_eq!(
+        Path::new("relative/./name").nfc(),
+        Path::new("relative/name")
+    );
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let invalid = Path::new(OsStr::from_bytes(b"cafe\xcc\x81/\xff"));
+        assert_eq!(
+            invalid.nfc(),
+            Path::new(OsStr::from_bytes(b"caf\xc3\xa9/\xff"))
+        );
+    }
+}
+
+#[cfg(not(windows))]
+#[test]
+fn test_is_hidden() {
+    assert!(Path::new(".hidden").is_hidden());
+    assert!(Path::new("/home/user/.config").is_hidden());
+    assert!(Path::new("/home/user/.config/").is_hidden());
+    assert!(!Path::new("/home/.user/config").is_hidden());
+    assert!(!Path::new("file.txt").is_hidden());
+    assert!(!Path::new(".").is_hidden());
+    assert!(!Path::new("..").is_hidden());
+    assert!(!Path::new("/").is_hidden());
+}
+
+#[test]
+fn test_path_extensions_double_ended() {
+    let p = Path::new("/data/archive.tar.xz");
+    let mut extensions = p.extensions();
+    assert_eq!(extensions.len(), 2);
+    assert_eq!(extensions.next_back(), Some(OsStr::new("tar")));
+    assert_eq!(extensions.size_hint(), (1, Some(1)));
+    assert_eq!(extensions.next(), Some(OsStr::new("xz")));
+    assert_eq!(extensions.len(), 0);
+    assert_eq!(extensions.next(), None);
+    assert_eq!(extensions.next_back(), None);
+
+    let p = Path::new("This.File.has.many.extensions");
+    assert_eq!(p.extensions().last(), Some(OsStr::new("File")));
+    assert_eq!(
+        p.extensions().rev().collect::<Vec<_>>(),
+        vec![
+            OsStr::new("File"),
+            OsStr::new("has"),
+            OsStr::new("many"),
+            OsStr::new("extensions")
+        ]
+    );
+    assert_eq!(
+        Path::new("a..b").extensions().collect::<Vec<_>>(),
+        vec![OsStr::new("b"), OsStr::new("")]
+    );
+    assert_eq!(Path::new(".hidden").extensions().len(), 0);
+    assert_eq!(Path::new("/").extensions().len(), 0);
+}
+
+#[test]
+fn test_ancestors_within() {
+    fn within<'a>(path: &'a str, root: &str) -> Vec<&'a Path> {
+        Path::new(path).ancestors_within(Path::new(root)).collect()
+    }
+
+    assert_eq!(
+        within("/a/b/c", "/a"),
+        vec![Path::new("/a/b/c"), Path::new("/a/b"), Path::new("/a")]
+    );
+    assert_eq!(within("/a", "/a"), vec![Path::new("/a")]);
+    assert_eq!(within("a/b", "a/"), vec![Path::new("a/b"), Path::new("a")]);
+    assert_eq!(within("/a/b", "/x"), Vec::<&Path>::new());
+    assert_eq!(within("/ab", "/a"), Vec::<&Path>::new());
+    // Leaving the root via `..` is not allowed
+    assert_eq!(within("/a/../b", "/a"), Vec::<&Path>::new());
+}
+
+#[test]
+fn test_descendants() {
+    let tmpdir = tempfile::Builder::new().tempdir().unwrap();
+    let root = tmpdir.path();
+    std::fs::create_dir_all(root.join("a/b")).unwrap();
+    std::fs::create_dir(root.join("c")).unwrap();
+    std::fs::write(root.join("a/b/file"), "").unwrap();
+    std::fs::write(root.join("file"), "").unwrap();
+
+    let descendants = root.descendants().collect::<Result<Vec<_>, _>>().unwrap();
+    let mut relative: Vec<_> = descendants
+        .iter()
+        .map(|path| path.strip_prefix(root).unwrap())
+        .collect();
+    // Directories come before their content
+    let position = |p: &str| relative.iter().position(|r| *r == Path::new(p)).unwrap();
+    assert!(position("a") < position("a/b"));
+    assert!(position("a/b") < position("a/b/file"));
+    relative.sort();
+    assert_eq!(
+        relative,
+        vec![
+            Path::new("a"),
+            Path::new("a/b"),
+            Path::new("a/b/file"),
+            Path::new("c"),
+            Path::new("file")
+        ]
+    );
+
+    let mut missing = root.join("missing").descendants();
+    assert!(matches!(missing.next(), Some(Err(Error::FileIo { .. }))));
+    assert!(missing.next().is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_file_url() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let roundtrip = |path: &Path, url: &str| {
+        assert_eq!(path.to_file_url().as_deref(), Some(url));
+        assert_eq!(from_file_url(url).as_deref(), Some(path));
+    };
+    roundtrip(Path::new("/"), "file:///");
+    roundtrip(
+        Path::new("/home/user/file.txt"),
+        "file:///home/user/file.txt",
+    );
+    roundtrip(
+        Path::new("/data/my file#1?.json"),
+        "file:///data/my%20file%231%3F.json",
+    );
+    roundtrip(Path::new("/caf\u{e9}"), "file:///caf%C3%A9");
+    roundtrip(Path::new(OsStr::from_bytes(b"/\xff")), "file:///%FF");
+    assert_eq!(Path::new("relative").to_file_url(), None);
+
+    assert_eq!(
+        from_file_url("FILE://localhost/etc/hosts?query#fragment"),
+        Some(PathBuf::from("/etc/hosts"))
+    );
+    assert_eq!(from_file_url("file://server/share"), None);
+    assert_eq!(from_file_url("file:///bad%2"), None);
+    assert_eq!(from_file_url("file:///bad%zz"), None);
+    assert_eq!(from_file_url("file:///bad%+5"), None);
+    assert_eq!(from_file_url("file:"), None);
+    assert_eq!(from_file_url("http:///etc/hosts"), None);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_file_url() {
+    let roundtrip = |path: &str, url: &str| {
+        assert_eq!(Path::new(path).to_file_url().as_deref(), Some(url));
+        assert_eq!(from_file_url(url), Some(PathBuf::from(path)));
+    };
+    roundtrip(r"C:\", "file:///C:/");
+    roundtrip(r"C:\Users\me\my data", "file:///C:/Users/me/my%20data");
+    roundtrip(r"\\server\share\file.txt", "file://server/share/file.txt");
+    assert_eq!(Path::new(r"relative\path").to_file_url(), None);
+    assert_eq!(from_file_url("file:///no-drive"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_display_escaped() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let escaped = |bytes: &[u8]| {
+        Path::new(OsStr::from_bytes(bytes))
+            .display_escaped()
+            .to_string()
+    };
+    assert_eq!(escaped(b"/plain/path.txt"), "/plain/path.txt");
+    assert_eq!(
+        escaped("/caf\u{e9}/\u{1f600}".as_bytes()),
+        "/caf\u{e9}/\u{1f600}"
+    );
+    assert_eq!(escaped(b"/bad\xff\xfe"), r"/bad\xff\xfe");
+    assert_eq!(escaped(b"/cut\xc3"), r"/cut\xc3");
+    assert_eq!(escaped(b"/tab\tnul\0del\x7f"), r"/tab\tnul\0del\x7f");
+    // A literal backslash must be distinguishable from an escaped byte
+    assert_eq!(escaped(b"/back\\slash"), r"/back\\slash");
+    assert_ne!(escaped(br"/lit\xff"), escaped(b"/lit\xff"));
+    assert_eq!(escaped(br"/lit\xff"), r"/lit\\xff");
+}
+
+#[test]
+fn test_wsl() {
+    let roundtrip = |windows: &str, wsl: &str| {
+        assert_eq!(to_wsl(Path::new(windows)).as_deref(), Some(wsl));
+        assert_eq!(from_wsl(wsl), Some(PathBuf::from(windows)));
+    };
+    roundtrip(r"C:\", "/mnt/c");
+    roundtrip(r"C:\Users\me\data", "/mnt/c/Users/me/data");
+    roundtrip("D:\\my data\\caf\u{e9}.txt", "/mnt/d/my data/caf\u{e9}.txt");
+
+    assert_eq!(to_wsl(Path::new("c:")).as_deref(), Some("/mnt/c"));
+    assert_eq!(
+        to_wsl(Path::new(r"\\?\C:\Users\")).as_deref(),
+        Some("/mnt/c/Users")
+    );
+    assert_eq!(
+        to_wsl(Path::new("C:/Users//me")).as_deref(),
+        Some("/mnt/c/Users/me")
+    );
+    assert_eq!(to_wsl(Path::new("C:data")), None);
+    assert_eq!(to_wsl(Path::new(r"\\server\share\file")), None);
+    assert_eq!(to_wsl(Path::new(r"Users\me")), None);
+    assert_eq!(to_wsl(Path::new("/mnt/c")), None);
+
+    assert_eq!(from_wsl("/mnt/c/"), Some(PathBuf::from(r"C:\")));
+    assert_eq!(from_wsl("/mnt/c//Users/"), Some(PathBuf::from(r"C:\Users")));
+    assert_eq!(from_wsl("/mnt/cd/file"), None);
+    assert_eq!(from_wsl("/mnt/"), None);
+    assert_eq!(from_wsl("/home/me"), None);
+    assert_eq!(from_wsl(r"C:\Users"), None);
+}
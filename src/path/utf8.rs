@@ -0,0 +1,419 @@
+//! Extension traits for the UTF-8 paths of the [`camino`] crate.
+//!
+//! The traits mirror [`PathExt`](super::PathExt) and [`PathBufExt`](super::PathBufExt), but return UTF-8 types.
+
+use super::AncestorsWithin;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::iter::FusedIterator;
+
+/// This traits extends the available methods on [`Utf8Path`].
+///
+/// It mirrors [`PathExt`](super::PathExt) for UTF-8 paths.
+pub trait Utf8PathExt {
+    /// Iterator over all file extensions of a [`Utf8Path`].
+    ///
+    /// See [`PathExt::extensions`](super::PathExt::extensions) for details.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use camino::Utf8Path;
+    /// # use misc_utils::path::Utf8PathExt;
+    /// #
+    /// let p = Utf8Path::new("/home/user/projects/misc_utils/archive.tar.xz");
+    /// assert_eq!(p.extensions().collect::<Vec<_>>(), vec!["xz", "tar"]);
+    /// ```
+    fn extensions(&'_ self) -> Utf8PathExtensions<'_>;
+
+    /// Iterate over all prefixes of the path, starting with the shortest one.
+    ///
+    /// See [`PathExt::prefixes`](super::PathExt::prefixes) for details.
+    fn prefixes(&self) -> Utf8PathPrefixes<'_>;
+
+    /// Convert the path into an extended-length path on Windows.
+    ///
+    /// See [`PathExt::to_extended_length`](super::PathExt::to_extended_length) for details.
+    fn to_extended_length(&self) -> Utf8PathBuf;
+
+    /// Compute the relative path which leads from `base` to this path.
+    ///
+    /// See [`PathExt::relative_to`](super::PathExt::relative_to) for details.
+    fn relative_to(&self, base: &Utf8Path) -> Option<Utf8PathBuf>;
+
+    /// Remove a trailing compression extension, returning the name of the uncompressed file.
+    ///
+    /// See [`PathExt::without_compression_extension`](super::PathExt::without_compression_extension) for details.
+    fn without_compression_extension(&self) -> Utf8PathBuf;
+
+    /// Extract the portion of the file name before the first `.`.
+    ///
+    /// See [`PathExt::file_prefix`](super::PathExt::file_prefix) for details.
+    fn file_prefix(&self) -> Option<&str>;
+
+    /// Extract the portion of the file name after the first `.`.
+    ///
+    /// See [`PathExt::full_extension`](super::PathExt::full_extension) for details.
+    fn full_extension(&self) -> Option<&str>;
+
+    /// Normalize the path into the Unicode Normalization Form C (canonical composition).
+    ///
+    /// See [`PathExt::nfc`](super::PathExt::nfc) for details.
+    #[cfg(feature = "unicode-normalization")]
+    fn nfc(&self) -> Utf8PathBuf;
+
+    /// Normalize the path into the Unicode Normalization Form D (canonical decomposition).
+    ///
+    /// See [`PathExt::nfd`](super::PathExt::nfd) for details.
+    #[cfg(feature = "unicode-normalization")]
+    fn nfd(&self) -> Utf8PathBuf;
+
+    /// Check if the path refers to a hidden file or directory.
+    ///
+    /// See [`PathExt::is_hidden`](super::PathExt::is_hidden) for details.
+    fn is_hidden(&self) -> bool;
+
+    /// Iterate over the path and its ancestors, but stop at `root`.
+    ///
+    /// See [`PathExt::ancestors_within`](super::PathExt::ancestors_within) for details.
+    fn ancestors_within(&self, root: &Utf8Path) -> Utf8AncestorsWithin<'_>;
+
+    /// Convert an absolute path into a `file://` URL.
+    ///
+    /// See [`PathExt::to_file_url`](super::PathExt::to_file_url) for details.
+    fn to_file_url(&self) -> Option<String>;
+}
+
+impl Utf8PathExt for Utf8Path {
+    fn extensions(&'_ self) -> Utf8PathExtensions<'_> {
+        Utf8PathExtensions(self.full_extension())
+    }
+
+    fn prefixes(&self) -> Utf8PathPrefixes<'_> {
+        Utf8PathPrefixes(super::PathExt::prefixes(self.as_std_path()))
+    }
+
+    fn to_extended_length(&self) -> Utf8PathBuf {
+        let extended = super::PathExt::to_extended_length(self.as_std_path());
+        // The conversion only adds ASCII prefixes and removes components
+        Utf8PathBuf::from_path_buf(extended).unwrap_or_else(|_| self.to_path_buf())
+    }
+
+    fn relative_to(&self, base: &Utf8Path) -> Option<Utf8PathBuf> {
+        let relative = super::PathExt::relative_to(self.as_std_path(), base.as_std_path())?;
+        // The relative path only consists of components of UTF-8 paths and `..`
+        Utf8PathBuf::from_path_buf(relative).ok()
+    }
+
+    fn without_compression_extension(&self) -> Utf8PathBuf {
+        let path = super::PathExt::without_compression_extension(self.as_std_path());
+        // Only ASCII extensions are removed or replaced
+        Utf8PathBuf::from_path_buf(path).unwrap_or_else(|_| self.to_path_buf())
+    }
+
+    fn file_prefix(&self) -> Option<&str> {
+        let file_name = self.file_name()?;
+        Some(split_file_name(file_name).0)
+    }
+
+    fn full_extension(&self) -> Option<&str> {
+        split_file_name(self.file_name()?).1
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    fn nfc(&self) -> Utf8PathBuf {
+        use unicode_normalization::UnicodeNormalization;
+        Utf8PathBuf::from(self.as_str().nfc().collect::<String>())
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    fn nfd(&self) -> Utf8PathBuf {
+        use unicode_normalization::UnicodeNormalization;
+        Utf8PathBuf::from(self.as_str().nfd().collect::<String>())
+    }
+
+    fn is_hidden(&self) -> bool {
+        super::PathExt::is_hidden(self.as_std_path())
+    }
+
+    fn ancestors_within(&self, root: &Utf8Path) -> Utf8AncestorsWithin<'_> {
+        Utf8AncestorsWithin(super::PathExt::ancestors_within(
+            self.as_std_path(),
+            root.as_std_path(),
+        ))
+    }
+
+    fn to_file_url(&self) -> Option<String> {
+        super::PathExt::to_file_url(self.as_std_path())
+    }
+}
+
+/// Iterator over all prefixes of a path, starting with the shortest one.
+///
+/// This iterator is the UTF-8 version of [`PathPrefixes`](super::PathPrefixes).
+#[derive(Clone, Debug)]
+pub struct Utf8PathPrefixes<'a>(super::PathPrefixes<'a>);
+
+impl<'a> Iterator for Utf8PathPrefixes<'a> {
+    type Item = &'a Utf8Path;
+
+    fn next(&mut self) -> Option<&'a Utf8Path> {
+        // Prefixes only remove components of a UTF-8 path
+        self.0.next().and_then(Utf8Path::from_path)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Utf8PathPrefixes<'a> {
+    fn next_back(&mut self) -> Option<&'a Utf8Path> {
+        self.0.next_back().and_then(Utf8Path::from_path)
+    }
+}
+
+impl ExactSizeIterator for Utf8PathPrefixes<'_> {}
+
+impl FusedIterator for Utf8PathPrefixes<'_> {}
+
+/// Split a file name at the first `.`, ignoring a leading `.`.
+fn split_file_name(file_name: &str) -> (&str, Option<&str>) {
+    let start = usize::from(file_name.starts_with('.'));
+    match file_name[start..].find('.') {
+        Some(pos) => (
+            &file_name[..start + pos],
+            Some(&file_name[start + pos + 1..]),
+        ),
+        None => (file_name, None),
+    }
+}
+
+/// This traits extends the available methods on [`Utf8PathBuf`].
+///
+/// It mirrors [`PathBufExt`](super::PathBufExt) for UTF-8 paths.
+pub trait Utf8PathBufExt {
+    /// Appends `extension` to [`self.file_name`](Utf8Path::file_name).
+    ///
+    /// See [`PathBufExt::add_extension`](super::PathBufExt::add_extension) for details.
+    fn add_extension<S: AsRef<str>>(&mut self, extension: S) -> bool;
+
+    /// Replaces all extensions of [`self.file_name`](Utf8Path::file_name) with `extensions`.
+    ///
+    /// See [`PathBufExt::set_extensions`](super::PathBufExt::set_extensions) for details.
+    fn set_extensions<I>(&mut self, extensions: I) -> bool
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>;
+}
+
+impl Utf8PathBufExt for Utf8PathBuf {
+    fn add_extension<S: AsRef<str>>(&mut self, extension: S) -> bool {
+        let Some(file_name) = self.file_name() else {
+            return false;
+        };
+
+        let mut file_name = file_name.to_string();
+        if !extension.as_ref().is_empty() {
+            file_name.push('.');
+            file_name.push_str(extension.as_ref());
+        }
+        self.set_file_name(file_name);
+
+        true
+    }
+
+    fn set_extensions<I>(&mut self, extensions: I) -> bool
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let Some(prefix) = self.file_prefix() else {
+            return false;
+        };
+
+        let mut file_name = prefix.to_string();
+        for extension in extensions {
+            if !extension.as_ref().is_empty() {
+                file_name.push('.');
+                file_name.push_str(extension.as_ref());
+            }
+        }
+        self.set_file_name(file_name);
+
+        true
+    }
+}
+
+/// Iterator over all file extensions of a [`Utf8Path`].
+///
+/// This iterator is the UTF-8 version of [`PathExtensions`](super::PathExtensions).
+#[derive(Copy, Clone, Debug)]
+pub struct Utf8PathExtensions<'a>(Option<&'a str>);
+
+impl<'a> Iterator for Utf8PathExtensions<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let remaining = self.0?;
+        match remaining.rsplit_once('.') {
+            Some((rest, extension)) => {
+                self.0 = Some(rest);
+                Some(extension)
+            }
+            None => self.0.take(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+
+    fn last(mut self) -> Option<&'a str> {
+        self.next_back()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Utf8PathExtensions<'a> {
+    fn next_back(&mut self) -> Option<&'a str> {
+        let remaining = self.0?;
+        match remaining.split_once('.') {
+            Some((extension, rest)) => {
+                self.0 = Some(rest);
+                Some(extension)
+            }
+            None => self.0.take(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Utf8PathExtensions<'_> {
+    fn len(&self) -> usize {
+        self.0.map_or(0, |remaining| {
+            memchr::memchr_iter(b'.', remaining.as_bytes()).count() + 1
+        })
+    }
+}
+
+impl FusedIterator for Utf8PathExtensions<'_> {}
+
+/// Iterator over a path and its ancestors, which stops at a root directory.
+///
+/// This iterator is the UTF-8 version of [`AncestorsWithin`].
+#[derive(Clone, Debug)]
+pub struct Utf8AncestorsWithin<'a>(AncestorsWithin<'a>);
+
+impl<'a> Iterator for Utf8AncestorsWithin<'a> {
+    type Item = &'a Utf8Path;
+
+    fn next(&mut self) -> Option<&'a Utf8Path> {
+        // Ancestors only remove components of a UTF-8 path
+        self.0.next().and_then(Utf8Path::from_path)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl FusedIterator for Utf8AncestorsWithin<'_> {}
+
+#[test]
+fn test_utf8_path_extensions() {
+    let p = Utf8Path::new("/home/user/projects/misc_utils/This.File.has.many.extensions");
+    assert_eq!(
+        p.extensions().collect::<Vec<_>>(),
+        vec!["extensions", "many", "has", "File"]
+    );
+    let p = Utf8Path::new("/home/user/projects/misc_utils/.hidden");
+    assert_eq!(p.extensions().collect::<Vec<_>>(), Vec::<&str>::new());
+}
+
+#[test]
+fn test_utf8_prefixes() {
+    let p = Utf8Path::new("/srv/data/file.json");
+    assert_eq!(
+        p.prefixes().collect::<Vec<_>>(),
+        vec!["/", "/srv", "/srv/data", "/srv/data/file.json"]
+    );
+    assert_eq!(p.prefixes().next_back(), Some(p));
+}
+
+#[test]
+fn test_utf8_add_extension() {
+    let mut pb = Utf8PathBuf::from("some.file");
+    assert!(Utf8PathBufExt::add_extension(&mut pb, "a"));
+    assert_eq!(pb, "some.file.a");
+
+    let mut pb = Utf8PathBuf::from("/");
+    assert!(!Utf8PathBufExt::add_extension(&mut pb, "ext"));
+}
+
+#[test]
+fn test_utf8_relative_to() {
+    let p = Utf8Path::new("/home/user/data/file.txt");
+    assert_eq!(
+        p.relative_to(Utf8Path::new("/home/other")),
+        Some("../user/data/file.txt".into())
+    );
+    assert_eq!(p.relative_to(Utf8Path::new("other")), None);
+}
+
+#[test]
+fn test_utf8_without_compression_extension() {
+    let p = Utf8Path::new("data/file.jsonl.xz");
+    assert_eq!(p.without_compression_extension(), "data/file.jsonl");
+    let p = Utf8Path::new("backup.tgz");
+    assert_eq!(p.without_compression_extension(), "backup.tar");
+}
+
+#[test]
+fn test_utf8_file_prefix_full_extension() {
+    let p = Utf8Path::new("data/archive.tar.xz");
+    assert_eq!(p.file_prefix(), Some("archive"));
+    assert_eq!(p.full_extension(), Some("tar.xz"));
+    let p = Utf8Path::new("ännchen/.ä.txt");
+    assert_eq!(p.file_prefix(), Some(".ä"));
+    assert_eq!(p.full_extension(), Some("txt"));
+}
+
+#[test]
+fn test_utf8_set_extensions() {
+    let mut pb = Utf8PathBuf::from("/data/table.csv.gz");
+    assert!(pb.set_extensions(["jsonl", "zst"]));
+    assert_eq!(pb, "/data/table.jsonl.zst");
+
+    let mut pb = Utf8PathBuf::from("/");
+    assert!(!pb.set_extensions(["ext"]));
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn test_utf8_unicode_normalization() {
+    let nfc = Utf8Path::new("/data/caf\u{e9}.txt");
+    let nfd = Utf8Path::new("/data/cafe\u{301}.txt");
+    assert_eq!(nfd.nfc(), nfc);
+    assert_eq!(nfc.nfd(), nfd);
+}
+
+#[test]
+fn test_utf8_path_extensions_double_ended() {
+    let p = Utf8Path::new("/data/archive.tar.xz");
+    let mut extensions = p.extensions();
+    assert_eq!(extensions.len(), 2);
+    assert_eq!(extensions.next_back(), Some("tar"));
+    assert_eq!(extensions.next(), Some("xz"));
+    assert_eq!(extensions.next(), None);
+    assert_eq!(p.extensions().last(), Some("tar"));
+}
+
+#[test]
+fn test_utf8_ancestors_within() {
+    let p = Utf8Path::new("/srv/data/file.json");
+    assert_eq!(
+        p.ancestors_within(Utf8Path::new("/srv"))
+            .collect::<Vec<_>>(),
+        vec!["/srv/data/file.json", "/srv/data", "/srv"]
+    );
+    assert_eq!(p.ancestors_within(Utf8Path::new("/home")).count(), 0);
+}
@@ -0,0 +1,476 @@
+use num_traits::{ops::saturating::SaturatingAdd, CheckedAdd, ToPrimitive, Zero};
+#[cfg(feature = "rayon")]
+use rayon::iter::{FromParallelIterator, IntoParallelIterator, ParallelExtend, ParallelIterator};
+use std::{
+    fmt::{Display, Formatter, Result as FmtResult},
+    marker::PhantomData,
+};
+
+/// Helper type to calculate streaming statistics over values
+///
+/// The type tracks the number of values, their mean and variance, as well as the minimal and maximal value.
+/// Mean and variance are calculated with [Welford's algorithm][welford], which is numerically stable and needs constant memory.
+/// Multiple instances, e.g., calculated in parallel, can be combined with [`Stats::merge`].
+///
+/// The statistics are calculated as `f64`.
+/// A `NaN` value makes the mean and variance `NaN`, but is ignored for the minimum and maximum.
+/// If only `NaN` values exist, the minimum and maximum are `None`.
+///
+/// [welford]: https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Welford's_online_algorithm
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::Stats;
+/// let stats: Stats<u32> = [2, 4, 4, 4, 5, 5, 7, 9].into_iter().collect();
+/// assert_eq!(stats.count(), 8);
+/// assert_eq!(stats.mean(), Some(5.0));
+/// assert_eq!(stats.stddev(), Some(2.0));
+/// assert_eq!(stats.get_min(), Some(2));
+/// assert_eq!(stats.get_max(), Some(9));
+/// ```
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Stats<T> {
+    count: u64,
+    mean: f64,
+    /// Sum of squared differences from the mean
+    m2: f64,
+    min: Option<T>,
+    max: Option<T>,
+}
+
+impl<T> Stats<T>
+where
+    T: Copy + PartialOrd + ToPrimitive,
+{
+    /// Create a new instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a value to the statistics
+    pub fn update(&mut self, value: T) {
+        // Values which cannot be represented as f64 are not possible for the primitive number types
+        let x = value.to_f64().unwrap_or(f64::NAN);
+        self.count += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (x - self.mean);
+        self.update_min_max(value, value);
+    }
+
+    /// Combine the statistics of `other` into `self`
+    ///
+    /// Afterwards, `self` contains the statistics of the values of both instances.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * self.count as f64 * other.count as f64 / count as f64;
+        self.count = count;
+        if let (Some(min), Some(max)) = (other.min, other.max) {
+            self.update_min_max(min, max);
+        }
+    }
+
+    fn update_min_max(&mut self, min: T, max: T) {
+        // Do not let a NaN replace an existing value
+        let is_nan = |v: &T| v.partial_cmp(v).is_none();
+        if !is_nan(&min) && self.min.is_none_or(|v| min < v) {
+            self.min = Some(min);
+        }
+        if !is_nan(&max) && self.max.is_none_or(|v| max > v) {
+            self.max = Some(max);
+        }
+    }
+
+    /// Return the number of values
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Return the arithmetic mean of the values
+    ///
+    /// Returns `None` if no value exists.
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Return the population variance of the values
+    ///
+    /// Returns `None` if no value exists.
+    pub fn variance(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.m2 / self.count as f64)
+    }
+
+    /// Return the sample variance of the values, using Bessel's correction
+    ///
+    /// Returns `None` if less than two values exist.
+    pub fn sample_variance(&self) -> Option<f64> {
+        (self.count > 1).then(|| self.m2 / (self.count - 1) as f64)
+    }
+
+    /// Return the population standard deviation of the values
+    ///
+    /// Returns `None` if no value exists.
+    pub fn stddev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    /// Return the sample standard deviation of the values, using Bessel's correction
+    ///
+    /// Returns `None` if less than two values exist.
+    pub fn sample_stddev(&self) -> Option<f64> {
+        self.sample_variance().map(f64::sqrt)
+    }
+
+    /// Return the minimal value
+    ///
+    /// Returns `None` if no value exists.
+    pub fn get_min(&self) -> Option<T> {
+        self.min
+    }
+
+    /// Return the maximal value
+    ///
+    /// Returns `None` if no value exists.
+    pub fn get_max(&self) -> Option<T> {
+        self.max
+    }
+}
+
+impl<T> Default for Stats<T> {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl<T> FromIterator<T> for Stats<T>
+where
+    T: Copy + PartialOrd + ToPrimitive,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut stats = Self::default();
+        for value in iter {
+            stats.update(value);
+        }
+        stats
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> FromParallelIterator<T> for Stats<T>
+where
+    T: Copy + PartialOrd + Send + ToPrimitive,
+{
+    fn from_par_iter<I: IntoParallelIterator<Item = T>>(par_iter: I) -> Self {
+        par_iter
+            .into_par_iter()
+            .fold(Self::default, |mut stats, value| {
+                stats.update(value);
+                stats
+            })
+            .reduce(Self::default, |mut stats, other| {
+                stats.merge(&other);
+                stats
+            })
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T> ParallelExtend<T> for Stats<T>
+where
+    T: Copy + PartialOrd + Send + ToPrimitive,
+{
+    fn par_extend<I: IntoParallelIterator<Item = T>>(&mut self, par_iter: I) {
+        let other: Self = par_iter.into_par_iter().collect();
+        self.merge(&other);
+    }
+}
+
+/// Print the statistics as `count=.. mean=.. stddev=.. min=.. max=..`
+///
+/// The precision of the formatter is applied to the mean and standard deviation.
+impl<T> Display for Stats<T>
+where
+    T: Copy + Display + PartialOrd + ToPrimitive,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if self.count == 0 {
+            return write!(f, "<uninitialized>");
+        }
+        write!(f, "count={} mean=", self.count)?;
+        Display::fmt(&self.mean, f)?;
+        write!(f, " stddev=")?;
+        Display::fmt(&(self.m2 / self.count as f64).sqrt(), f)?;
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            write!(f, " min={} max={}", min, max)?;
+        }
+        Ok(())
+    }
+}
+
+/// Policy how [`Sum`] handles an overflow
+pub trait OverflowPolicy<T> {
+    /// Add two values, returning `None` if the sum overflows
+    fn add(a: &T, b: &T) -> Option<T>;
+}
+
+/// Overflow policy which marks a [`Sum`] as overflowed
+///
+/// After an overflow, [`Sum::get_sum`] returns `None`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Checked;
+
+impl<T> OverflowPolicy<T> for Checked
+where
+    T: CheckedAdd,
+{
+    fn add(a: &T, b: &T) -> Option<T> {
+        a.checked_add(b)
+    }
+}
+
+/// Overflow policy which saturates a [`Sum`] at the numeric bounds
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Saturating;
+
+impl<T> OverflowPolicy<T> for Saturating
+where
+    T: SaturatingAdd,
+{
+    fn add(a: &T, b: &T) -> Option<T> {
+        Some(a.saturating_add(b))
+    }
+}
+
+/// Helper type to calculate the sum of integer values
+///
+/// The policy `P` determines the behavior on overflow.
+/// By default, the [`Checked`] policy is used, which marks the sum as overflowed.
+/// The [`Saturating`] policy saturates the sum at the numeric bounds instead.
+/// For floating point values use [`Mean`] or [`Stats`].
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::{Saturating, Sum};
+/// let sum: Sum<u8> = [100, 100].into_iter().collect();
+/// assert_eq!(sum.get_sum(), Some(200));
+///
+/// let mut sum = Sum::<u8>::new();
+/// sum.update(200);
+/// sum.update(100);
+/// assert_eq!(sum.get_sum(), None);
+///
+/// let mut sum = Sum::<u8, Saturating>::new();
+/// sum.update(200);
+/// sum.update(100);
+/// assert_eq!(sum.get_sum(), Some(255));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Sum<T, P = Checked> {
+    /// Current sum or `None` after an overflow
+    value: Option<T>,
+    policy: PhantomData<P>,
+}
+
+impl<T, P> Sum<T, P>
+where
+    T: Zero,
+    P: OverflowPolicy<T>,
+{
+    /// Create a new instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new instance with an initial value to add to
+    pub fn with_initial(initial: T) -> Self {
+        Self {
+            value: Some(initial),
+            policy: PhantomData,
+        }
+    }
+
+    /// Return the sum of all values
+    ///
+    /// Returns `None` if the sum overflowed.
+    /// Returns zero if neither an initial value exists nor `update` was called.
+    pub fn get_sum(&self) -> Option<T>
+    where
+        T: Copy,
+    {
+        self.value
+    }
+
+    /// Return `true` if the sum overflowed
+    pub fn is_overflowed(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Add a value to the sum
+    ///
+    /// This method can be called with type `T` or type `Sum<T, P>`.
+    pub fn update<V: Into<Self>>(&mut self, value: V) {
+        self.value = match (&self.value, value.into().value) {
+            (Some(a), Some(b)) => P::add(a, &b),
+            _ => None,
+        };
+    }
+}
+
+impl<T, P> Default for Sum<T, P>
+where
+    T: Zero,
+{
+    fn default() -> Self {
+        Self {
+            value: Some(T::zero()),
+            policy: PhantomData,
+        }
+    }
+}
+
+impl<T, P> From<T> for Sum<T, P>
+where
+    T: Zero,
+    P: OverflowPolicy<T>,
+{
+    fn from(value: T) -> Self {
+        Self::with_initial(value)
+    }
+}
+
+impl<T, P> FromIterator<T> for Sum<T, P>
+where
+    T: Zero,
+    P: OverflowPolicy<T>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut sum = Self::default();
+        for value in iter {
+            sum.update(value);
+        }
+        sum
+    }
+}
+
+impl<T, P> Display for Sum<T, P>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(v) = &self.value {
+            write!(f, "{}", v)
+        } else {
+            write!(f, "<overflow>")
+        }
+    }
+}
+
+/// Helper type to calculate the arithmetic mean of values
+///
+/// The mean is updated incrementally as `f64`, such that it does not overflow for large sums.
+/// Use [`Stats`] to calculate the variance, too.
+///
+/// # Example
+///
+/// ```rust
+/// # use misc_utils::Mean;
+/// let mean: Mean = [u64::MAX, u64::MAX].into_iter().collect();
+/// assert_eq!(mean.get_mean(), Some(u64::MAX as f64));
+/// ```
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Mean {
+    count: u64,
+    mean: f64,
+}
+
+impl Mean {
+    /// Create a new instance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new instance with an initial value
+    pub fn with_initial<T: ToPrimitive>(initial: T) -> Self {
+        let mut mean = Self::default();
+        mean.update(initial);
+        mean
+    }
+
+    /// Return the mean of all values
+    ///
+    /// Returns `None` if neither an initial value exists nor `update` was called.
+    pub fn get_mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Return the number of values
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Add a value to the mean
+    pub fn update<T: ToPrimitive>(&mut self, value: T) {
+        // Values which cannot be represented as f64 are not possible for the primitive number types
+        let x = value.to_f64().unwrap_or(f64::NAN);
+        self.count += 1;
+        self.mean += (x - self.mean) / self.count as f64;
+    }
+
+    /// Combine the mean of `other` into `self`
+    ///
+    /// Afterwards, `self` contains the mean of the values of both instances.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        self.mean += (other.mean - self.mean) * other.count as f64 / count as f64;
+        self.count = count;
+    }
+}
+
+impl From<f64> for Mean {
+    fn from(value: f64) -> Self {
+        Self::with_initial(value)
+    }
+}
+
+impl<T> FromIterator<T> for Mean
+where
+    T: ToPrimitive,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut mean = Self::default();
+        for value in iter {
+            mean.update(value);
+        }
+        mean
+    }
+}
+
+/// The precision of the formatter is applied to the mean.
+impl Display for Mean {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self.get_mean() {
+            Some(mean) => Display::fmt(&mean, f),
+            None => write!(f, "<uninitialized>"),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "cbor")]
+
+use misc_utils::error::MtCborError;
+use misc_utils::fs::{parse_cbor_seq, CborSeqWriter};
+use pretty_assertions::assert_eq;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+struct Reading {
+    sensor: String,
+    value: f64,
+}
+
+#[test]
+fn test_cbor_seq_roundtrip() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let mut names = vec!["seq.cbor"];
+    if cfg!(feature = "file-gz") {
+        names.push("seq.cbor.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        names.push("seq.cbor.xz");
+    }
+    for name in names {
+        let path = tmpdir.path().join(name);
+        let readings: Vec<_> = (0..3000)
+            .map(|i| Reading {
+                sensor: format!("s{}", i % 7),
+                value: f64::from(i) / 4.,
+            })
+            .collect();
+
+        let mut writer = CborSeqWriter::create(&path)?;
+        for reading in &readings {
+            writer.write(reading)?;
+        }
+        writer.finish()?;
+
+        let read = parse_cbor_seq::<_, Reading>(&path).collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(read, readings);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_cbor_seq_errors() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".cbor").tempfile()?;
+    let mut writer = CborSeqWriter::create(tmpfile.path())?;
+    writer.write(&1u64)?;
+    writer.write("text")?;
+    writer.write(&3u64)?;
+    writer.finish()?;
+    // Append a truncated item: a text string of length 5 with only one byte
+    let mut content = std::fs::read(tmpfile.path())?;
+    content.extend_from_slice(&[0x65, b'a']);
+    std::fs::write(tmpfile.path(), content)?;
+
+    let mut iter = parse_cbor_seq::<_, u64>(tmpfile.path());
+    assert_eq!(iter.next().unwrap()?, 1);
+    assert!(matches!(
+        iter.next(),
+        Some(Err(MtCborError::ParsingError { .. }))
+    ));
+    assert_eq!(iter.next().unwrap()?, 3);
+    assert!(matches!(
+        iter.next(),
+        Some(Err(MtCborError::DecodingError { .. }))
+    ));
+    assert!(matches!(iter.next(), Some(Err(MtCborError::NotCompleted))));
+    Ok(())
+}
+
+#[test]
+fn test_cbor_seq_writer_failed_value() -> Result<(), anyhow::Error> {
+    /// Fails after serializing the first element of a sequence
+    struct Failing;
+
+    impl Serialize for Failing {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::{Error, SerializeSeq};
+
+            let mut seq = serializer.serialize_seq(Some(2))?;
+            seq.serialize_element("partial")?;
+            Err(S::Error::custom("failed"))
+        }
+    }
+
+    let tmpfile = tempfile::Builder::new().suffix(".cbor").tempfile()?;
+    let mut writer = CborSeqWriter::create(tmpfile.path())?;
+    writer.write(&1u64)?;
+    assert!(matches!(
+        writer.write(&Failing),
+        Err(misc_utils::error::Error::CborError { .. })
+    ));
+    writer.write(&2u64)?;
+    writer.finish()?;
+
+    let values = parse_cbor_seq::<_, u64>(tmpfile.path()).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, vec![1, 2]);
+    Ok(())
+}
@@ -0,0 +1,79 @@
+#![cfg(feature = "csv")]
+
+use misc_utils::error::MtCsvError;
+use misc_utils::fs::{file_write, parse_csv_multi_threaded, CsvOptions};
+use pretty_assertions::assert_eq;
+use serde::Deserialize;
+use std::io::Write;
+
+#[derive(Debug, Eq, PartialEq, Deserialize)]
+struct Row {
+    name: String,
+    count: u64,
+}
+
+#[test]
+fn test_read_csv() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".csv").tempfile()?;
+    std::fs::write(
+        tmpfile.path(),
+        "count,name\n1,a\n2,\"multi\nline\"\nbroken,c\n4,d\n",
+    )?;
+
+    let mut iter =
+        parse_csv_multi_threaded::<_, Row>(tmpfile.path(), CsvOptions::new().batch_size(2));
+    assert_eq!(
+        iter.next().unwrap()?,
+        Row {
+            name: "a".into(),
+            count: 1
+        }
+    );
+    assert_eq!(
+        iter.next().unwrap()?,
+        Row {
+            name: "multi\nline".into(),
+            count: 2
+        }
+    );
+    assert!(matches!(
+        iter.next(),
+        Some(Err(MtCsvError::ParsingError { .. }))
+    ));
+    assert_eq!(
+        iter.next().unwrap()?,
+        Row {
+            name: "d".into(),
+            count: 4
+        }
+    );
+    assert!(iter.next().is_none());
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "file-gz"), ignore)]
+#[test]
+fn test_read_compressed_tsv() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".tsv.gz").tempfile()?;
+    let mut writer = file_write(tmpfile.path()).truncate()?;
+    for i in 0..1000 {
+        writeln!(writer, "{i}\t{}", i * 2)?;
+    }
+    writer.finish()?;
+
+    let values = parse_csv_multi_threaded::<_, (u64, u64)>(
+        tmpfile.path(),
+        CsvOptions::new().delimiter(b'\t').has_headers(false),
+    )
+    .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, (0..1000).map(|i| (i, i * 2)).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn test_missing_file() {
+    let mut iter =
+        parse_csv_multi_threaded::<_, Row>("./tests/data/does-not-exist.csv", &CsvOptions::new());
+    assert!(matches!(iter.next(), Some(Err(MtCsvError::IoError { .. }))));
+    assert!(matches!(iter.next(), Some(Err(MtCsvError::NotCompleted))));
+}
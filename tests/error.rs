@@ -0,0 +1,249 @@
+use misc_utils::error::{Error, Operation, Retryable};
+use std::io;
+
+fn file_io(kind: io::ErrorKind) -> Error {
+    Error::FileIo {
+        file: "data.txt".into(),
+        operation: Operation::Read,
+        source: io::Error::from(kind),
+    }
+}
+
+#[test]
+fn test_is_transient() {
+    for kind in [
+        io::ErrorKind::Interrupted,
+        io::ErrorKind::WouldBlock,
+        io::ErrorKind::TimedOut,
+        io::ErrorKind::ResourceBusy,
+        io::ErrorKind::Deadlock,
+    ] {
+        assert!(file_io(kind).is_transient(), "{kind:?} is transient");
+        assert!(io::Error::from(kind).is_transient());
+    }
+    for kind in [
+        io::ErrorKind::NotFound,
+        io::ErrorKind::PermissionDenied,
+        io::ErrorKind::InvalidData,
+        io::ErrorKind::UnexpectedEof,
+    ] {
+        assert!(!file_io(kind).is_transient(), "{kind:?} is permanent");
+    }
+
+    assert!(!Error::NotAFileError { path: "dir".into() }.is_transient());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_is_transient_os_errors() {
+    assert!(io::Error::from_raw_os_error(libc::EINTR).is_transient());
+    assert!(io::Error::from_raw_os_error(libc::EAGAIN).is_transient());
+    assert!(io::Error::from_raw_os_error(libc::ETIMEDOUT).is_transient());
+    assert!(io::Error::from_raw_os_error(libc::EBUSY).is_transient());
+    assert!(!io::Error::from_raw_os_error(libc::ENOENT).is_transient());
+}
+
+#[cfg(feature = "jsonl")]
+#[test]
+fn test_is_transient_jsonl() {
+    use misc_utils::error::MtJsonlError;
+
+    let err = MtJsonlError::from(file_io(io::ErrorKind::Interrupted));
+    assert!(Retryable::is_transient(&err));
+    assert!(!Retryable::is_transient(&MtJsonlError::NotCompleted));
+}
+
+#[cfg(all(feature = "serde", feature = "serde_json"))]
+#[test]
+fn test_serialize_error() {
+    let err = Error::FileIo {
+        file: "data.txt".into(),
+        operation: Operation::Open,
+        source: io::Error::from_raw_os_error(2),
+    };
+    let value = serde_json::to_value(&err).unwrap();
+    assert_eq!("FileIo", value["kind"]);
+    assert_eq!("data.txt", value["path"]);
+    assert_eq!(2, value["os_error"]);
+    let message = value["message"].as_str().unwrap();
+    assert!(
+        message.starts_with("Could not open data.txt: "),
+        "{message}"
+    );
+
+    let err = Error::NotAFileError { path: "dir".into() };
+    assert_eq!(
+        serde_json::json!({
+            "kind": "NotAFileError",
+            "path": "dir",
+            "os_error": null,
+            "message": "dir is not a file",
+        }),
+        serde_json::to_value(&err).unwrap()
+    );
+}
+
+#[cfg(feature = "jsonl")]
+#[test]
+fn test_serialize_mt_jsonl_error() {
+    use misc_utils::error::MtJsonlError;
+
+    let err = MtJsonlError::from(Error::NotAFileError { path: "dir".into() });
+    assert_eq!(
+        serde_json::json!({
+            "kind": "NotAFileError",
+            "path": "dir",
+            "os_error": null,
+            "message": "dir is not a file",
+        }),
+        serde_json::to_value(&err).unwrap()
+    );
+    assert_eq!(
+        serde_json::json!({
+            "kind": "NotCompleted",
+            "path": null,
+            "os_error": null,
+            "message": "Reading the file has failed and not all entries could be read.",
+        }),
+        serde_json::to_value(MtJsonlError::NotCompleted).unwrap()
+    );
+}
+
+#[cfg(feature = "miette")]
+#[test]
+fn test_miette_diagnostic() {
+    use miette::Diagnostic;
+
+    let err = Error::CompressionNotEnabled {
+        file: "data.bz2".into(),
+        technique: "bz2",
+    };
+    assert_eq!(
+        Some("misc_utils::CompressionNotEnabled".to_string()),
+        err.code().map(|code| code.to_string())
+    );
+    assert_eq!(
+        Some("Enable the `file-bz2` feature of misc_utils to support this file type.".to_string()),
+        err.help().map(|help| help.to_string())
+    );
+
+    let err = file_io(io::ErrorKind::NotFound);
+    assert_eq!(
+        Some("Check that the file exists.".to_string()),
+        err.help().map(|help| help.to_string())
+    );
+    assert!(file_io(io::ErrorKind::InvalidData).help().is_none());
+
+    let err = misc_utils::byteascii::unbyteascii(r"\q").unwrap_err();
+    assert_eq!(
+        Some("misc_utils::byteascii::DecodeError".to_string()),
+        err.code().map(|code| code.to_string())
+    );
+}
+
+#[cfg(all(feature = "miette", feature = "jsonl"))]
+#[test]
+fn test_miette_diagnostic_jsonl() {
+    use miette::Diagnostic;
+    use misc_utils::error::MtJsonlError;
+
+    let tmpfile = tempfile::Builder::new()
+        .suffix(".jsonl")
+        .tempfile()
+        .unwrap();
+    std::fs::write(tmpfile.path(), "[1]\n[2]\n[1, 2 3]\n").unwrap();
+    let err = misc_utils::fs::parse_jsonl_multi_threaded::<_, Vec<u32>>(tmpfile.path(), 10)
+        .find_map(Result::err)
+        .unwrap();
+    assert_eq!(
+        Some("misc_utils::jsonl::ParsingError".to_string()),
+        err.code().map(|code| code.to_string())
+    );
+    assert_eq!(
+        Some("Fix the JSON value in line 3 at column 7.".to_string()),
+        err.help().map(|help| help.to_string())
+    );
+    let labels: Vec<_> = err.labels().unwrap().collect();
+    assert_eq!(1, labels.len());
+    assert_eq!(6, labels[0].offset());
+
+    let mut rendered = String::new();
+    miette::NarratableReportHandler::new()
+        .render_report(&mut rendered, &err)
+        .unwrap();
+    assert!(rendered.contains("[1, 2 3]"), "{rendered}");
+    assert!(rendered.contains("invalid JSON"), "{rendered}");
+
+    let err = MtJsonlError::from(file_io(io::ErrorKind::NotFound));
+    assert_eq!(
+        Some("misc_utils::FileIo".to_string()),
+        err.code().map(|code| code.to_string())
+    );
+}
+
+#[test]
+fn test_into_io_error() {
+    let err = io::Error::from(file_io(io::ErrorKind::NotFound));
+    assert_eq!(io::ErrorKind::NotFound, err.kind());
+    assert_eq!("Could not read data.txt", err.to_string());
+    let inner = err.into_inner().unwrap().downcast::<Error>().unwrap();
+    assert!(matches!(*inner, Error::FileIo { .. }));
+
+    let err = io::Error::from(Error::CompressionNotEnabled {
+        file: "data.bz2".into(),
+        technique: "bz2",
+    });
+    assert_eq!(io::ErrorKind::Unsupported, err.kind());
+    let err = io::Error::from(Error::FormatMismatch {
+        file: "data.gz".into(),
+        expected: "gz",
+        detected: "xz",
+    });
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    let err = io::Error::from(Error::NotAFileError { path: "/".into() });
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+}
+
+#[cfg(feature = "serde_json")]
+#[test]
+fn test_into_io_error_json() {
+    let err = io::Error::from(Error::JsonError {
+        file: "data.json".into(),
+        source: serde_json::from_str::<Vec<u32>>("[1").unwrap_err(),
+    });
+    assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+    let err = io::Error::from(Error::JsonError {
+        file: "data.json".into(),
+        source: serde_json::from_str::<u32>("\"a\"").unwrap_err(),
+    });
+    assert_eq!(io::ErrorKind::InvalidData, err.kind());
+}
+
+#[test]
+fn test_file_io_operation() {
+    let dir = tempfile::tempdir().unwrap();
+    let missing = dir.path().join("missing.txt");
+    let err = misc_utils::fs::read(&missing).unwrap_err();
+    match err {
+        Error::FileIo {
+            operation: Operation::Metadata | Operation::Open,
+            ref source,
+            ..
+        } => assert_eq!(io::ErrorKind::NotFound, source.kind()),
+        _ => panic!("unexpected error: {err:?}"),
+    }
+
+    let file = dir.path().join("missing/file.txt");
+    let err = misc_utils::fs::write(&file, "").unwrap_err();
+    assert!(matches!(
+        err,
+        Error::FileIo {
+            operation: Operation::Open,
+            ..
+        }
+    ));
+    assert_eq!(
+        format!("Could not open {}", file.display()),
+        err.to_string()
+    );
+}
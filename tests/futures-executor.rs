@@ -0,0 +1,38 @@
+#![cfg(feature = "futures-fs")]
+
+//! The blocking executor is global state, so it is tested in its own test binary.
+
+use misc_utils::futures_fs;
+use pretty_assertions::assert_eq;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static TASKS: AtomicUsize = AtomicUsize::new(0);
+
+#[test]
+fn test_set_blocking_executor() -> Result<(), anyhow::Error> {
+    futures_fs::set_blocking_executor(|task: Box<dyn FnOnce() + Send>| {
+        TASKS.fetch_add(1, Ordering::SeqCst);
+        std::thread::spawn(task);
+    });
+
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let path = tmpdir.path().join("executor.jsonl");
+    futures_lite::future::block_on(async {
+        futures_fs::write(&path, "1\n2\n3\n").await?;
+        assert_eq!(futures_fs::read_to_string(&path).await?, "1\n2\n3\n");
+        Ok::<_, anyhow::Error>(())
+    })?;
+    assert_eq!(TASKS.load(Ordering::SeqCst), 2);
+
+    #[cfg(feature = "jsonl")]
+    {
+        use futures_lite::StreamExt;
+
+        let values = futures_lite::future::block_on(
+            futures_fs::parse_jsonl_stream::<u64>(&path).try_collect::<_, _, Vec<_>>(),
+        )?;
+        assert_eq!(values, vec![1, 2, 3]);
+        assert_eq!(TASKS.load(Ordering::SeqCst), 3);
+    }
+    Ok(())
+}
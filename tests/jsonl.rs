@@ -1,6 +1,11 @@
 #![cfg(feature = "jsonl")]
 
-use misc_utils::fs::parse_jsonl_multi_threaded;
+use misc_utils::error::{Error, MtJsonlError};
+use misc_utils::fs::{
+    merge_jsonl_sorted, parse_jsonl_multi_threaded, parse_jsonl_multi_threaded_many,
+    parse_jsonl_multi_worker, sample_jsonl, split_jsonl, JsonlAppender, JsonlEvent, JsonlWriter,
+    MtJsonlBuilder, ShardSize,
+};
 use serde::Deserialize;
 
 #[derive(Debug, Eq, PartialEq, Deserialize)]
@@ -87,3 +92,794 @@ fn test_read_broken_json() {
     // assert finished completely
     assert!(iter.next().is_none())
 }
+
+#[test]
+fn test_multi_worker_keeps_order() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+    std::fs::write(tmpfile.path(), content)?;
+
+    let values =
+        parse_jsonl_multi_worker::<_, u64>(tmpfile.path(), 7, 4).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, (0..10_000).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn test_multi_worker_missing_file() {
+    let mut iter = parse_jsonl_multi_worker::<_, u64>("./tests/data/does-not-exist.jsonl", 7, 4);
+    assert!(matches!(
+        iter.next(),
+        Some(Err(MtJsonlError::IoError { .. }))
+    ));
+    assert!(matches!(iter.next(), Some(Err(MtJsonlError::NotCompleted))));
+}
+
+#[test]
+fn test_builder() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..1000).map(|i| format!("{i}\n")).collect();
+    std::fs::write(tmpfile.path(), content)?;
+
+    let values = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(3)
+        .buffer_capacity(16)
+        .channel_capacity(1)
+        .threads(3)
+        .parse::<u64>()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, (0..1000).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn test_skip_errors() -> Result<(), anyhow::Error> {
+    use std::sync::{Arc, Mutex};
+
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    std::fs::write(tmpfile.path(), "1\n2\nbroken\n\n3\n{\"a\": 1}\n4\n")?;
+
+    let skipped = Arc::new(Mutex::new(Vec::new()));
+    let skipped_clone = Arc::clone(&skipped);
+    let values = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(2)
+        .threads(2)
+        .skip_errors(move |line, _err| skipped_clone.lock().unwrap().push(line.to_string()))
+        .parse::<u64>()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, vec![1, 2, 3, 4]);
+    let mut skipped = skipped.lock().unwrap().clone();
+    skipped.sort();
+    assert_eq!(skipped, vec!["broken", "{\"a\": 1}"]);
+    Ok(())
+}
+
+#[test]
+fn test_positions() -> Result<(), anyhow::Error> {
+    use misc_utils::fs::Positioned;
+
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    std::fs::write(tmpfile.path(), "1\n22\n\n333\r\n4\n")?;
+
+    let values = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(2)
+        .threads(2)
+        .parse::<u64>()
+        .with_positions()
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected = vec![
+        Positioned {
+            line: 1,
+            offset: 0,
+            value: 1,
+        },
+        Positioned {
+            line: 2,
+            offset: 2,
+            value: 22,
+        },
+        Positioned {
+            line: 4,
+            offset: 6,
+            value: 333,
+        },
+        Positioned {
+            line: 5,
+            offset: 11,
+            value: 4,
+        },
+    ];
+    assert_eq!(values, expected);
+
+    // The same positions are reported if every line is parsed separately
+    let values = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(2)
+        .skip_errors(|_, _| {})
+        .parse::<u64>()
+        .with_positions()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, expected);
+    Ok(())
+}
+
+#[test]
+fn test_parsing_error_position() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    std::fs::write(tmpfile.path(), "1\n2\n3\n4\nbroken\n5\n")?;
+
+    let mut iter = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(3)
+        .parse::<u64>();
+    for expected in 1..=4 {
+        assert_eq!(iter.next().unwrap()?, expected);
+    }
+    match iter.next() {
+        Some(Err(err @ MtJsonlError::ParsingError { line, offset, .. })) => {
+            assert_eq!(line, 5);
+            assert_eq!(offset, 8);
+            assert!(err.to_string().contains("line 5 at byte offset 8"));
+        }
+        other => panic!("Expected a ParsingError, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parsing_error_excerpt() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let long = format!("[{}x]\n", "1,".repeat(5_000));
+    std::fs::write(tmpfile.path(), format!("[1]\n{long}"))?;
+
+    let err = parse_jsonl_multi_threaded::<_, Vec<u64>>(tmpfile.path(), 10)
+        .find_map(Result::err)
+        .unwrap();
+    match err {
+        MtJsonlError::ParsingError {
+            line: 2,
+            excerpt,
+            position: Some(position),
+            ..
+        } => {
+            // Only a part of the line around the error is kept
+            assert!(excerpt.len() <= 512, "{}", excerpt.len());
+            assert!(excerpt.ends_with("1,x]"));
+            assert_eq!(&excerpt[position..], "x]");
+        }
+        other => panic!("Expected a ParsingError, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse_raw() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    std::fs::write(tmpfile.path(), "1\n 2 \nbroken\n\n3\r\n")?;
+
+    let mut iter = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(2)
+        .threads(2)
+        .parse_raw::<u64>();
+    let (raw, value) = iter.next().unwrap();
+    assert_eq!((raw.as_str(), value?), ("1", 1));
+    let (raw, value) = iter.next().unwrap();
+    assert_eq!((raw.as_str(), value?), (" 2 ", 2));
+    match iter.next().unwrap() {
+        (raw, Err(MtJsonlError::ParsingError { line, .. })) => {
+            assert_eq!(raw, "broken");
+            assert_eq!(line, 3);
+        }
+        other => panic!("Expected a ParsingError, got {other:?}"),
+    }
+    let (raw, value) = iter.next().unwrap();
+    assert_eq!((raw.as_str(), value?), ("3", 3));
+    assert!(iter.next().is_none());
+    Ok(())
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parse_jsonl_parallel() -> Result<(), anyhow::Error> {
+    use misc_utils::fs::parse_jsonl_parallel;
+    use rayon::iter::ParallelIterator;
+
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..10_000).map(|i| format!("{i}\n")).collect();
+    std::fs::write(tmpfile.path(), content)?;
+
+    let mut values =
+        parse_jsonl_parallel::<_, u64>(tmpfile.path(), 7).collect::<Result<Vec<_>, _>>()?;
+    values.sort_unstable();
+    assert_eq!(values, (0..10_000).collect::<Vec<_>>());
+
+    let errors: Vec<_> =
+        parse_jsonl_parallel::<_, u64>("./tests/data/does-not-exist.jsonl", 7).collect();
+    assert!(matches!(&*errors, [Err(MtJsonlError::IoError { .. })]));
+    Ok(())
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_parse_jsonl_stream() -> Result<(), anyhow::Error> {
+    use futures_core::Stream;
+    use misc_utils::async_fs::parse_jsonl_stream;
+    use std::pin::Pin;
+
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..5000).map(|i| format!("{i}\n")).collect();
+    std::fs::write(tmpfile.path(), content)?;
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let values = runtime.block_on(async {
+        let mut stream = parse_jsonl_stream::<u64>(tmpfile.path());
+        let mut values = Vec::new();
+        while let Some(value) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await
+        {
+            values.push(value?);
+        }
+        Ok::<_, MtJsonlError>(values)
+    })?;
+    assert_eq!(values, (0..5000).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[cfg(feature = "futures-fs")]
+#[test]
+fn test_futures_parse_jsonl_stream() -> Result<(), anyhow::Error> {
+    use futures_lite::StreamExt;
+    use misc_utils::futures_fs::parse_jsonl_stream;
+
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..5000).map(|i| format!("{i}\n")).collect();
+    std::fs::write(tmpfile.path(), content)?;
+
+    let values = futures_lite::future::block_on(
+        parse_jsonl_stream::<u64>(tmpfile.path()).try_collect::<_, _, Vec<_>>(),
+    )?;
+    assert_eq!(values, (0..5000).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn test_batch_bytes() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    // Mix very long and short lines
+    let content: String = (0..500)
+        .map(|i| {
+            if i % 50 == 0 {
+                format!("\"{}\"\n", "x".repeat(100_000))
+            } else {
+                format!("\"{i}\"\n")
+            }
+        })
+        .collect();
+    std::fs::write(tmpfile.path(), &content)?;
+
+    let values = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(u32::MAX)
+        .batch_bytes(4096)
+        .threads(2)
+        .parse::<String>()
+        .collect::<Result<Vec<_>, _>>()?;
+    let expected: Vec<_> = content
+        .lines()
+        .map(|line| line.trim_matches('"').to_string())
+        .collect();
+    assert_eq!(values, expected);
+    Ok(())
+}
+
+#[test]
+fn test_resume_from() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let content: String = (0..1000).map(|i| format!("{i}\n")).collect();
+    let mut names = vec!["data.jsonl"];
+    if cfg!(feature = "file-gz") {
+        names.push("data.jsonl.gz");
+    }
+    for name in names {
+        let path = tmpdir.path().join(name);
+        misc_utils::fs::write(&path, &content)?;
+
+        // Simulate a crash after processing 500 values
+        let checkpoint = MtJsonlBuilder::new(path.clone())
+            .batch_size(7)
+            .parse::<u64>()
+            .with_positions()
+            .nth(500)
+            .unwrap()?;
+        assert_eq!(checkpoint.value, 500);
+
+        let resumed = MtJsonlBuilder::new(path)
+            .batch_size(7)
+            .resume_from(checkpoint.line, checkpoint.offset)
+            .parse::<u64>()
+            .with_positions()
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(resumed.len(), 500);
+        assert_eq!(resumed[0], checkpoint);
+        assert_eq!(resumed[499].value, 999);
+        assert_eq!(resumed[499].line, 1000);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_filter_lines() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..1000)
+        .map(|i| {
+            if i % 10 == 0 {
+                format!("{{\"tag\": \"keep\", \"int\": {i}}}\n")
+            } else {
+                // Lines which are filtered out are never parsed
+                "not json\n".to_string()
+            }
+        })
+        .collect();
+    std::fs::write(tmpfile.path(), content)?;
+
+    #[derive(Debug, Deserialize)]
+    struct Tagged {
+        int: u64,
+    }
+
+    let values = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(7)
+        .threads(2)
+        .filter_lines(|line| line.contains("\"keep\""))
+        .parse::<Tagged>()
+        .with_positions()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values.len(), 100);
+    for (i, value) in values.iter().enumerate() {
+        let i = i as u64 * 10;
+        assert_eq!(value.value.int, i);
+        assert_eq!(value.line, i + 1);
+    }
+    // The offset points to the start of the line
+    assert_eq!(values[1].offset, 26 + 9 * 9);
+    Ok(())
+}
+
+#[test]
+fn test_parse_jsonl_single_threaded() -> Result<(), anyhow::Error> {
+    use misc_utils::fs::parse_jsonl;
+
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..5000).map(|i| format!("{i}\n")).collect();
+    std::fs::write(tmpfile.path(), content)?;
+    let values = parse_jsonl::<_, u64>(tmpfile.path()).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, (0..5000).collect::<Vec<_>>());
+
+    let mut iter = parse_jsonl::<_, Deserializeable>("./tests/data/jsonl-broken-json.txt");
+    assert!(iter.next().unwrap().is_ok());
+    assert!(matches!(
+        iter.next(),
+        Some(Err(MtJsonlError::ParsingError { line: 2, .. }))
+    ));
+    assert!(iter.next().is_none());
+
+    let mut iter = parse_jsonl::<_, u64>("./tests/data/does-not-exist.jsonl");
+    assert!(matches!(
+        iter.next(),
+        Some(Err(MtJsonlError::IoError { .. }))
+    ));
+    assert!(iter.next().is_none());
+    Ok(())
+}
+
+#[test]
+fn test_abort() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..100_000).map(|i| format!("{i}\n")).collect();
+    std::fs::write(tmpfile.path(), content)?;
+
+    let mut iter = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(10)
+        .threads(3)
+        .parse::<u64>();
+    assert_eq!(iter.next().unwrap()?, 0);
+    assert_eq!(iter.next().unwrap()?, 1);
+    iter.abort();
+    assert!(iter.next().is_none());
+
+    // Dropping the iterator early must not block
+    let mut iter = parse_jsonl_multi_worker::<_, u64>(tmpfile.path(), 10, 3);
+    assert_eq!(iter.next().unwrap()?, 0);
+    drop(iter);
+    Ok(())
+}
+
+#[test]
+fn test_progress() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    std::fs::write(tmpfile.path(), "1\n2\nbroken\n3\nalso broken\n4\n")?;
+
+    let mut iter = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(2)
+        .threads(2)
+        .skip_errors(|_, _| {})
+        .parse::<u64>();
+    let progress = iter.progress();
+    let values = iter.by_ref().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, vec![1, 2, 3, 4]);
+    assert_eq!(progress.bytes_read(), 27);
+    assert_eq!(progress.lines_read(), 6);
+    assert_eq!(progress.values_parsed(), 4);
+    assert_eq!(progress.errors(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_parse_many() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let mut names = ["first.jsonl", "empty.jsonl", "third.jsonl"];
+    if cfg!(feature = "file-gz") {
+        names[2] = "third.jsonl.gz";
+    }
+    let paths: Vec<_> = names.iter().map(|name| tmpdir.path().join(name)).collect();
+    misc_utils::fs::write(&paths[0], "1\n2\n3\n")?;
+    misc_utils::fs::write(&paths[1], "")?;
+    misc_utils::fs::write(&paths[2], "4\n5\n")?;
+
+    let events =
+        parse_jsonl_multi_threaded_many::<_, u64>(&paths, 2).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        events,
+        vec![
+            JsonlEvent::Value(1),
+            JsonlEvent::Value(2),
+            JsonlEvent::Value(3),
+            JsonlEvent::FileCompleted(paths[0].clone()),
+            JsonlEvent::FileCompleted(paths[1].clone()),
+            JsonlEvent::Value(4),
+            JsonlEvent::Value(5),
+            JsonlEvent::FileCompleted(paths[2].clone()),
+        ]
+    );
+
+    // A missing file stops the iteration after the previous files
+    let paths = vec![paths[0].clone(), tmpdir.path().join("does-not-exist.jsonl")];
+    let mut iter = parse_jsonl_multi_threaded_many::<_, u64>(&paths, 2);
+    assert_eq!(iter.by_ref().take(4).count(), 4);
+    assert!(matches!(
+        iter.next(),
+        Some(Err(MtJsonlError::IoError { .. }))
+    ));
+    assert!(matches!(iter.next(), Some(Err(MtJsonlError::NotCompleted))));
+    Ok(())
+}
+
+#[test]
+fn test_finish_stats() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    std::fs::write(tmpfile.path(), "1\n2\nbroken\n3\nalso broken\n4\n")?;
+
+    let mut iter = MtJsonlBuilder::new(tmpfile.path().to_path_buf())
+        .batch_size(2)
+        .threads(2)
+        .skip_errors(|_, _| {})
+        .parse::<u64>();
+    let values = iter.by_ref().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, vec![1, 2, 3, 4]);
+    let stats = iter.finish_stats();
+    assert_eq!(stats.bytes_read, 27);
+    assert_eq!(stats.lines_read, 6);
+    assert_eq!(stats.values_parsed, 4);
+    assert_eq!(stats.errors, 2);
+    assert!(stats.read_time <= stats.total_time);
+    assert!(stats.parse_time <= stats.total_time);
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_writer() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let path = tmpdir.path().join("values.jsonl");
+    let mut writer = JsonlWriter::create(&path)?;
+    writer.batch_bytes(10);
+    for i in 0..100_u64 {
+        writer.write(&i)?;
+    }
+    writer.write_batch(["a", "b"])?;
+    writer.finish()?;
+    let expected: String = (0..100)
+        .map(|i| format!("{i}\n"))
+        .chain(["\"a\"\n".to_string(), "\"b\"\n".to_string()])
+        .collect();
+    assert_eq!(std::fs::read_to_string(&path)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_writer_failed_value() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let path = tmpdir.path().join("values.jsonl");
+    let mut writer = JsonlWriter::create(&path)?;
+    writer.write(&1)?;
+    // Map keys must be strings, so serialization fails after writing `[{`
+    let invalid = vec![std::collections::BTreeMap::from([((1, 2), 3)])];
+    assert!(matches!(
+        writer.write(&invalid),
+        Err(Error::JsonError { .. })
+    ));
+    writer.write(&2)?;
+    writer.finish()?;
+    assert_eq!(std::fs::read_to_string(&path)?, "1\n2\n");
+    Ok(())
+}
+
+#[cfg(feature = "file-gz")]
+#[test]
+fn test_jsonl_writer_gzip_members() -> Result<(), anyhow::Error> {
+    use misc_utils::fs::Compression;
+
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let write = |name: &str, batches: &[(u64, u64)]| -> Result<_, anyhow::Error> {
+        let path = tmpdir.path().join(name);
+        let mut writer = JsonlWriter::create_gzip_members(&path, Compression::Default)?;
+        for &(start, end) in batches {
+            writer.write_batch(start..end)?;
+        }
+        writer.finish()?;
+        Ok(path)
+    };
+    let first = write("first.jsonl.gz", &[(0, 500)])?;
+    let second = write("second.jsonl.gz", &[(500, 1000)])?;
+    let both = write("both.jsonl", &[(0, 500), (500, 1000)])?;
+
+    // Every batch is an independent gzip member
+    let mut concatenated = std::fs::read(first)?;
+    concatenated.extend(std::fs::read(second)?);
+    assert_eq!(std::fs::read(&both)?, concatenated);
+
+    let values = parse_jsonl_multi_threaded::<_, u64>(&both, 100).collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values, (0..1000).collect::<Vec<_>>());
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_appender() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let path = tmpdir.path().join("events.jsonl");
+    // Long lines make interleaved writes likely without the lock
+    let line = "x".repeat(10_000);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4_u64)
+            .map(|thread| {
+                let (path, line) = (&path, &line);
+                scope.spawn(move || -> Result<(), misc_utils::error::Error> {
+                    // Separate file handles behave like separate processes
+                    let mut appender = JsonlAppender::open(path)?;
+                    for i in 0..50 {
+                        appender.append_batch([(thread, i, line), (thread, i, line)])?;
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().unwrap())
+    })?;
+
+    let mut values = parse_jsonl_multi_threaded::<_, (u64, u64, String)>(&path, 10)
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(values.len(), 400);
+    // Both records of a batch are adjacent
+    for pair in values.chunks(2) {
+        assert_eq!(pair[0], pair[1]);
+    }
+    values.sort();
+    values.dedup();
+    assert_eq!(values.len(), 200);
+    Ok(())
+}
+
+#[test]
+fn test_split_jsonl() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let content: String = (0..1000).map(|i| format!("{i}\n")).collect();
+    let mut names = vec!["values.jsonl"];
+    if cfg!(feature = "file-gz") {
+        names.push("values.jsonl.gz");
+    }
+    for name in names {
+        let src = tmpdir.path().join(name);
+        misc_utils::fs::write(&src, &content)?;
+        let prefix = tmpdir.path().join("shard-");
+
+        let shards = split_jsonl(&src, &prefix, ShardSize::Records(300))?;
+        let expected_name = |i: usize| format!("shard-{i:05}{}", &name[6..]);
+        assert_eq!(
+            shards,
+            (0..4)
+                .map(|i| tmpdir.path().join(expected_name(i)))
+                .collect::<Vec<_>>()
+        );
+        let mut values = Vec::new();
+        for shard in &shards {
+            let shard_values =
+                parse_jsonl_multi_threaded::<_, u64>(shard, 100).collect::<Result<Vec<_>, _>>()?;
+            assert!(shard_values.len() <= 300);
+            values.extend(shard_values);
+        }
+        assert_eq!(values, (0..1000).collect::<Vec<_>>());
+
+        // 0-9 need two bytes per line, 10-99 three bytes, and 100-999 four bytes
+        let shards = split_jsonl(&src, &prefix, ShardSize::Bytes(1000))?;
+        let sizes = shards
+            .iter()
+            .map(|shard| Ok(misc_utils::fs::read(shard)?.len()))
+            .collect::<Result<Vec<_>, anyhow::Error>>()?;
+        assert!(sizes.iter().all(|&size| size <= 1000));
+        assert_eq!(sizes.iter().sum::<usize>(), content.len());
+        assert_eq!(sizes.len(), content.len().div_ceil(1000));
+    }
+
+    // Every shard contains at least one line
+    let src = tmpdir.path().join("small.jsonl");
+    misc_utils::fs::write(&src, "1\n2\n3\n")?;
+    let shards = split_jsonl(&src, tmpdir.path().join("small-"), ShardSize::Records(0))?;
+    assert_eq!(shards.len(), 3);
+    Ok(())
+}
+
+/// The extensions of non-UTF-8 file names must be kept for the shards
+#[cfg(unix)]
+#[test]
+fn test_split_jsonl_non_utf8() -> Result<(), anyhow::Error> {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let src = tmpdir.path().join(OsStr::from_bytes(b"values-\xff.jsonl"));
+    misc_utils::fs::write(&src, "1\n2\n3\n")?;
+    let prefix = tmpdir.path().join("shard-");
+
+    let shards = split_jsonl(&src, &prefix, ShardSize::Records(2))?;
+    assert_eq!(
+        shards,
+        [
+            tmpdir.path().join("shard-00000.jsonl"),
+            tmpdir.path().join("shard-00001.jsonl"),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_merge_jsonl_sorted() -> Result<(), anyhow::Error> {
+    #[derive(Deserialize)]
+    struct Event {
+        time: u64,
+    }
+
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let second = if cfg!(feature = "file-gz") {
+        "second.jsonl.gz"
+    } else {
+        "second.jsonl"
+    };
+    let paths = [
+        tmpdir.path().join("first.jsonl"),
+        tmpdir.path().join(second),
+        tmpdir.path().join("third.jsonl"),
+    ];
+    misc_utils::fs::write(
+        &paths[0],
+        "{\"time\": 1, \"file\": 0}\n\n{\"time\": 4, \"file\": 0}\n",
+    )?;
+    misc_utils::fs::write(
+        &paths[1],
+        "{\"time\": 2, \"file\": 1}\n{\"time\": 4, \"file\": 1}\n{\"time\": 9, \"file\": 1}",
+    )?;
+    misc_utils::fs::write(&paths[2], "")?;
+
+    let dst = tmpdir.path().join("merged.jsonl");
+    let records = merge_jsonl_sorted(&paths, |event: &Event| event.time, &dst)?;
+    assert_eq!(records, 5);
+    assert_eq!(
+        std::fs::read_to_string(&dst)?,
+        "{\"time\": 1, \"file\": 0}\n\
+         {\"time\": 2, \"file\": 1}\n\
+         {\"time\": 4, \"file\": 0}\n\
+         {\"time\": 4, \"file\": 1}\n\
+         {\"time\": 9, \"file\": 1}\n"
+    );
+
+    misc_utils::fs::write(&paths[2], "{\"time\": \"broken\"}\n")?;
+    let err = merge_jsonl_sorted(&paths, |event: &Event| event.time, &dst).unwrap_err();
+    assert!(matches!(err, Error::JsonError { file, .. } if file == paths[2]));
+    Ok(())
+}
+
+#[test]
+fn test_sample_jsonl() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    let content: String = (0..10_000).map(|i| format!("{i}\n\n")).collect();
+    std::fs::write(tmpfile.path(), content)?;
+
+    let sample = sample_jsonl::<_, u64>(tmpfile.path(), 100, 42)?;
+    assert_eq!(sample.len(), 100);
+    assert!(sample.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(sample, sample_jsonl::<_, u64>(tmpfile.path(), 100, 42)?);
+    assert_ne!(sample, sample_jsonl::<_, u64>(tmpfile.path(), 100, 43)?);
+
+    let all = sample_jsonl::<_, u64>(tmpfile.path(), 20_000, 42)?;
+    assert_eq!(all, (0..10_000).collect::<Vec<_>>());
+
+    // Every record is sampled with the same probability
+    std::fs::write(tmpfile.path(), "0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n")?;
+    let mut counts = [0; 10];
+    for seed in 0..2000 {
+        for value in sample_jsonl::<_, usize>(tmpfile.path(), 2, seed)? {
+            counts[value] += 1;
+        }
+    }
+    assert!(
+        counts.iter().all(|&count| (300..500).contains(&count)),
+        "{counts:?}"
+    );
+
+    std::fs::write(tmpfile.path(), "1\nbroken\n")?;
+    let err = sample_jsonl::<_, u64>(tmpfile.path(), 10, 0).unwrap_err();
+    assert!(matches!(
+        err,
+        MtJsonlError::ParsingError {
+            line: 2,
+            offset: 2,
+            ..
+        }
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_validate() -> Result<(), anyhow::Error> {
+    let tmpfile = tempfile::Builder::new().suffix(".jsonl").tempfile()?;
+    std::fs::write(tmpfile.path(), "1\n2\n-3\n4\n-5\n")?;
+
+    let mut builder = MtJsonlBuilder::new(tmpfile.path().to_path_buf());
+    let validated = builder.batch_size(2).threads(2).validate(|value: &i64| {
+        if *value < 0 {
+            Err("negative")
+        } else {
+            Ok(())
+        }
+    });
+    let mut iter = validated.parse();
+    let progress = iter.progress();
+    assert_eq!(iter.next().unwrap()?, 1);
+    assert_eq!(iter.next().unwrap()?, 2);
+    match iter.next() {
+        Some(Err(MtJsonlError::ValidationError {
+            line: 3,
+            offset: 4,
+            source,
+        })) => assert_eq!(source.to_string(), "negative"),
+        other => panic!("Expected a validation error, got {other:?}"),
+    }
+    assert_eq!(iter.next().unwrap()?, 4);
+    assert!(matches!(
+        iter.next(),
+        Some(Err(MtJsonlError::ValidationError { line: 5, .. }))
+    ));
+    assert!(iter.next().is_none());
+    assert_eq!(progress.values_parsed(), 3);
+    assert_eq!(progress.errors(), 2);
+
+    // The validator also applies when parsing the lines separately
+    let values: Vec<_> = builder
+        .skip_errors(|_, _| {})
+        .validate(|value: &i64| if *value < 0 { Err("negative") } else { Ok(()) })
+        .parse_raw()
+        .map(|(raw, value)| (raw, value.is_ok()))
+        .collect();
+    assert_eq!(values[2], ("-3".to_string(), false));
+    assert_eq!(values.iter().filter(|(_, ok)| *ok).count(), 3);
+
+    // The builder itself does not validate
+    assert_eq!(builder.parse::<i64>().filter(Result::is_ok).count(), 5);
+    Ok(())
+}
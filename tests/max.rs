@@ -1,4 +1,4 @@
-use misc_utils::Max;
+use misc_utils::{AtomicMax, Max, MaxByKey, MinMaxIteratorExt, Timestamped};
 
 #[test]
 fn test_max_usize() {
@@ -65,3 +65,182 @@ fn test_max_u8_from() {
     assert_eq!(Max::with_initial(3u8), vec![1, 2, 3].into_iter().collect());
     assert_eq!(Max::<u8>::default(), vec![].into_iter().collect());
 }
+
+#[test]
+fn test_max_by_key() {
+    let mut m = MaxByKey::new();
+    assert_eq!(None, m.get_max());
+
+    m.update(5, "first");
+    assert_eq!(Some((&5, &"first")), m.get_max());
+    m.update(3, "smaller");
+    assert_eq!(Some((&5, &"first")), m.get_max());
+    m.update(5, "equal");
+    assert_eq!(Some((&5, &"first")), m.get_max());
+    m.update(10, "larger");
+    assert_eq!(Some((10, "larger")), m.into_inner());
+
+    let m: MaxByKey<_, _> = ["b", "c", "a", "c"]
+        .into_iter()
+        .enumerate()
+        .map(|(idx, s)| (s, idx))
+        .collect();
+    assert_eq!(Some(("c", 1)), m.into_inner());
+    assert_eq!(
+        MaxByKey::<u8, String>::default(),
+        Vec::new().into_iter().collect()
+    );
+}
+
+#[test]
+fn test_max_extend() {
+    let mut m = Max::new();
+    m.extend(Vec::<i32>::new());
+    assert_eq!(None, m.get_max());
+    m.extend(vec![5, 3, 8]);
+    assert_eq!(Some(8), m.get_max());
+    m.extend([4, 7]);
+    assert_eq!(Some(8), m.get_max());
+
+    m.extend([Max::default(), Max::with_initial(7), Max::with_initial(11)]);
+    assert_eq!(Some(11), m.get_max());
+}
+
+#[test]
+fn test_max_merge() {
+    let a = Max::with_initial(4u32);
+    let b = Max::with_initial(2u32);
+    assert_eq!(Some(4), (a + b).get_max());
+    assert_eq!(Some(2), (Max::default() + b).get_max());
+
+    let mut c = Max::default();
+    c += b;
+    assert_eq!(Some(2), c.get_max());
+    c += a;
+    assert_eq!(Some(4), c.get_max());
+
+    let shards = [vec![7, 9, 8], vec![], vec![6, 12]];
+    let total: Max<u32> = shards
+        .iter()
+        .map(|shard| shard.iter().copied().collect::<Max<_>>())
+        .sum();
+    assert_eq!(Some(12), total.get_max());
+    let trackers = [a, b, Max::default()];
+    assert_eq!(Some(4), trackers.iter().sum::<Max<_>>().get_max());
+    assert_eq!(
+        None,
+        Vec::<Max<u32>>::new().into_iter().sum::<Max<_>>().get_max()
+    );
+}
+
+#[test]
+fn test_atomic_max() {
+    let m = AtomicMax::<i64>::default();
+    assert_eq!(None, m.get_max());
+    assert_eq!(i64::MIN, m.get_max_extreme());
+    assert_eq!(Max::default(), m.load());
+
+    std::thread::scope(|s| {
+        for t in 0..4i64 {
+            let m = &m;
+            s.spawn(move || {
+                for i in 0..1000 {
+                    m.update(-(i * 4 + t) - 10);
+                }
+            });
+        }
+    });
+    assert_eq!(Some(-10), m.get_max());
+    assert_eq!(-10, m.get_max_extreme());
+    assert_eq!(Max::with_initial(-10), m.load());
+
+    let m = AtomicMax::from(5u64);
+    m.update(3);
+    assert_eq!(Some(5), m.get_max());
+    m.update(u64::MAX);
+    assert_eq!(Some(u64::MAX), m.get_max());
+    assert_eq!(
+        "AtomicMax { value: Some(18446744073709551615) }",
+        format!("{m:?}")
+    );
+}
+
+#[test]
+fn test_max_by_key_closure() {
+    let words = ["b", "ccc", "aa", "ddd"];
+
+    let mut m = MaxByKey::new();
+    for word in words {
+        m.update_by_key(word, |w| w.len());
+    }
+    assert_eq!(Some((3, "ccc")), m.into_inner());
+
+    let m = words
+        .iter()
+        .enumerate()
+        .collect_max_by_key(|(_, w)| w.len());
+    assert_eq!(Some((3, (1, &"ccc"))), m.into_inner());
+    let m = Vec::<u8>::new().into_iter().collect_max_by_key(|&x| x);
+    assert_eq!(None, m.into_inner());
+}
+
+#[test]
+fn test_max_take_reset() {
+    let mut m = Max::new();
+    assert_eq!(None, m.take());
+
+    m.extend([5, 3, 8]);
+    assert_eq!(Some(8), m.take());
+    assert_eq!(None, m.get_max());
+    m.update(2);
+    assert_eq!(Some(2), m.take());
+
+    m.update(10);
+    m.reset();
+    assert_eq!(Max::default(), m);
+    m.update(4);
+    assert_eq!(Some(4), m.get_max());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_max_rayon() {
+    use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+    let m: Max<u32> = (100..10_000u32).into_par_iter().collect();
+    assert_eq!(Some(9_999), m.get_max());
+    let m: Max<u32> = Vec::new().into_par_iter().collect();
+    assert_eq!(None, m.get_max());
+
+    let mut m = Max::with_initial(500);
+    m.par_extend(0..200);
+    assert_eq!(Some(500), m.get_max());
+    m.par_extend(vec![7, 3000, 9]);
+    assert_eq!(Some(3000), m.get_max());
+}
+
+#[test]
+fn test_max_timestamped() {
+    let mut m = Timestamped::<Max<i32>, &str>::new();
+    assert_eq!(None, m.get_max());
+    assert_eq!(None, m.observed_at());
+
+    m.update_at(-50, "a");
+    m.update_at(-70, "b");
+    assert_eq!(Some(-50), m.get_max());
+    assert_eq!(Some(&"a"), m.observed_at());
+    m.update_at(20, "c");
+    m.update_at(20, "d");
+    assert_eq!(Some(20), m.get_max());
+    assert_eq!(Some(&"c"), m.observed_at());
+    assert_eq!((Max::with_initial(20), Some("c")), m.into_inner());
+
+    let mut m = Timestamped::<Max<i32>>::default();
+    let before = std::time::Instant::now();
+    m.update(5);
+    let first = *m.observed_at().unwrap();
+    assert!(first >= before);
+    m.update(4);
+    assert_eq!(Some(&first), m.observed_at());
+    assert_eq!(&Max::with_initial(5), m.inner());
+}
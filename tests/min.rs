@@ -1,4 +1,4 @@
-use misc_utils::Min;
+use misc_utils::{AtomicMin, Min, MinByKey, MinMaxIteratorExt, Timestamped};
 
 #[test]
 fn test_min_usize() {
@@ -65,3 +65,198 @@ fn test_min_u8_from() {
     assert_eq!(Min::with_initial(1u8), vec![1, 2, 3].into_iter().collect());
     assert_eq!(Min::<u8>::default(), vec![].into_iter().collect());
 }
+
+#[test]
+fn test_min_by_key() {
+    let mut m = MinByKey::new();
+    assert_eq!(None, m.get_min());
+
+    m.update(5, "first");
+    assert_eq!(Some((&5, &"first")), m.get_min());
+    m.update(7, "larger");
+    assert_eq!(Some((&5, &"first")), m.get_min());
+    m.update(5, "equal");
+    assert_eq!(Some((&5, &"first")), m.get_min());
+    m.update(-1, "smaller");
+    assert_eq!(Some((-1, "smaller")), m.into_inner());
+
+    let m: MinByKey<_, _> = ["b", "a", "c", "a"]
+        .into_iter()
+        .enumerate()
+        .map(|(idx, s)| (s, idx))
+        .collect();
+    assert_eq!(Some(("a", 1)), m.into_inner());
+    assert_eq!(
+        MinByKey::<u8, String>::default(),
+        Vec::new().into_iter().collect()
+    );
+}
+
+#[test]
+fn test_min_extend() {
+    let mut m = Min::new();
+    m.extend(Vec::<i32>::new());
+    assert_eq!(None, m.get_min());
+    m.extend(vec![5, 3, 8]);
+    assert_eq!(Some(3), m.get_min());
+    m.extend([4, 9]);
+    assert_eq!(Some(3), m.get_min());
+
+    m.extend([Min::default(), Min::with_initial(7), Min::with_initial(-1)]);
+    assert_eq!(Some(-1), m.get_min());
+}
+
+#[test]
+fn test_min_merge() {
+    let a = Min::with_initial(4u32);
+    let b = Min::with_initial(2u32);
+    assert_eq!(Some(2), (a + b).get_min());
+    assert_eq!(Some(4), (a + Min::default()).get_min());
+
+    let mut c = Min::default();
+    c += a;
+    assert_eq!(Some(4), c.get_min());
+    c += b;
+    assert_eq!(Some(2), c.get_min());
+
+    let shards = [vec![7, 9, 8], vec![], vec![6, 12]];
+    let total: Min<u32> = shards
+        .iter()
+        .map(|shard| shard.iter().copied().collect::<Min<_>>())
+        .sum();
+    assert_eq!(Some(6), total.get_min());
+    let trackers = [a, b, Min::default()];
+    assert_eq!(Some(2), trackers.iter().sum::<Min<_>>().get_min());
+    assert_eq!(
+        None,
+        Vec::<Min<u32>>::new().into_iter().sum::<Min<_>>().get_min()
+    );
+}
+
+#[test]
+fn test_atomic_min() {
+    let m = AtomicMin::<u64>::new();
+    assert_eq!(None, m.get_min());
+    assert_eq!(u64::MAX, m.get_min_extreme());
+    assert_eq!(Min::default(), m.load());
+
+    std::thread::scope(|s| {
+        for t in 0..4u64 {
+            let m = &m;
+            s.spawn(move || {
+                for i in (0..1000).rev() {
+                    m.update(i * 4 + t + 10);
+                }
+            });
+        }
+    });
+    assert_eq!(Some(10), m.get_min());
+    assert_eq!(10, m.get_min_extreme());
+    assert_eq!(Min::with_initial(10), m.load());
+
+    let m = AtomicMin::from(-5i64);
+    m.update(3);
+    assert_eq!(Some(-5), m.get_min());
+    m.update(i64::MIN);
+    assert_eq!(Some(i64::MIN), m.get_min());
+    assert_eq!(
+        "AtomicMin { value: Some(-9223372036854775808) }",
+        format!("{m:?}")
+    );
+}
+
+#[test]
+fn test_min_by_key_closure() {
+    #[derive(Debug, PartialEq)]
+    struct Record {
+        name: &'static str,
+        latency: u32,
+    }
+
+    let records = vec![
+        Record {
+            name: "a",
+            latency: 30,
+        },
+        Record {
+            name: "b",
+            latency: 10,
+        },
+        Record {
+            name: "c",
+            latency: 10,
+        },
+    ];
+
+    let mut m = MinByKey::new();
+    for record in &records {
+        m.update_by_key(record, |r| r.latency);
+    }
+    assert_eq!(Some((10, &records[1])), m.into_inner());
+
+    let m = records.into_iter().collect_min_by_key(|r| r.latency);
+    assert_eq!(Some((&10, &"b")), m.get_min().map(|(k, r)| (k, &r.name)));
+    let m = Vec::<u8>::new().into_iter().collect_min_by_key(|&x| x);
+    assert_eq!(None, m.into_inner());
+}
+
+#[test]
+fn test_min_take_reset() {
+    let mut m = Min::new();
+    assert_eq!(None, m.take());
+
+    m.extend([5, 3, 8]);
+    assert_eq!(Some(3), m.take());
+    assert_eq!(None, m.get_min());
+    m.update(9);
+    assert_eq!(Some(9), m.take());
+
+    m.update(1);
+    m.reset();
+    assert_eq!(Min::default(), m);
+    m.update(4);
+    assert_eq!(Some(4), m.get_min());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_min_rayon() {
+    use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+    let m: Min<u32> = (100..10_000u32).into_par_iter().collect();
+    assert_eq!(Some(100), m.get_min());
+    let m: Min<u32> = Vec::new().into_par_iter().collect();
+    assert_eq!(None, m.get_min());
+
+    let mut m = Min::with_initial(500);
+    m.par_extend(1_000..2_000);
+    assert_eq!(Some(500), m.get_min());
+    m.par_extend(vec![7, 3, 9]);
+    assert_eq!(Some(3), m.get_min());
+}
+
+#[test]
+fn test_min_timestamped() {
+    let mut m = Timestamped::<Min<u32>, u64>::new();
+    assert_eq!(None, m.get_min());
+    assert_eq!(None, m.observed_at());
+
+    m.update_at(50, 1);
+    m.update_at(70, 2);
+    assert_eq!(Some(50), m.get_min());
+    assert_eq!(Some(&1), m.observed_at());
+    m.update_at(20, 3);
+    m.update_at(20, 4);
+    assert_eq!(Some(20), m.get_min());
+    assert_eq!(Some(&3), m.observed_at());
+    assert_eq!((Min::with_initial(20), Some(3)), m.into_inner());
+
+    let mut m = Timestamped::<Min<u32>>::default();
+    let before = std::time::Instant::now();
+    m.update(5);
+    let first = *m.observed_at().unwrap();
+    assert!(first >= before);
+    m.update(6);
+    assert_eq!(Some(&first), m.observed_at());
+    assert_eq!(&Min::with_initial(5), m.inner());
+}
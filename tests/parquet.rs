@@ -0,0 +1,75 @@
+#![cfg(feature = "parquet")]
+
+use arrow_schema::{DataType, Field, Schema};
+use misc_utils::error::Error;
+use misc_utils::fs::jsonl_to_parquet;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::record::RowAccessor;
+use pretty_assertions::assert_eq;
+use std::{fs::File, sync::Arc};
+
+fn schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("name", DataType::Utf8, false),
+        Field::new("count", DataType::UInt64, true),
+    ]))
+}
+
+#[test]
+fn test_jsonl_to_parquet() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let content: String = (0..3000)
+        .map(|i| {
+            if i % 3 == 0 {
+                format!("{{\"name\": \"n{i}\"}}\n")
+            } else {
+                format!("{{\"name\": \"n{i}\", \"count\": {i}, \"ignored\": true}}\n")
+            }
+        })
+        .collect();
+    let mut names = vec!["data.jsonl"];
+    if cfg!(feature = "file-gz") {
+        names.push("data.jsonl.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        names.push("data.jsonl.xz");
+    }
+    for name in names {
+        let src = tmpdir.path().join(name);
+        let dst = tmpdir.path().join("data.parquet");
+        misc_utils::fs::write(&src, &content)?;
+
+        assert_eq!(jsonl_to_parquet(&src, &dst, schema())?, 3000);
+
+        let reader = SerializedFileReader::new(File::open(&dst)?)?;
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 3000);
+        let rows = reader
+            .get_row_iter(None)?
+            .map(|row| {
+                let row = row?;
+                let count = row.get_ulong(1).ok();
+                Ok((row.get_string(0)?.clone(), count))
+            })
+            .collect::<Result<Vec<_>, parquet::errors::ParquetError>>()?;
+        let expected: Vec<_> = (0..3000)
+            .map(|i| (format!("n{i}"), (i % 3 != 0).then_some(i)))
+            .collect();
+        assert_eq!(rows, expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_jsonl_to_parquet_invalid() -> Result<(), anyhow::Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let src = tmpdir.path().join("data.jsonl");
+    let dst = tmpdir.path().join("data.parquet");
+    std::fs::write(&src, "{\"name\": \"a\"}\n{\"count\": 1}\n")?;
+
+    let err = jsonl_to_parquet(&src, &dst, schema()).unwrap_err();
+    assert!(matches!(err, Error::ArrowError { file, .. } if file == src));
+
+    let err = jsonl_to_parquet(tmpdir.path().join("missing.jsonl"), &dst, schema()).unwrap_err();
+    assert!(matches!(err, Error::FileIo { .. }));
+    Ok(())
+}
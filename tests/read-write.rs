@@ -1,8 +1,8 @@
 use anyhow::Error;
-use misc_utils::byteascii::ByteAscii;
+use misc_utils::byteascii::{self, ByteAscii};
 #[cfg(any(feature = "file-gz", feature = "file-xz", feature = "file-bz2"))]
 use misc_utils::fs::Compression;
-use misc_utils::fs::{self, file_open_read, file_write};
+use misc_utils::fs::{self, file_open_read, file_read, file_write};
 use pretty_assertions::assert_eq;
 use std::{fs::File, io::prelude::*, path::Path};
 use tempfile::Builder;
@@ -24,7 +24,11 @@ fn assert_file_eq(expected_file: &Path, actual_file: &Path) -> Result<(), Error>
     let mut actual_file = File::open(actual_file)?;
     expected_file.read_to_end(&mut expected_content)?;
     actual_file.read_to_end(&mut actual_content)?;
-    assert_eq!(ByteAscii(expected_content), ByteAscii(actual_content));
+    assert!(
+        expected_content == actual_content,
+        "{}",
+        byteascii::diff(&expected_content, &actual_content)
+    );
     Ok(())
 }
 
@@ -41,7 +45,7 @@ fn do_read_test(expected: &str, actual_file: &Path) -> Result<(), Error> {
 fn do_write_test(
     expected_file: &Path,
     actual_file: &Path,
-    mut writer: Box<dyn Write>,
+    mut writer: impl Write,
 ) -> Result<(), Error> {
     writer.write_all(LOREM_IPSUM.as_bytes())?;
     // flush all data
@@ -275,3 +279,1011 @@ fn test_read_dev_null() -> Result<(), Error> {
     fs::read_to_string("/dev/null")?;
     Ok(())
 }
+
+#[cfg_attr(not(all(feature = "file-gz", feature = "file-xz")), ignore)]
+#[test]
+fn test_copy_recompress() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".xz").tempfile()?;
+
+    let copied = fs::copy("./tests/data/lorem.txt.gz", tmpfile.path())?;
+    assert_eq!(copied, LOREM_IPSUM.len() as u64);
+    assert_file_eq(
+        Path::new("./tests/data/lorem.txt.default.xz"),
+        tmpfile.path(),
+    )
+}
+
+#[test]
+fn test_recompress_preserve_metadata() -> Result<(), Error> {
+    use std::{
+        fs::FileTimes,
+        time::{Duration, SystemTime},
+    };
+
+    let src = Builder::new().suffix(".txt").tempfile()?;
+    let dst = Builder::new().suffix(".txt").tempfile()?;
+    fs::write(src.path(), LOREM_IPSUM)?;
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    src.as_file()
+        .set_times(FileTimes::new().set_modified(mtime))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(src.path(), std::fs::Permissions::from_mode(0o640))?;
+    }
+
+    fs::recompress(
+        src.path(),
+        dst.path(),
+        fs::RecompressOptions::new().preserve_metadata(true),
+    )?;
+    do_read_test(LOREM_IPSUM, dst.path())?;
+    let metadata = std::fs::metadata(dst.path())?;
+    assert_eq!(metadata.modified()?, mtime);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o640);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_copy_plaintext() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".txt").tempfile()?;
+    // Ensure the destination is truncated
+    fs::write(tmpfile.path(), "Long Text\n".repeat(200))?;
+
+    let copied = fs::copy("./tests/data/lorem.txt", tmpfile.path())?;
+    assert_eq!(copied, LOREM_IPSUM.len() as u64);
+    assert_file_eq(Path::new("./tests/data/lorem.txt"), tmpfile.path())
+}
+
+#[cfg_attr(not(feature = "file-gz"), ignore)]
+#[test]
+fn test_write_vectored_gz() -> Result<(), Error> {
+    use std::io::IoSlice;
+
+    let tmpfile = Builder::new().suffix(".gz").tempfile()?;
+    let mut writer = file_write(tmpfile.path()).truncate()?;
+    // The encoder receives the slices one by one
+    assert!(!writer.is_write_vectored());
+    let lines: Vec<&str> = LOREM_IPSUM.split_inclusive('\n').collect();
+    let slices: Vec<IoSlice<'_>> = lines.iter().map(|l| IoSlice::new(l.as_bytes())).collect();
+    // All slices are accepted in a single call
+    assert_eq!(writer.write_vectored(&slices)?, LOREM_IPSUM.len());
+    drop(writer);
+
+    do_read_test(LOREM_IPSUM, tmpfile.path())
+}
+
+#[test]
+fn test_writer_statistics() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".txt").tempfile()?;
+    let mut writer = file_write(tmpfile.path()).truncate()?;
+    assert!(writer.is_write_vectored());
+    assert_eq!(writer.bytes_written(), 0);
+    assert_eq!(writer.filetype(), fs::FileType::PlainText);
+    assert_eq!(writer.path(), tmpfile.path());
+
+    writer.write_all(LOREM_IPSUM.as_bytes())?;
+    assert_eq!(writer.bytes_written(), LOREM_IPSUM.len() as u64);
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "file-xz"), ignore)]
+#[test]
+fn test_read_strict_format_mismatch() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".gz").tempfile()?;
+    std::fs::copy("./tests/data/lorem.txt.xz", tmpfile.path())?;
+
+    // Without strict mode the content is detected from the magic bytes
+    do_read_test(LOREM_IPSUM, tmpfile.path())?;
+    match file_read(tmpfile.path()).strict(true).open() {
+        Err(misc_utils::error::Error::FormatMismatch {
+            expected, detected, ..
+        }) => {
+            assert_eq!(expected, "gz");
+            assert_eq!(detected, "xz");
+        }
+        Err(err) => panic!("Unexpected error: {}", err),
+        Ok(_) => panic!("Opening the file in strict mode must fail"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_read_strict_plaintext() -> Result<(), Error> {
+    let mut reader = file_read("./tests/data/lorem.txt").strict(true).open()?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    assert_eq!(LOREM_IPSUM, content);
+    Ok(())
+}
+
+#[test]
+fn test_read_detected_filetype() -> Result<(), Error> {
+    let reader = file_read("./tests/data/lorem.txt").open()?;
+    assert_eq!(reader.filetype(), fs::FileType::PlainText);
+
+    #[cfg(feature = "file-gz")]
+    {
+        let mut reader = file_read("./tests/data/lorem.txt.gz").open()?;
+        assert_eq!(reader.filetype(), fs::FileType::Gz);
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        assert_eq!(LOREM_IPSUM, content);
+    }
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "file-xz"), ignore)]
+#[test]
+fn test_read_prefetch() -> Result<(), Error> {
+    let mut reader = file_read("./tests/data/lorem.txt.xz")
+        // Use a small buffer to force many buffers
+        .buffer_capacity(16)
+        .prefetch(2)
+        .open()?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    assert_eq!(LOREM_IPSUM, content);
+    Ok(())
+}
+
+#[test]
+fn test_read_prefetch_empty_file() -> Result<(), Error> {
+    let mut reader = file_read("./tests/data/empty.txt").prefetch(1).open()?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    assert_eq!("", content);
+    Ok(())
+}
+
+#[test]
+fn test_write_read_many_buffers() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".txt").tempfile()?;
+    let content: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+
+    let mut writer = file_write(tmpfile.path())
+        .buffer_capacity(4096)
+        .truncate()?;
+    writer.write_all(&content)?;
+    writer.flush()?;
+    drop(writer);
+
+    let mut reader = file_read(tmpfile.path()).buffer_capacity(4096).open()?;
+    let mut actual = Vec::new();
+    reader.read_to_end(&mut actual)?;
+    assert_eq!(ByteAscii(content), ByteAscii(actual));
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_write_anonymous_until_finish() -> Result<(), Error> {
+    let tmpdir = Builder::new().tempdir()?;
+    let path = tmpdir.path().join("anonymous.txt");
+
+    let mut writer = file_write(&path).anonymous_until_finish().truncate()?;
+    writer.write_all(LOREM_IPSUM.as_bytes())?;
+    // The file has no name before `finish`
+    assert!(!path.exists());
+    writer.finish()?;
+    do_read_test(LOREM_IPSUM, &path)?;
+
+    // Existing files are replaced
+    let mut writer = file_write(&path).anonymous_until_finish().truncate()?;
+    writer.write_all(b"Hello World")?;
+    writer.finish()?;
+    do_read_test("Hello World", &path)?;
+    // No temporary files remain
+    assert_eq!(std::fs::read_dir(tmpdir.path())?.count(), 1);
+
+    // Existing files are kept with `create_new`
+    let mut writer = file_write(&path)
+        .create_new(true)
+        .anonymous_until_finish()
+        .truncate()?;
+    writer.write_all(b"Something else")?;
+    assert!(writer.finish().is_err());
+    do_read_test("Hello World", &path)
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_write_anonymous_dropped() -> Result<(), Error> {
+    let tmpdir = Builder::new().tempdir()?;
+    let path = tmpdir.path().join("anonymous.txt");
+
+    let mut writer = file_write(&path).anonymous_until_finish().truncate()?;
+    writer.write_all(LOREM_IPSUM.as_bytes())?;
+    drop(writer);
+    assert_eq!(std::fs::read_dir(tmpdir.path())?.count(), 0);
+    Ok(())
+}
+
+#[test]
+fn test_write_preallocate() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".txt").tempfile()?;
+
+    let mut writer = file_write(tmpfile.path()).preallocate(1 << 20).truncate()?;
+    writer.write_all(b"Hello")?;
+    drop(writer);
+    // Reserving space does not change the file size
+    assert_eq!(std::fs::metadata(tmpfile.path())?.len(), 5);
+
+    let mut writer = file_write(tmpfile.path()).preallocate(1 << 20).append()?;
+    writer.write_all(b" World")?;
+    drop(writer);
+    do_read_test("Hello World", tmpfile.path())
+}
+
+#[cfg(feature = "bytes")]
+#[test]
+fn test_read_bytes() -> Result<(), Error> {
+    let content = fs::read_bytes("./tests/data/lorem.txt.gz")?;
+    assert_eq!(content, LOREM_IPSUM.as_bytes());
+    // Slices share the same buffer
+    let slice = content.slice(..11);
+    assert_eq!(slice, &LOREM_IPSUM.as_bytes()[..11]);
+    Ok(())
+}
+
+#[cfg(feature = "camino")]
+#[test]
+fn test_utf8_paths() -> Result<(), Error> {
+    use camino::{Utf8Path, Utf8PathBuf};
+    use misc_utils::path::{Utf8PathBufExt, Utf8PathExt};
+
+    let tmpdir = Builder::new().tempdir()?;
+    let mut path = Utf8PathBuf::from_path_buf(tmpdir.path().join("file.txt")).unwrap();
+    Utf8PathBufExt::add_extension(&mut path, "gz");
+    assert_eq!(path.extensions().collect::<Vec<_>>(), vec!["gz", "txt"]);
+
+    fs::copy(Utf8Path::new("./tests/data/lorem.txt"), &path)?;
+    do_read_test(LOREM_IPSUM, path.as_std_path())
+}
+
+#[test]
+fn test_move_file() -> Result<(), Error> {
+    let tmpdir = Builder::new().tempdir()?;
+    let src = tmpdir.path().join("src.txt");
+    let dst = tmpdir.path().join("dst.txt");
+    fs::write(&src, LOREM_IPSUM)?;
+
+    fs::move_file(&src, &dst)?;
+    assert!(!src.exists());
+    do_read_test(LOREM_IPSUM, &dst)?;
+
+    // Moving a missing file fails
+    assert!(fs::move_file(&src, &dst).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "file-gz")]
+#[test]
+fn test_move_recompress() -> Result<(), Error> {
+    let tmpdir = Builder::new().tempdir()?;
+    let src = tmpdir.path().join("src.txt");
+    let dst = tmpdir.path().join("dst.txt.gz");
+    fs::write(&src, LOREM_IPSUM)?;
+
+    fs::move_recompress(&src, &dst, &fs::RecompressOptions::new())?;
+    assert!(!src.exists());
+    assert_eq!(file_read(&dst).open()?.filetype(), fs::FileType::Gz);
+    do_read_test(LOREM_IPSUM, &dst)
+}
+
+/// A failure while finishing the compressed stream must keep the source file
+#[cfg(all(feature = "file-gz", target_os = "linux"))]
+#[test]
+fn test_move_recompress_failed_finish_keeps_src() -> Result<(), Error> {
+    let tmpdir = Builder::new().tempdir()?;
+    let src = tmpdir.path().join("src.txt");
+    fs::write(&src, LOREM_IPSUM)?;
+
+    let mut options = fs::RecompressOptions::new();
+    options.filetype(fs::FileType::Gz);
+    assert!(fs::move_recompress(&src, "/dev/full", &options).is_err());
+    do_read_test(LOREM_IPSUM, &src)
+}
+
+#[test]
+fn test_mirror() -> Result<(), Error> {
+    let src = Builder::new().tempdir()?;
+    let dst = Builder::new().tempdir()?;
+    std::fs::create_dir(src.path().join("sub"))?;
+    fs::write(src.path().join("a.txt"), "a")?;
+    fs::write(src.path().join("sub/b.txt"), "b")?;
+    fs::write(dst.path().join("extra.txt"), "extra")?;
+
+    let stats = fs::mirror(src.path(), dst.path(), &fs::MirrorOptions::new())?;
+    assert_eq!((stats.copied, stats.unchanged, stats.deleted), (2, 0, 0));
+    do_read_test("a", &dst.path().join("a.txt"))?;
+    do_read_test("b", &dst.path().join("sub/b.txt"))?;
+
+    // Only changed files are copied again
+    fs::write(src.path().join("a.txt"), "aa")?;
+    let stats = fs::mirror(
+        src.path(),
+        dst.path(),
+        fs::MirrorOptions::new().compare(fs::MirrorCompare::Content),
+    )?;
+    assert_eq!((stats.copied, stats.unchanged, stats.deleted), (1, 1, 0));
+    do_read_test("aa", &dst.path().join("a.txt"))?;
+
+    let stats = fs::mirror(
+        src.path(),
+        dst.path(),
+        fs::MirrorOptions::new().delete(true),
+    )?;
+    assert_eq!((stats.copied, stats.unchanged, stats.deleted), (0, 2, 1));
+    assert!(!dst.path().join("extra.txt").exists());
+    Ok(())
+}
+
+/// Skipped entries must not cause the deletion of the same-named destination entries
+#[cfg(unix)]
+#[test]
+fn test_mirror_delete_keeps_skipped() -> Result<(), Error> {
+    let src = Builder::new().tempdir()?;
+    let dst = Builder::new().tempdir()?;
+    fs::write(src.path().join("a.txt"), "a")?;
+    std::os::unix::fs::symlink("a.txt", src.path().join("link"))?;
+    std::fs::create_dir(dst.path().join("link"))?;
+    fs::write(dst.path().join("link/b.txt"), "b")?;
+
+    let stats = fs::mirror(
+        src.path(),
+        dst.path(),
+        fs::MirrorOptions::new().delete(true),
+    )?;
+    assert_eq!((stats.copied, stats.unchanged, stats.deleted), (1, 0, 0));
+    do_read_test("b", &dst.path().join("link/b.txt"))
+}
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+#[derive(Debug, PartialEq, serde::Deserialize)]
+struct Config {
+    name: String,
+    values: Vec<u32>,
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_read_json() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".json").tempfile()?;
+    fs::write(tmpfile.path(), r#"{"name": "test", "values": [1, 2, 3]}"#)?;
+    let config: Config = fs::read_json(tmpfile.path())?;
+    assert_eq!(
+        config,
+        Config {
+            name: "test".into(),
+            values: vec![1, 2, 3],
+        }
+    );
+
+    fs::write(tmpfile.path(), r#"{"name": "test"}"#)?;
+    let err = fs::read_json::<_, Config>(tmpfile.path()).unwrap_err();
+    assert!(
+        matches!(err, misc_utils::error::Error::JsonError { file, .. } if file == tmpfile.path())
+    );
+    Ok(())
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_read_toml() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".toml").tempfile()?;
+    fs::write(tmpfile.path(), "name = \"test\"\nvalues = [1, 2, 3]\n")?;
+    let config: Config = fs::read_toml(tmpfile.path())?;
+    assert_eq!(
+        config,
+        Config {
+            name: "test".into(),
+            values: vec![1, 2, 3],
+        }
+    );
+    Ok(())
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_read_yaml() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".yaml").tempfile()?;
+    fs::write(tmpfile.path(), "name: test\nvalues: [1, 2, 3]\n")?;
+    let config: Config = fs::read_yaml(tmpfile.path())?;
+    assert_eq!(
+        config,
+        Config {
+            name: "test".into(),
+            values: vec![1, 2, 3],
+        }
+    );
+    Ok(())
+}
+
+#[cfg(all(feature = "json", feature = "file-xz"))]
+#[test]
+fn test_write_json() -> Result<(), Error> {
+    let tmpdir = Builder::new().tempdir()?;
+    let path = tmpdir.path().join("results.json.xz");
+    let value = serde_json::json!({"name": "test", "values": [1, 2, 3]});
+
+    fs::write_json(&path, &value)?;
+    assert_eq!(file_read(&path).open()?.filetype(), fs::FileType::Xz);
+    do_read_test(r#"{"name":"test","values":[1,2,3]}"#, &path)?;
+
+    fs::write_json_pretty(&path, &value)?;
+    let read: serde_json::Value = fs::read_json(&path)?;
+    assert_eq!(read, value);
+    assert!(fs::read_to_string(&path)?.contains("\n  \"name\": \"test\""));
+    Ok(())
+}
+
+#[cfg(feature = "file-xz")]
+#[test]
+fn test_read_xz_memlimit() -> Result<(), Error> {
+    let path = Path::new("./tests/data/lorem.txt.xz");
+
+    let mut content = String::new();
+    file_read(path)
+        .xz_memlimit(1 << 30)
+        .open()?
+        .read_to_string(&mut content)?;
+    assert_eq!(content, LOREM_IPSUM);
+
+    let err = file_read(path)
+        .xz_memlimit(1024)
+        .open()?
+        .read_to_string(&mut String::new())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::OutOfMemory);
+    let inner = err
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<misc_utils::error::Error>());
+    assert!(matches!(
+        inner,
+        Some(misc_utils::error::Error::XzMemLimitError { memlimit: 1024, .. })
+    ));
+    Ok(())
+}
+
+#[cfg(feature = "file-xz")]
+#[test]
+fn test_write_xz_threads_auto() -> Result<(), Error> {
+    let tmpfile = Builder::new().suffix(".xz").tempfile()?;
+    let mut writer = file_write(tmpfile.path())
+        .threads_auto()
+        .memory_budget(256 * 1024 * 1024)
+        .truncate()?;
+    writer.write_all(LOREM_IPSUM.as_bytes())?;
+    writer.finish()?;
+    do_read_test(LOREM_IPSUM, tmpfile.path())
+}
+
+#[test]
+fn test_idempotent_helpers() -> Result<(), Error> {
+    let tmpdir = Builder::new().tempdir()?;
+    let dir = tmpdir.path().join("a/b");
+    let file = dir.join("file.txt");
+
+    fs::create_dir_all_idempotent(&dir)?;
+    fs::create_dir_all_idempotent(&dir)?;
+    fs::write(&file, "content")?;
+    // A file is not a directory
+    assert!(fs::create_dir_all_idempotent(&file).is_err());
+
+    assert!(fs::remove_file_if_exists(&file)?);
+    assert!(!fs::remove_file_if_exists(&file)?);
+
+    fs::write(&file, "content")?;
+    std::fs::create_dir(dir.join("sub"))?;
+    fs::write(dir.join("sub/file.txt"), "content")?;
+    fs::ensure_empty_dir(&dir)?;
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 0);
+    fs::ensure_empty_dir(tmpdir.path().join("new"))?;
+    assert!(tmpdir.path().join("new").is_dir());
+    Ok(())
+}
+
+#[test]
+fn test_read_offset() -> Result<(), Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let mut names = vec!["offset.txt"];
+    if cfg!(feature = "file-gz") {
+        names.push("offset.txt.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        names.push("offset.txt.xz");
+    }
+    for name in names {
+        let path = tmpdir.path().join(name);
+        misc_utils::fs::write(&path, "Hello World")?;
+
+        let mut content = String::new();
+        misc_utils::fs::file_read(&path)
+            .offset(6)
+            .open()?
+            .read_to_string(&mut content)?;
+        assert_eq!(content, "World");
+
+        let mut content = String::new();
+        misc_utils::fs::file_read(&path)
+            .offset(100)
+            .open()?
+            .read_to_string(&mut content)?;
+        assert_eq!(content, "");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_count_lines() -> Result<(), Error> {
+    let tmpdir = tempfile::Builder::new().tempdir()?;
+    let mut names = vec!["lines.txt"];
+    if cfg!(feature = "file-gz") {
+        names.push("lines.txt.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        names.push("lines.txt.xz");
+    }
+    let content: String = (0..100_000).map(|i| format!("{i}\n")).collect();
+    for name in names {
+        let path = tmpdir.path().join(name);
+        misc_utils::fs::write(&path, &content)?;
+        assert_eq!(misc_utils::fs::count_lines(&path)?, 100_000);
+
+        misc_utils::fs::write(&path, "no\nterminator")?;
+        assert_eq!(misc_utils::fs::count_lines(&path)?, 2);
+
+        misc_utils::fs::write(&path, "")?;
+        assert_eq!(misc_utils::fs::count_lines(&path)?, 0);
+
+        // Invalid UTF-8 does not matter
+        misc_utils::fs::write(&path, b"\xff\n\xfe\n\n")?;
+        assert_eq!(misc_utils::fs::count_lines(&path)?, 3);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_async_file_open_read() -> Result<(), Error> {
+    use misc_utils::async_fs;
+    use tokio::io::AsyncReadExt;
+
+    let mut files = vec!["./tests/data/empty.txt", "./tests/data/lorem.txt"];
+    if cfg!(feature = "file-bz2") {
+        files.push("./tests/data/lorem.txt.bz2");
+    }
+    if cfg!(feature = "file-gz") {
+        files.push("./tests/data/lorem.txt.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        files.push("./tests/data/lorem.txt.xz");
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        for file in files {
+            let mut content = String::new();
+            async_fs::file_open_read(file)
+                .await?
+                .read_to_string(&mut content)
+                .await?;
+            let expected = if file.ends_with("empty.txt") {
+                ""
+            } else {
+                LOREM_IPSUM
+            };
+            assert_eq!(content, expected);
+        }
+
+        // Concatenated gzip members are read completely
+        if cfg!(feature = "file-gz") {
+            let tmpfile = Builder::new().suffix(".gz").tempfile()?;
+            let mut member = std::fs::read("./tests/data/lorem.txt.gz")?;
+            member.extend(member.clone());
+            std::fs::write(tmpfile.path(), member)?;
+            let mut content = String::new();
+            async_fs::file_open_read(tmpfile.path())
+                .await?
+                .read_to_string(&mut content)
+                .await?;
+            assert_eq!(content, LOREM_IPSUM.repeat(2));
+        }
+        Ok(())
+    })
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_async_file_write() -> Result<(), Error> {
+    use misc_utils::async_fs;
+    use tokio::io::AsyncWriteExt;
+
+    let tmpdir = Builder::new().tempdir()?;
+    let mut names = vec!["async.txt"];
+    if cfg!(feature = "file-bz2") {
+        names.push("async.txt.bz2");
+    }
+    if cfg!(feature = "file-gz") {
+        names.push("async.txt.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        names.push("async.txt.xz");
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        for name in names {
+            let path = tmpdir.path().join(name);
+            let mut writer = async_fs::file_write(&path).truncate().await?;
+            writer.write_all(LOREM_IPSUM.as_bytes()).await?;
+            assert_eq!(writer.bytes_written(), LOREM_IPSUM.len() as u64);
+            writer.finish().await?;
+            assert_eq!(fs::read_to_string(&path)?, LOREM_IPSUM);
+
+            // Truncate an existing file
+            let mut writer = async_fs::file_write(&path)
+                .buffer_capacity(16)
+                .truncate()
+                .await?;
+            writer.write_all(b"Hello").await?;
+            writer.finish().await?;
+            assert_eq!(fs::read_to_string(&path)?, "Hello");
+
+            let err = async_fs::file_write(&path)
+                .create_new(true)
+                .truncate()
+                .await
+                .unwrap_err();
+            assert!(matches!(err, misc_utils::error::Error::FileIo { .. }));
+        }
+
+        let path = tmpdir.path().join("async.txt");
+        let mut writer = async_fs::file_write(&path).append().await?;
+        writer.write_all(b" World").await?;
+        writer.finish().await?;
+        assert_eq!(fs::read_to_string(&path)?, "Hello World");
+        Ok(())
+    })
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_async_lines() -> Result<(), Error> {
+    use futures_core::Stream;
+    use misc_utils::async_fs;
+    use std::pin::Pin;
+
+    async fn collect(
+        mut stream: impl Stream<Item = Result<String, misc_utils::error::Error>> + Unpin,
+    ) -> Vec<Result<String, misc_utils::error::Error>> {
+        let mut items = Vec::new();
+        while let Some(item) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await
+        {
+            items.push(item);
+        }
+        items
+    }
+
+    let tmpdir = Builder::new().tempdir()?;
+    let mut names = vec!["lines.txt"];
+    if cfg!(feature = "file-gz") {
+        names.push("lines.txt.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        names.push("lines.txt.xz");
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        for name in names {
+            let path = tmpdir.path().join(name);
+            fs::write(&path, "first\r\nsecond\n\nlast")?;
+            let lines = collect(async_fs::lines(&path))
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(lines, vec!["first", "second", "", "last"]);
+        }
+
+        let items = collect(async_fs::lines(tmpdir.path().join("missing.txt"))).await;
+        assert_eq!(items.len(), 1);
+        assert!(matches!(
+            items[0],
+            Err(misc_utils::error::Error::FileIo { .. })
+        ));
+        Ok(())
+    })
+}
+
+#[cfg(feature = "futures-fs")]
+#[test]
+fn test_futures_fs() -> Result<(), Error> {
+    use misc_utils::futures_fs;
+
+    let tmpdir = Builder::new().tempdir()?;
+    let mut names = vec!["futures.txt"];
+    if cfg!(feature = "file-gz") {
+        names.push("futures.txt.gz");
+    }
+
+    futures_lite::future::block_on(async {
+        for name in names {
+            let path = tmpdir.path().join(name);
+            futures_fs::write(&path, "Hello").await?;
+            futures_fs::append(&path, " World").await?;
+            assert_eq!(futures_fs::read(&path).await?, b"Hello World");
+            assert_eq!(futures_fs::read_to_string(&path).await?, "Hello World");
+        }
+
+        let err = futures_fs::read(tmpdir.path().join("missing.txt"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, misc_utils::error::Error::FileIo { .. }));
+        Ok(())
+    })
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_async_with_runtime() -> Result<(), Error> {
+    use misc_utils::async_fs;
+
+    let tmpdir = Builder::new().tempdir()?;
+    let path = tmpdir.path().join("runtime.txt");
+
+    // The future is polled outside of any Tokio context, so the handle must be used
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let fs = async_fs::with_runtime(runtime.handle().clone());
+    futures_lite::future::block_on(async {
+        fs.write(&path, "Hello").await?;
+        fs.append(&path, " World").await?;
+        assert_eq!(fs.read(&path).await?, b"Hello World");
+        assert_eq!(fs.read_to_string(&path).await?, "Hello World");
+        Ok::<_, misc_utils::error::Error>(())
+    })?;
+    Ok(())
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_async_copy_recompress() -> Result<(), Error> {
+    use misc_utils::async_fs;
+    use std::time::{Duration, SystemTime};
+
+    let tmpdir = Builder::new().tempdir()?;
+    let src = tmpdir.path().join("lorem.txt");
+    fs::write(&src, LOREM_IPSUM)?;
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    std::fs::File::options()
+        .write(true)
+        .open(&src)?
+        .set_modified(mtime)?;
+
+    let mut names = vec!["copy.txt"];
+    if cfg!(feature = "file-gz") {
+        names.push("copy.txt.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        names.push("copy.txt.xz");
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        for name in names {
+            let dst = tmpdir.path().join(name);
+            let copied = async_fs::copy(&src, &dst).await?;
+            assert_eq!(copied, LOREM_IPSUM.len() as u64);
+            do_read_test(LOREM_IPSUM, &dst)?;
+
+            // Recompress back into plaintext, keeping the metadata
+            let back = tmpdir.path().join(format!("{name}.back"));
+            let copied = async_fs::recompress(
+                &dst,
+                &back,
+                fs::RecompressOptions::new()
+                    .filetype(fs::FileType::PlainText)
+                    .preserve_metadata(true),
+            )
+            .await?;
+            assert_eq!(copied, LOREM_IPSUM.len() as u64);
+            assert_eq!(std::fs::read(&back)?, LOREM_IPSUM.as_bytes());
+        }
+
+        let back = tmpdir.path().join("meta.txt");
+        async_fs::recompress(
+            &src,
+            &back,
+            fs::RecompressOptions::new().preserve_metadata(true),
+        )
+        .await?;
+        assert_eq!(std::fs::metadata(&back)?.modified()?, mtime);
+        Ok(())
+    })
+}
+
+#[cfg(all(feature = "async-fs", feature = "file-gz"))]
+#[test]
+fn test_async_read_cancel() -> Result<(), Error> {
+    use misc_utils::async_fs;
+    use std::time::{Duration, Instant};
+
+    // 1 GiB of zeros, which takes a long time to decompress
+    let tmpdir = Builder::new().tempdir()?;
+    let member = tmpdir.path().join("member.gz");
+    fs::write(&member, vec![0; 1024 * 1024])?;
+    let path = tmpdir.path().join("large.gz");
+    std::fs::write(&path, std::fs::read(&member)?.repeat(1024))?;
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    let polled = runtime.block_on(futures_lite::future::poll_once(async_fs::read(&path)));
+    assert!(polled.is_none());
+
+    // Dropping the runtime waits for the blocking task, which stops early after the future was dropped
+    let start = Instant::now();
+    drop(runtime);
+    assert!(start.elapsed() < Duration::from_millis(500));
+    Ok(())
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_async_decompress_reader() -> Result<(), Error> {
+    use misc_utils::async_fs;
+    use tokio::io::AsyncReadExt;
+
+    let mut sources = vec!["./tests/data/lorem.txt"];
+    if cfg!(feature = "file-gz") {
+        sources.push("./tests/data/lorem.txt.gz");
+    }
+    if cfg!(feature = "file-xz") {
+        sources.push("./tests/data/lorem.txt.xz");
+    }
+    if cfg!(feature = "file-bz2") {
+        sources.push("./tests/data/lorem.txt.bz2");
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        for source in sources {
+            // The in-memory content stands in for a network source
+            let content = std::io::Cursor::new(std::fs::read(source)?);
+            let mut reader = async_fs::decompress_reader(content).await?;
+            let mut buffer = String::new();
+            reader.read_to_string(&mut buffer).await?;
+            assert_eq!(buffer, LOREM_IPSUM, "Source: {source}");
+        }
+
+        // Content shorter than the magic bytes is plaintext
+        let mut reader = async_fs::decompress_reader(&b"ab"[..]).await?;
+        let mut buffer = String::new();
+        reader.read_to_string(&mut buffer).await?;
+        assert_eq!(buffer, "ab");
+        Ok(())
+    })
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_async_read_dir_sorted() -> Result<(), Error> {
+    use misc_utils::async_fs;
+
+    let tmpdir = Builder::new().tempdir()?;
+    for name in [
+        "file10.txt",
+        "file2.txt",
+        "file02.txt",
+        "b",
+        "a1b",
+        "file1.txt",
+        "a",
+    ] {
+        fs::write(tmpdir.path().join(name), "")?;
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        let names = |entries: Vec<async_fs::DirEntry>| {
+            entries
+                .iter()
+                .map(|entry| entry.file_name().into_string().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let sorted = async_fs::read_dir_sorted(tmpdir.path()).await?;
+        assert_eq!(
+            names(sorted),
+            [
+                "a",
+                "a1b",
+                "b",
+                "file02.txt",
+                "file1.txt",
+                "file10.txt",
+                "file2.txt"
+            ]
+        );
+
+        let sorted = async_fs::read_dir_sorted_natural(tmpdir.path()).await?;
+        assert_eq!(
+            names(sorted),
+            [
+                "a",
+                "a1b",
+                "b",
+                "file1.txt",
+                "file02.txt",
+                "file2.txt",
+                "file10.txt"
+            ]
+        );
+
+        let err = async_fs::read_dir_sorted(tmpdir.path().join("missing"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, misc_utils::error::Error::FileIo { .. }));
+        Ok(())
+    })
+}
+
+#[cfg(feature = "async-fs")]
+#[test]
+fn test_async_glob() -> Result<(), Error> {
+    use futures_lite::StreamExt;
+    use misc_utils::async_fs;
+
+    let tmpdir = Builder::new().tempdir()?;
+    std::fs::create_dir_all(tmpdir.path().join("sub/deeper"))?;
+    for name in ["a.jsonl", "b.txt", "sub/c.jsonl", "sub/deeper/d.jsonl"] {
+        fs::write(tmpdir.path().join(name), "")?;
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+    runtime.block_on(async {
+        let pattern = format!("{}/**/*.jsonl", tmpdir.path().display());
+        let paths = async_fs::glob(&pattern)
+            .try_collect::<_, _, Vec<_>>()
+            .await?;
+        assert_eq!(
+            paths,
+            [
+                tmpdir.path().join("a.jsonl"),
+                tmpdir.path().join("sub/c.jsonl"),
+                tmpdir.path().join("sub/deeper/d.jsonl"),
+            ]
+        );
+
+        let items = async_fs::glob("[").collect::<Vec<_>>().await;
+        assert_eq!(items.len(), 1);
+        assert!(matches!(
+            items[0],
+            Err(misc_utils::error::Error::GlobPatternError { .. })
+        ));
+        Ok(())
+    })
+}
+
+#[test]
+fn test_ensure_parent_dirs() -> Result<(), Error> {
+    let tmpdir = Builder::new().tempdir()?;
+    let path = tmpdir.path().join("out/new/dir/file.txt");
+    // Opening fails without the parent directories
+    assert!(fs::write(&path, LOREM_IPSUM).is_err());
+
+    fs::ensure_parent_dirs(&path)?;
+    fs::ensure_parent_dirs(&path)?;
+    assert!(tmpdir.path().join("out/new/dir").is_dir());
+    // A path without parent is fine
+    fs::ensure_parent_dirs("file.txt")?;
+
+    let path = tmpdir.path().join("other/dir/file.txt");
+    let mut writer = fs::file_write(&path).create_parents(true).truncate()?;
+    writer.write_all(LOREM_IPSUM.as_bytes())?;
+    writer.finish()?;
+    do_read_test(LOREM_IPSUM, &path)
+}
@@ -0,0 +1,169 @@
+use misc_utils::{Mean, Saturating, Stats, Sum};
+
+fn assert_close(expected: f64, actual: Option<f64>) {
+    let actual = actual.unwrap();
+    assert!(
+        (expected - actual).abs() < 1e-9,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn test_stats_empty() {
+    let s = Stats::<u8>::default();
+    assert_eq!(0, s.count());
+    assert_eq!(None, s.mean());
+    assert_eq!(None, s.variance());
+    assert_eq!(None, s.stddev());
+    assert_eq!(None, s.get_min());
+    assert_eq!(None, s.get_max());
+    assert_eq!("<uninitialized>", s.to_string());
+}
+
+#[test]
+fn test_stats_update() {
+    let mut s = Stats::new();
+    s.update(-3i64);
+    assert_eq!(1, s.count());
+    assert_eq!(Some(-3.0), s.mean());
+    assert_eq!(Some(0.0), s.variance());
+    assert_eq!(None, s.sample_variance());
+
+    s.update(7);
+    s.update(2);
+    assert_eq!(3, s.count());
+    assert_close(2.0, s.mean());
+    assert_close(50.0 / 3.0, s.variance());
+    assert_close(25.0, s.sample_variance());
+    assert_close(5.0, s.sample_stddev());
+    assert_eq!(Some(-3), s.get_min());
+    assert_eq!(Some(7), s.get_max());
+}
+
+#[test]
+fn test_stats_floats() {
+    // A large offset makes the naive sum of squares algorithm lose all precision
+    let s: Stats<f64> = [4.0, 7.0, 13.0, 16.0]
+        .into_iter()
+        .map(|x| x + 1e9)
+        .collect();
+    assert_close(1e9 + 10.0, s.mean());
+    assert_close(30.0, s.sample_variance());
+
+    let s: Stats<f32> = [1.0, f32::NAN, -2.0].into_iter().collect();
+    assert!(s.mean().unwrap().is_nan());
+    assert_eq!(Some(-2.0), s.get_min());
+    assert_eq!(Some(1.0), s.get_max());
+}
+
+#[test]
+fn test_stats_merge() {
+    let values = [1u32, 5, 2, 8, 3, 9, 4, 4, 7];
+    let all: Stats<_> = values.into_iter().collect();
+    for split in 0..=values.len() {
+        let mut left: Stats<_> = values[..split].iter().copied().collect();
+        let right: Stats<_> = values[split..].iter().copied().collect();
+        left.merge(&right);
+        assert_eq!(all.count(), left.count());
+        assert_close(all.mean().unwrap(), left.mean());
+        assert_close(all.variance().unwrap(), left.variance());
+        assert_eq!(all.get_min(), left.get_min());
+        assert_eq!(all.get_max(), left.get_max());
+    }
+}
+
+#[test]
+fn test_stats_display() {
+    let s: Stats<u16> = [1, 2, 3, 4].into_iter().collect();
+    assert_eq!(
+        "count=4 mean=2.500 stddev=1.118 min=1 max=4",
+        format!("{s:.3}")
+    );
+}
+
+#[test]
+fn test_stats_display_nan() {
+    let s: Stats<f64> = [f64::NAN].into_iter().collect();
+    assert_eq!(None, s.get_min());
+    assert_eq!("count=1 mean=NaN stddev=NaN", s.to_string());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_stats_rayon() {
+    use rayon::iter::{IntoParallelIterator, ParallelExtend, ParallelIterator};
+
+    let values: Vec<u64> = (0..100_000).map(|i| (i * 7919) % 1013).collect();
+    let sequential: Stats<u64> = values.iter().copied().collect();
+    let parallel: Stats<u64> = values.clone().into_par_iter().collect();
+    assert_eq!(sequential.count(), parallel.count());
+    assert_close(sequential.mean().unwrap(), parallel.mean());
+    assert_close(sequential.variance().unwrap(), parallel.variance());
+    assert_eq!(sequential.get_min(), parallel.get_min());
+    assert_eq!(sequential.get_max(), parallel.get_max());
+
+    let mut extended: Stats<u64> = values[..50_000].iter().copied().collect();
+    extended.par_extend(values[50_000..].to_vec());
+    assert_eq!(sequential.count(), extended.count());
+    assert_close(sequential.mean().unwrap(), extended.mean());
+    assert_close(sequential.variance().unwrap(), extended.variance());
+}
+
+#[test]
+fn test_sum_checked() {
+    let mut s = Sum::<i8>::new();
+    assert_eq!(Some(0), s.get_sum());
+    s.update(100);
+    s.update(-50);
+    assert_eq!(Some(50), s.get_sum());
+    assert_eq!("50", s.to_string());
+    s.update(Sum::with_initial(77));
+    assert_eq!(Some(127), s.get_sum());
+    assert!(!s.is_overflowed());
+
+    s.update(1);
+    assert_eq!(None, s.get_sum());
+    assert!(s.is_overflowed());
+    assert_eq!("<overflow>", s.to_string());
+    // An overflow is permanent, even if later values would bring the sum back into range
+    s.update(-100);
+    assert_eq!(None, s.get_sum());
+
+    let s: Sum<u64> = (1..=100).collect();
+    assert_eq!(Some(5050), s.get_sum());
+    assert_eq!(Sum::<u32>::from(5), Sum::with_initial(5));
+}
+
+#[test]
+fn test_sum_saturating() {
+    let mut s = Sum::<u8, Saturating>::from(250);
+    s.update(10);
+    assert_eq!(Some(255), s.get_sum());
+    assert!(!s.is_overflowed());
+
+    let s: Sum<i16, Saturating> = [i16::MIN, -1, 5].into_iter().collect();
+    assert_eq!(Some(i16::MIN + 5), s.get_sum());
+}
+
+#[test]
+fn test_mean() {
+    let m = Mean::new();
+    assert_eq!(None, m.get_mean());
+    assert_eq!("<uninitialized>", m.to_string());
+
+    let mut m = Mean::with_initial(1u8);
+    m.update(2i64);
+    m.update(6.0f32);
+    assert_eq!(3, m.count());
+    assert_eq!(Some(3.0), m.get_mean());
+    assert_eq!("3.00", format!("{m:.2}"));
+
+    let mut a: Mean = [1, 2, 3].into_iter().collect();
+    let b: Mean = [10, 20].into_iter().collect();
+    a.merge(&b);
+    assert_eq!(5, a.count());
+    assert_close(7.2, a.get_mean());
+    a.merge(&Mean::new());
+    assert_eq!(5, a.count());
+    assert_eq!(Some(2.5), Mean::from(2.5).get_mean());
+}